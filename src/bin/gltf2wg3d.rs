@@ -0,0 +1,37 @@
+extern crate clap;
+extern crate wg3d;
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::process::exit;
+
+use clap::{App, Arg};
+
+use wg3d::convert::{get, write_wg3d};
+
+fn main() {
+    let matches = App::new("gltf2wg3d")
+        .about("Converts a glTF model to the wg3d format")
+        .arg(Arg::with_name("INPUT")
+             .help(".gltf or .glb file to convert")
+             .required(true)
+             .index(1))
+        .arg(Arg::with_name("OUTPUT")
+             .help("wg3d file to write")
+             .required(true)
+             .index(2))
+        .get_matches();
+    let input = matches.value_of("INPUT").unwrap();
+    let output = matches.value_of("OUTPUT").unwrap();
+
+    let result = get(input).and_then(|conversion| {
+        let mut writer = BufWriter::new(File::create(output)?);
+        write_wg3d(conversion.models(), &mut writer)?;
+        Ok(writer.flush()?)
+    });
+
+    if let Err(err) = result {
+        eprintln!("Could not convert {}: {}", input, err);
+        exit(1);
+    }
+}
@@ -1,201 +1,1419 @@
+use std::cmp::Ordering;
+use std::f32;
+use std::mem::discriminant;
 use std::u16;
 
-use cgmath::{Vector3, Quaternion};
+use cgmath::{InnerSpace, Vector3, Quaternion};
 use gltf::gltf::Animations as GltfAnimations;
-use gltf::animation::{Animation as GltfAnimation, InterpolationAlgorithm, TrsProperty};
-use gltf_importer::Buffers;
+use gltf::animation::{Animation as GltfAnimation, Channel as GltfChannel, InterpolationAlgorithm, TrsProperty};
+use gltf::json::Value;
 
 use super::super::Result;
-use super::ConvertError;
+use super::{ConvertError, ConvertWarning};
+use super::buffer::Buffers;
+use super::options::{Handedness, Options};
+use super::quantize::{SmallestThree, dequantize, quantize};
 use super::skin::Skins;
 use super::util::ChannelIterators;
 
-pub struct Animations {
+/// All animations of a glTF file, in glTF order.
+pub struct AnimationSet {
     animations: Vec<Animation>,
+    camera_animations: Vec<CameraAnimation>,
 }
 
+impl AnimationSet {
+    pub fn animations(&self) -> &[Animation] {
+        &self.animations
+    }
+
+    pub fn into_animations(self) -> Vec<Animation> {
+        self.animations
+    }
+
+    /// Paths of animated camera nodes, in glTF order.
+    pub fn camera_animations(&self) -> &[CameraAnimation] {
+        &self.camera_animations
+    }
+
+    pub fn by_name<'a>(&'a self, name: &'a str) -> Option<&'a Animation> {
+        self.animations.iter().find(|animation| animation.name == name)
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.animations.iter().map(|animation| animation.name.as_ref()).collect()
+    }
+
+    /// Returns the sorted indices of every joint animated by any animation.
+    pub fn joints(&self) -> Vec<u16> {
+        let mut joints = self.animations.iter()
+            .flat_map(|animation| animation.channels.iter().map(Channel::joint_index))
+            .collect::<Vec<_>>();
+        joints.sort();
+        joints.dedup();
+        joints
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Animation {
     name: String,
+    duration: f32,
     channels: Vec<Channel>,
+    /// Distinct time arrays of the channels, which refer to them by index.
+    times: Vec<Vec<f32>>,
+    morph_channels: Vec<MorphChannel>,
+}
+
+impl Animation {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Time stamp of the last keyframe, in seconds.
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+
+    pub fn channels(&self) -> &[Channel] {
+        &self.channels
+    }
+
+    /// Every distinct time array of the channels, stored once however many
+    /// channels share it.
+    pub fn times(&self) -> &[Vec<f32>] {
+        &self.times
+    }
+
+    /// Index into `times` of the time array of channel `channel`.
+    pub fn time_index(&self, channel: usize) -> Option<usize> {
+        self.channels.get(channel).map(Channel::time_index)
+    }
+
+    /// Channels driving the morph target weights of mesh nodes.
+    pub fn morph_channels(&self) -> &[MorphChannel] {
+        &self.morph_channels
+    }
+
+    /// Returns the sorted indices of the joints this animation targets.
+    pub fn joints(&self) -> Vec<u16> {
+        let mut joints = self.channels.iter().map(Channel::joint_index).collect::<Vec<_>>();
+        joints.sort();
+        joints.dedup();
+        joints
+    }
+
+    /// Returns the animation restricted to the channels driving the skin at
+    /// `skin_index`, if there are any. The duration is kept, so the clip
+    /// stays in step with the rest of the animation. Morph channels are
+    /// left out.
+    pub fn for_skin(&self, skin_index: usize) -> Option<Animation> {
+        let mut times = Vec::new();
+        let channels = self.channels.iter()
+            .filter(|channel| channel.skin_index() == skin_index)
+            .map(|channel| {
+                let mut channel = channel.clone();
+                let time_index = pool_times(&mut times, &self.times[channel.time_index()]);
+                channel.set_time_index(time_index);
+                channel
+            })
+            .collect::<Vec<_>>();
+        if channels.is_empty() {
+            return None;
+        }
+
+        Some(Animation {
+            name: self.name.clone(),
+            duration: self.duration,
+            channels: channels,
+            times: times,
+            morph_channels: Vec::new(),
+        })
+    }
+
+    /// Expresses the animation as deltas from `reference`, for additive
+    /// layering. Each keyframe has the matching channel of `reference`,
+    /// sampled at its time, subtracted: rotations by the quaternion
+    /// difference, everything else component-wise. Channels `reference` does
+    /// not animate have the joint's rest pose subtracted instead. Spline
+    /// tangents, and morph channels, are kept as they are.
+    pub fn to_additive(&self, reference: &Animation) -> Animation {
+        let channels = self.channels.iter().map(|channel| {
+            let reference_channel = reference.channels.iter().find(|other| {
+                other.skin_index() == channel.skin_index() &&
+                    other.joint_index() == channel.joint_index() &&
+                    discriminant(*other) == discriminant(channel)
+            });
+            let reference_channel = reference_channel.map(|reference_channel| {
+                (reference_channel, reference.times[reference_channel.time_index()].as_slice())
+            });
+            channel.subtract(&self.times[channel.time_index()], reference_channel)
+        }).collect::<Vec<_>>();
+
+        Animation {
+            name: self.name.clone(),
+            duration: self.duration,
+            channels: channels,
+            times: self.times.clone(),
+            morph_channels: self.morph_channels.clone(),
+        }
+    }
+}
+
+/// Keyframe values of a channel in fixed point. Translations, scales and
+/// weights are mapped from the channel's range onto `i16`, per component.
+#[derive(Clone, Serialize, Deserialize)]
+pub enum QuantizedChannel {
+    Vector3 {
+        min: [f32; 3],
+        max: [f32; 3],
+        values: Vec<[i16; 3]>,
+    },
+    Rotation {
+        values: Vec<SmallestThree>,
+    },
+    Scalar {
+        min: f32,
+        max: f32,
+        values: Vec<i16>,
+    },
+}
+
+impl QuantizedChannel {
+    fn from_vectors<I: Iterator<Item = Vector3<f32>> + Clone>(vectors: I) -> QuantizedChannel {
+        let (min, max) = vectors.clone().fold(
+            ([f32::MAX; 3], [f32::MIN; 3]),
+            |(mut min, mut max), vector| {
+                for axis in 0..3 {
+                    min[axis] = min[axis].min(vector[axis]);
+                    max[axis] = max[axis].max(vector[axis]);
+                }
+                (min, max)
+            },
+        );
+        let values = vectors.map(|vector| {
+            [
+                quantize(vector.x, min[0], max[0]),
+                quantize(vector.y, min[1], max[1]),
+                quantize(vector.z, min[2], max[2]),
+            ]
+        }).collect();
+
+        QuantizedChannel::Vector3 {
+            min: min,
+            max: max,
+            values: values,
+        }
+    }
+
+    /// Decoded translations or scales.
+    pub fn vectors(&self) -> Vec<Vector3<f32>> {
+        match self {
+            &QuantizedChannel::Vector3 { min, max, ref values } => {
+                values.iter().map(|value| Vector3::new(
+                    dequantize(value[0], min[0], max[0]),
+                    dequantize(value[1], min[1], max[1]),
+                    dequantize(value[2], min[2], max[2]),
+                )).collect()
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    /// Decoded rotations.
+    pub fn rotations(&self) -> Vec<Quaternion<f32>> {
+        match self {
+            &QuantizedChannel::Rotation { ref values } => {
+                values.iter().map(SmallestThree::to_quaternion).collect()
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    /// Decoded weights.
+    pub fn scalars(&self) -> Vec<f32> {
+        match self {
+            &QuantizedChannel::Scalar { min, max, ref values } => {
+                values.iter().map(|&value| dequantize(value, min, max)).collect()
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            &QuantizedChannel::Vector3 { ref values, .. } => values.len(),
+            &QuantizedChannel::Rotation { ref values } => values.len(),
+            &QuantizedChannel::Scalar { ref values, .. } => values.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Keyframe values of a channel. With `Options::quantize_keyframes` they are
+/// kept in fixed point only.
+#[derive(Clone, Serialize, Deserialize)]
+pub enum Keyframes<T> {
+    Float(Vec<T>),
+    Quantized(QuantizedChannel),
+}
+
+impl<T> Keyframes<T> {
+    pub fn len(&self) -> usize {
+        match self {
+            &Keyframes::Float(ref values) => values.len(),
+            &Keyframes::Quantized(ref quantized) => quantized.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_quantized(&self) -> bool {
+        match self {
+            &Keyframes::Float(_) => false,
+            &Keyframes::Quantized(_) => true,
+        }
+    }
+}
+
+impl Keyframes<Vector3<f32>> {
+    /// The values, decoded if they are quantized.
+    pub fn values(&self) -> Vec<Vector3<f32>> {
+        match self {
+            &Keyframes::Float(ref values) => values.clone(),
+            &Keyframes::Quantized(ref quantized) => quantized.vectors(),
+        }
+    }
+
+    fn quantize(&mut self) {
+        let quantized = match self {
+            &mut Keyframes::Float(ref values) => QuantizedChannel::from_vectors(values.iter().cloned()),
+            &mut Keyframes::Quantized(_) => return,
+        };
+        *self = Keyframes::Quantized(quantized);
+    }
+}
+
+impl Keyframes<Quaternion<f32>> {
+    /// The values, decoded if they are quantized.
+    pub fn values(&self) -> Vec<Quaternion<f32>> {
+        match self {
+            &Keyframes::Float(ref values) => values.clone(),
+            &Keyframes::Quantized(ref quantized) => quantized.rotations(),
+        }
+    }
+
+    fn quantize(&mut self) {
+        let quantized = match self {
+            &mut Keyframes::Float(ref values) => QuantizedChannel::Rotation {
+                values: values.iter().map(|&quaternion| SmallestThree::from_quaternion(quaternion)).collect(),
+            },
+            &mut Keyframes::Quantized(_) => return,
+        };
+        *self = Keyframes::Quantized(quantized);
+    }
+}
+
+impl Keyframes<f32> {
+    /// The values, decoded if they are quantized.
+    pub fn values(&self) -> Vec<f32> {
+        match self {
+            &Keyframes::Float(ref values) => values.clone(),
+            &Keyframes::Quantized(ref quantized) => quantized.scalars(),
+        }
+    }
+
+    fn quantize(&mut self) {
+        let quantized = match self {
+            &mut Keyframes::Float(ref values) => {
+                let (min, max) = values.iter().fold((f32::MAX, f32::MIN), |(min, max), &scalar| {
+                    (min.min(scalar), max.max(scalar))
+                });
+
+                QuantizedChannel::Scalar {
+                    min: min,
+                    max: max,
+                    values: values.iter().map(|&scalar| quantize(scalar, min, max)).collect(),
+                }
+            },
+            &mut Keyframes::Quantized(_) => return,
+        };
+        *self = Keyframes::Quantized(quantized);
+    }
+}
+
+/// Keyframed transforms of a camera node, from one animation.
+pub struct CameraAnimation {
+    animation: String,
+    node_index: usize,
+    translations: Vec<Vector3Data>,
+    rotations: Vec<QuaternionData>,
+}
+
+impl CameraAnimation {
+    /// Name of the animation the path belongs to.
+    pub fn animation(&self) -> &str {
+        &self.animation
+    }
+
+    /// Index of the camera node in the glTF nodes.
+    pub fn node_index(&self) -> usize {
+        self.node_index
+    }
+
+    pub fn keyframe_count(&self) -> usize {
+        self.translations.len() + self.rotations.len()
+    }
+}
+
+/// Keyframed weights of the morph targets of a mesh node, from one
+/// animation.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MorphChannel {
+    node_index: usize,
+    target_count: usize,
+    target_names: Vec<String>,
+    interpolation: Interpolation,
+    weights: Vec<MorphWeightsData>,
+}
+
+impl MorphChannel {
+    /// Index of the mesh node in the glTF nodes.
+    pub fn node_index(&self) -> usize {
+        self.node_index
+    }
+
+    /// Number of morph targets of the node's mesh, which is the number of
+    /// weights in each keyframe.
+    pub fn target_count(&self) -> usize {
+        self.target_count
+    }
+
+    /// Names of the morph targets, from the `targetNames` array in the
+    /// mesh's extras. Empty when the mesh does not name them.
+    pub fn target_names(&self) -> &[String] {
+        &self.target_names
+    }
+
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    pub fn keyframe_count(&self) -> usize {
+        self.weights.len()
+    }
+
+    pub fn weights(&self) -> &[MorphWeightsData] {
+        &self.weights
+    }
+
+    fn times(&self) -> Vec<f32> {
+        self.weights.iter().map(|data| data.time_stamp).collect()
+    }
+}
+
+/// Weights of every morph target at one time. Cubic spline keyframes hold
+/// the in-tangents, values and out-tangents, `target_count` weights each.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MorphWeightsData {
+    time_stamp: f32,
+    weights: Vec<f32>,
+}
+
+impl MorphWeightsData {
+    pub fn time_stamp(&self) -> f32 {
+        self.time_stamp
+    }
+
+    pub fn weights(&self) -> &[f32] {
+        &self.weights
+    }
 }
 
 pub fn get<'a>(
     animations: GltfAnimations,
     skins: &'a Skins,
     buffers: &'a Buffers,
-) -> Result<Animations> {
+    options: &'a Options,
+    warnings: &'a mut Vec<ConvertWarning>,
+) -> Result<AnimationSet> {
+    let mut camera_animations = Vec::<CameraAnimation>::new();
     let my_animations = animations.map(|animation| {
         let name = animation.name().ok_or(ConvertError::NoName)?;
-        let channels = get_channels(&animation, skins, buffers)?;
-        
+        let mut times = Vec::new();
+        let mut channels = get_channels(&animation, name, skins, buffers, options, &mut times, warnings)?;
+        let mut cameras = get_camera_animations(&animation, name, skins, buffers, warnings)?;
+        let mut morph_channels = get_morph_channels(&animation, buffers)?;
+
+        // Shift the whole clip so its first keyframe is at zero.
+        if options.rebase_time {
+            let camera_times = cameras.iter().flat_map(|camera| {
+                camera.translations.iter().map(|data| data.time_stamp)
+                    .chain(camera.rotations.iter().map(|data| data.time_stamp))
+            });
+            let morph_times = morph_channels.iter().flat_map(MorphChannel::times);
+            let start = times.iter()
+                .flat_map(|pooled| pooled.iter().cloned())
+                .chain(camera_times)
+                .chain(morph_times)
+                .fold(None, |min: Option<f32>, time| Some(min.map_or(time, |min| min.min(time))));
+            if let Some(start) = start {
+                for pooled in times.iter_mut() {
+                    for time in pooled.iter_mut() { *time -= start; }
+                }
+                for camera in cameras.iter_mut() {
+                    for data in camera.translations.iter_mut() { data.time_stamp -= start; }
+                    for data in camera.rotations.iter_mut() { data.time_stamp -= start; }
+                }
+                for morph_channel in morph_channels.iter_mut() {
+                    for data in morph_channel.weights.iter_mut() { data.time_stamp -= start; }
+                }
+            }
+        }
+        let duration = times.iter()
+            .flat_map(|pooled| pooled.iter().cloned())
+            .chain(morph_channels.iter().flat_map(MorphChannel::times))
+            .fold(0.0, f32::max);
+        if options.handedness == Handedness::Left {
+            for channel in channels.iter_mut() {
+                channel.mirror_z();
+            }
+            for camera in cameras.iter_mut() {
+                for data in camera.translations.iter_mut() { data.vector = mirror_vector_z(data.vector); }
+                for data in camera.rotations.iter_mut() { data.quaternion = mirror_quaternion_z(data.quaternion); }
+            }
+        }
+        if options.quantize_keyframes {
+            for channel in channels.iter_mut() {
+                channel.quantize();
+            }
+        }
+        camera_animations.extend(cameras);
+
         Ok(Animation {
             name: String::from(name),
+            duration: duration,
             channels: channels,
+            times: times,
+            morph_channels: morph_channels,
         })
 
     }).collect::<Result<Vec<_>>>()?;
 
-    Ok(Animations {
+    Ok(AnimationSet {
         animations: my_animations,
+        camera_animations: camera_animations,
     })
 }
 
+/// Returns the index of `times` in `pool`, adding it if no equal array is
+/// there yet.
+fn pool_times(pool: &mut Vec<Vec<f32>>, times: &[f32]) -> usize {
+    match pool.iter().position(|pooled| pooled.as_slice() == times) {
+        Some(index) => index,
+        None => {
+            pool.push(times.to_vec());
+            pool.len() - 1
+        },
+    }
+}
+
+/// Whether a channel moves a camera node rather than a joint.
+fn targets_camera<'a>(
+    channel: &'a GltfChannel,
+    skins: &'a Skins,
+) -> bool {
+    let target = channel.target();
+    let node = target.node();
+    node.camera().is_some() && skins.get_joint_index(node.index()).is_none()
+}
+
+/// Whether a channel drives the morph target weights of a mesh node rather
+/// than a joint.
+fn targets_morph<'a>(channel: &'a GltfChannel) -> bool {
+    let target = channel.target();
+    match target.path() {
+        TrsProperty::Weights => target.node().mesh().is_some(),
+        _ => false,
+    }
+}
+
+/// Collects the weights channels of mesh nodes, split into one run of
+/// weights per keyframe.
+fn get_morph_channels<'a>(
+    animation: &'a GltfAnimation,
+    buffers: &'a Buffers,
+) -> Result<Vec<MorphChannel>> {
+    animation.channels().filter(targets_morph).map(|channel| {
+        let target = channel.target();
+        let node = target.node();
+        let mesh = node.mesh().unwrap();
+        let target_count = mesh.primitives().next()
+            .map_or(0, |primitive| primitive.morph_targets().count());
+        let target_names = mesh.extras().as_ref()
+            .and_then(|extras| extras.get("targetNames"))
+            .and_then(Value::as_array)
+            .map_or(Vec::new(), |names| {
+                names.iter().filter_map(Value::as_str).map(String::from).collect()
+            });
+        let (interpolation, values_per_keyframe) = match channel.sampler().interpolation() {
+            InterpolationAlgorithm::CatmullRomSpline => (Interpolation::CatmullRom, target_count),
+            InterpolationAlgorithm::CubicSpline => (Interpolation::Cubic, 3 * target_count),
+            InterpolationAlgorithm::Linear => (Interpolation::Linear, target_count),
+            InterpolationAlgorithm::Step => (Interpolation::Step, target_count),
+        };
+
+        let values = channel.weights_f32(buffers)?.unwrap().collect::<Vec<_>>();
+        let weights = match values_per_keyframe {
+            0 => Vec::new(),
+            _ => channel.times(buffers)?.zip(values.chunks(values_per_keyframe)).map(|(time_stamp, weights)| {
+                MorphWeightsData {
+                    time_stamp: time_stamp,
+                    weights: weights.to_vec(),
+                }
+            }).collect(),
+        };
+
+        Ok(MorphChannel {
+            node_index: node.index(),
+            target_count: target_count,
+            target_names: target_names,
+            interpolation: interpolation,
+            weights: weights,
+        })
+    }).collect()
+}
+
+/// Collects the translation and rotation channels of every camera node the
+/// animation moves. Scale and weights do not apply to cameras. Camera paths
+/// are sampled linearly, so channels with any other interpolation are left
+/// out with a warning.
+fn get_camera_animations<'a>(
+    animation: &'a GltfAnimation,
+    name: &'a str,
+    skins: &'a Skins,
+    buffers: &'a Buffers,
+    warnings: &'a mut Vec<ConvertWarning>,
+) -> Result<Vec<CameraAnimation>> {
+    let mut cameras = Vec::<CameraAnimation>::new();
+
+    for channel in animation.channels().filter(|channel| targets_camera(channel, skins)) {
+        let node_index = channel.target().node().index();
+        match channel.sampler().interpolation() {
+            InterpolationAlgorithm::Linear => {},
+            _ => {
+                warnings.push(ConvertWarning::NonLinearCameraChannel {
+                    animation: String::from(name),
+                    node_index: node_index,
+                });
+                continue;
+            },
+        }
+        let position = match cameras.iter().position(|camera| camera.node_index == node_index) {
+            Some(position) => position,
+            None => {
+                cameras.push(CameraAnimation {
+                    animation: String::from(name),
+                    node_index: node_index,
+                    translations: Vec::new(),
+                    rotations: Vec::new(),
+                });
+                cameras.len() - 1
+            },
+        };
+        let times = channel.times(buffers)?;
+
+        match channel.target().path() {
+            TrsProperty::Translation => {
+                cameras[position].translations = times.zip(channel.translations(
+                    buffers
+                )?.unwrap()).map(|(time_stamp, vector)| {
+                    Vector3Data {
+                        time_stamp: time_stamp,
+                        vector: Vector3::from(vector),
+                    }
+                }).collect();
+            },
+            TrsProperty::Rotation => {
+                cameras[position].rotations = times.zip(channel.rotations_f32(
+                    buffers
+                )?.unwrap()).map(|(time_stamp, quaternion)| {
+                    QuaternionData {
+                        time_stamp: time_stamp,
+                        quaternion: quaternion_from_xyzw(quaternion),
+                    }
+                }).collect();
+            },
+            _ => {},
+        }
+    }
+
+    Ok(cameras)
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub enum Channel {
     Translation {
+        skin_index: usize,
         joint_index: u16,
+        joint_name: String,
         interpolation: Interpolation,
-        translations: Vec<Vector3Data>,
+        time_index: usize,
+        translations: Keyframes<Vector3<f32>>,
+        rest: Vector3<f32>,
     },
     Rotation {
+        skin_index: usize,
         joint_index: u16,
+        joint_name: String,
         interpolation: Interpolation,
-        rotations: Vec<QuaternionData>,
+        time_index: usize,
+        rotations: Keyframes<Quaternion<f32>>,
+        rest: Quaternion<f32>,
     },
     Scale {
+        skin_index: usize,
         joint_index: u16,
+        joint_name: String,
         interpolation: Interpolation,
-        scales: Vec<Vector3Data>,
+        time_index: usize,
+        scales: Keyframes<Vector3<f32>>,
+        rest: Vector3<f32>,
     },
     Weights {
+        skin_index: usize,
         joint_index: u16,
+        joint_name: String,
         interpolation: Interpolation,
-        weights: Vec<ScalarData>,
+        time_index: usize,
+        weights: Keyframes<f32>,
     },
 }
 
+impl Channel {
+    /// Index of the skin the channel drives.
+    pub fn skin_index(&self) -> usize {
+        match self {
+            &Channel::Translation { skin_index, .. } |
+            &Channel::Rotation { skin_index, .. } |
+            &Channel::Scale { skin_index, .. } |
+            &Channel::Weights { skin_index, .. } => skin_index,
+        }
+    }
+
+    pub fn joint_index(&self) -> u16 {
+        match self {
+            &Channel::Translation { joint_index, .. } |
+            &Channel::Rotation { joint_index, .. } |
+            &Channel::Scale { joint_index, .. } |
+            &Channel::Weights { joint_index, .. } => joint_index,
+        }
+    }
+
+    pub fn joint_name(&self) -> &str {
+        match self {
+            &Channel::Translation { ref joint_name, .. } |
+            &Channel::Rotation { ref joint_name, .. } |
+            &Channel::Scale { ref joint_name, .. } |
+            &Channel::Weights { ref joint_name, .. } => joint_name,
+        }
+    }
+
+    pub fn interpolation(&self) -> Interpolation {
+        match self {
+            &Channel::Translation { interpolation, .. } |
+            &Channel::Rotation { interpolation, .. } |
+            &Channel::Scale { interpolation, .. } |
+            &Channel::Weights { interpolation, .. } => interpolation,
+        }
+    }
+
+    /// Index into `Animation::times` of the time stamps of the keyframes.
+    pub fn time_index(&self) -> usize {
+        match self {
+            &Channel::Translation { time_index, .. } |
+            &Channel::Rotation { time_index, .. } |
+            &Channel::Scale { time_index, .. } |
+            &Channel::Weights { time_index, .. } => time_index,
+        }
+    }
+
+    fn set_time_index(&mut self, index: usize) {
+        match self {
+            &mut Channel::Translation { ref mut time_index, .. } |
+            &mut Channel::Rotation { ref mut time_index, .. } |
+            &mut Channel::Scale { ref mut time_index, .. } |
+            &mut Channel::Weights { ref mut time_index, .. } => *time_index = index,
+        }
+    }
+
+    /// Stably sorts the keyframes by time, along with `times`. Returns
+    /// whether any moved. Keyframes are sorted as they are read, before any
+    /// quantization, so quantized channels are left alone.
+    fn sort_keyframes(&mut self, times: &mut Vec<f32>) -> bool {
+        match self {
+            &mut Channel::Translation { translations: Keyframes::Float(ref mut translations), .. } => {
+                sort_by_time(times, translations)
+            },
+            &mut Channel::Rotation { rotations: Keyframes::Float(ref mut rotations), .. } => {
+                sort_by_time(times, rotations)
+            },
+            &mut Channel::Scale { scales: Keyframes::Float(ref mut scales), .. } => {
+                sort_by_time(times, scales)
+            },
+            &mut Channel::Weights { weights: Keyframes::Float(ref mut weights), .. } => {
+                sort_by_time(times, weights)
+            },
+            _ => false,
+        }
+    }
+
+    fn is_quantized(&self) -> bool {
+        match self {
+            &Channel::Translation { ref translations, .. } => translations.is_quantized(),
+            &Channel::Rotation { ref rotations, .. } => rotations.is_quantized(),
+            &Channel::Scale { ref scales, .. } => scales.is_quantized(),
+            &Channel::Weights { ref weights, .. } => weights.is_quantized(),
+        }
+    }
+
+    /// Replaces the keyframe values with fixed point ones.
+    fn quantize(&mut self) {
+        match self {
+            &mut Channel::Translation { ref mut translations, .. } => translations.quantize(),
+            &mut Channel::Rotation { ref mut rotations, .. } => rotations.quantize(),
+            &mut Channel::Scale { ref mut scales, .. } => scales.quantize(),
+            &mut Channel::Weights { ref mut weights, .. } => weights.quantize(),
+        }
+    }
+
+    /// Replaces fixed point keyframe values with decoded ones.
+    fn dequantize(&mut self) {
+        match self {
+            &mut Channel::Translation { ref mut translations, .. } => {
+                *translations = Keyframes::Float(translations.values());
+            },
+            &mut Channel::Rotation { ref mut rotations, .. } => {
+                *rotations = Keyframes::Float(rotations.values());
+            },
+            &mut Channel::Scale { ref mut scales, .. } => {
+                *scales = Keyframes::Float(scales.values());
+            },
+            &mut Channel::Weights { ref mut weights, .. } => {
+                *weights = Keyframes::Float(weights.values());
+            },
+        }
+    }
+
+    /// Mirrors the values and rest pose along the Z axis for left handed
+    /// output. Tangents of spline channels mirror like the values. Channels
+    /// are mirrored as they are read, before any quantization.
+    fn mirror_z(&mut self) {
+        match self {
+            &mut Channel::Translation {
+                translations: Keyframes::Float(ref mut translations), ref mut rest, ..
+            } => {
+                for translation in translations.iter_mut().chain(Some(rest)) {
+                    *translation = mirror_vector_z(*translation);
+                }
+            },
+            &mut Channel::Rotation {
+                rotations: Keyframes::Float(ref mut rotations), ref mut rest, ..
+            } => {
+                for rotation in rotations.iter_mut().chain(Some(rest)) {
+                    *rotation = mirror_quaternion_z(*rotation);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    /// Subtracts `reference`, sampled at the time of every keyframe, or the
+    /// rest pose without one. `times` are the time stamps of the channel;
+    /// `reference` is paired with its own and must animate the same
+    /// property. Quantized channels are decoded for the subtraction and
+    /// quantized again.
+    fn subtract(&self, times: &[f32], reference: Option<(&Channel, &[f32])>) -> Channel {
+        let mut channel = self.clone();
+        channel.dequantize();
+        let (reference, reference_interpolation, reference_times) = match reference {
+            Some((reference, reference_times)) => (Some(reference), reference.interpolation(), reference_times),
+            None => (None, Interpolation::Linear, &[][..]),
+        };
+
+        match &mut channel {
+            &mut Channel::Translation {
+                interpolation, translations: Keyframes::Float(ref mut translations), rest, ..
+            } => {
+                let keyframes = match reference {
+                    Some(&Channel::Translation { translations: ref reference_translations, .. }) => {
+                        keyframe_values(reference_interpolation, reference_times, &reference_translations.values())
+                    },
+                    _ => Vec::new(),
+                };
+                subtract_values(interpolation, times, translations, |vector, time| {
+                    *vector -= sample(&keyframes, time, Vector3::lerp).unwrap_or(rest);
+                });
+            },
+            &mut Channel::Rotation {
+                interpolation, rotations: Keyframes::Float(ref mut rotations), rest, ..
+            } => {
+                let keyframes = match reference {
+                    Some(&Channel::Rotation { rotations: ref reference_rotations, .. }) => {
+                        keyframe_values(reference_interpolation, reference_times, &reference_rotations.values())
+                    },
+                    _ => Vec::new(),
+                };
+                subtract_values(interpolation, times, rotations, |quaternion, time| {
+                    let reference = sample(&keyframes, time, Quaternion::nlerp).unwrap_or(rest);
+                    *quaternion = reference.conjugate() * *quaternion;
+                });
+            },
+            &mut Channel::Scale {
+                interpolation, scales: Keyframes::Float(ref mut scales), rest, ..
+            } => {
+                let keyframes = match reference {
+                    Some(&Channel::Scale { scales: ref reference_scales, .. }) => {
+                        keyframe_values(reference_interpolation, reference_times, &reference_scales.values())
+                    },
+                    _ => Vec::new(),
+                };
+                subtract_values(interpolation, times, scales, |vector, time| {
+                    *vector -= sample(&keyframes, time, Vector3::lerp).unwrap_or(rest);
+                });
+            },
+            // Weights rest at zero, so without a reference they are deltas
+            // already.
+            &mut Channel::Weights {
+                interpolation, weights: Keyframes::Float(ref mut weights), ..
+            } => {
+                let keyframes = match reference {
+                    Some(&Channel::Weights { weights: ref reference_weights, .. }) => {
+                        keyframe_values(reference_interpolation, reference_times, &reference_weights.values())
+                    },
+                    _ => Vec::new(),
+                };
+                subtract_values(interpolation, times, weights, |scalar, time| {
+                    *scalar -= sample(&keyframes, time, |a, b, amount| a + (b - a) * amount).unwrap_or(0.0);
+                });
+            },
+            _ => {},
+        }
+        if self.is_quantized() {
+            channel.quantize();
+        }
+
+        channel
+    }
+
+    /// Number of keyframes, not counting spline tangents.
+    pub fn keyframe_count(&self) -> usize {
+        let value_count = match self {
+            &Channel::Translation { ref translations, .. } => translations.len(),
+            &Channel::Rotation { ref rotations, .. } => rotations.len(),
+            &Channel::Scale { ref scales, .. } => scales.len(),
+            &Channel::Weights { ref weights, .. } => weights.len(),
+        };
+
+        match self.interpolation() {
+            Interpolation::CatmullRom => value_count.saturating_sub(2),
+            Interpolation::Cubic => value_count / 3,
+            Interpolation::Linear | Interpolation::Step => value_count,
+        }
+    }
+}
+
+fn sort_by_time<T: Clone>(times: &mut Vec<f32>, values: &mut Vec<T>) -> bool {
+    if times.windows(2).all(|pair| pair[0] <= pair[1]) {
+        return false;
+    }
+
+    let mut order = (0..times.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| times[a].partial_cmp(&times[b]).unwrap_or(Ordering::Equal));
+    *values = order.iter().filter_map(|&index| values.get(index).cloned()).collect();
+    *times = order.iter().map(|&index| times[index]).collect();
+    true
+}
+
+/// Pairs each time stamp with the value of its keyframe, skipping spline
+/// tangents.
+fn keyframe_values<T: Copy>(interpolation: Interpolation, times: &[f32], values: &[T]) -> Vec<(f32, T)> {
+    times.iter().enumerate().filter_map(|(keyframe, &time)| {
+        values.get(interpolation.value_position(keyframe)).map(|&value| (time, value))
+    }).collect()
+}
+
+/// Calls `subtract` on the value of every keyframe, with its time. Spline
+/// tangents are rates of change, so they are left as they are.
+fn subtract_values<T, F: Fn(&mut T, f32)>(interpolation: Interpolation, times: &[f32], values: &mut [T], subtract: F) {
+    for (keyframe, &time) in times.iter().enumerate() {
+        if let Some(value) = values.get_mut(interpolation.value_position(keyframe)) {
+            subtract(value, time);
+        }
+    }
+}
+
+/// Value of `keyframes` at `time`. Between keyframes the surrounding values
+/// are blended with `lerp`; before the first and after the last keyframe
+/// their values are held.
+fn sample<T: Copy, F: Fn(T, T, f32) -> T>(keyframes: &[(f32, T)], time: f32, lerp: F) -> Option<T> {
+    if let Some(&(_, value)) = keyframes.iter().find(|&&(time_stamp, _)| time_stamp == time) {
+        return Some(value);
+    }
+
+    let &(first_time, first_value) = keyframes.first()?;
+    if time < first_time {
+        return Some(first_value);
+    }
+    for pair in keyframes.windows(2) {
+        let ((start, from), (end, to)) = (pair[0], pair[1]);
+        if start < time && time < end {
+            return Some(lerp(from, to, (time - start) / (end - start)));
+        }
+    }
+
+    keyframes.last().map(|&(_, value)| value)
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Vector3Data {
     time_stamp: f32,
     vector: Vector3<f32>,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct QuaternionData {
     time_stamp: f32,
     quaternion: Quaternion<f32>,
 }
 
-pub struct ScalarData {
-    time_stamp: f32,
-    scalar: f32,
+/// glTF stores rotations as `[x, y, z, w]`, while cgmath's array
+/// conversion expects the scalar first.
+fn quaternion_from_xyzw(rotation: [f32; 4]) -> Quaternion<f32> {
+    Quaternion::from_sv(rotation[3], Vector3::new(rotation[0], rotation[1], rotation[2]))
+}
+
+fn mirror_vector_z(vector: Vector3<f32>) -> Vector3<f32> {
+    Vector3::new(vector.x, vector.y, -vector.z)
+}
+
+/// Mirrors a rotation along the Z axis, which reverses its direction about
+/// the mirrored axis.
+fn mirror_quaternion_z(rotation: Quaternion<f32>) -> Quaternion<f32> {
+    Quaternion::new(rotation.s, -rotation.v.x, -rotation.v.y, rotation.v.z)
 }
 
+/// Collects the channels moving joints. Their time arrays are added to
+/// `times`, with each distinct array stored once.
 fn get_channels<'a>(
     animation: &'a GltfAnimation,
+    animation_name: &'a str,
     skins: &'a Skins,
     buffers: &'a Buffers,
+    options: &'a Options,
+    times: &'a mut Vec<Vec<f32>>,
+    warnings: &'a mut Vec<ConvertWarning>,
 ) -> Result<Vec<Channel>> {
-    animation.channels().map(|channel| {
-        let sampler = channel.sampler();
-        let (interpolation_method, times) = match sampler.interpolation() {
-            InterpolationAlgorithm::CatmullRomSpline => {
-                let mut times = channel.times(buffers).collect::<Vec<_>>();
-                // Add stub timestamps for start and end tangents of spline.
-                let (first, last) = {
-                    (times[0], times[times.len()-1])
-                };
-                times.push(first);
-                times.push(last);
+    // Joints shared between skins resolve to the skin owning every target.
+    let node_indices = animation.channels()
+        .filter(|channel| !targets_camera(channel, skins) && !targets_morph(channel))
+        .map(|channel| channel.target().node().index())
+        .collect::<Vec<_>>();
+    let animation_skin = skins.find_skin(&node_indices);
 
-                (Interpolation::CatmullRom, times)
-            },
-            InterpolationAlgorithm::CubicSpline => {
-                let mut times = channel.times(buffers).collect::<Vec<_>>();
-                // Add stub timestamps for start and end tangents of spline.
-                let (first, last) = {
-                    (times[0], times[times.len()-1])
-                };
-                times.push(first);
-                times.push(last);
+    let targets_joint = |channel: &GltfChannel| !targets_camera(channel, skins) && !targets_morph(channel);
 
-                (Interpolation::Cubic, times)
-            },
-            InterpolationAlgorithm::Linear => {
-                let times = channel.times(buffers).collect::<Vec<_>>();
-
-                (Interpolation::Linear, times)
-            },
-            InterpolationAlgorithm::Step => {
-                let times = channel.times(buffers).collect::<Vec<_>>();
+    animation.channels().filter(|channel| targets_joint(channel)).map(|channel| {
+        let interpolation_method = match channel.sampler().interpolation() {
+            InterpolationAlgorithm::CatmullRomSpline => Interpolation::CatmullRom,
+            InterpolationAlgorithm::CubicSpline => Interpolation::Cubic,
+            InterpolationAlgorithm::Linear => Interpolation::Linear,
+            InterpolationAlgorithm::Step => Interpolation::Step,
+        };
+        let mut channel_times = channel.times(buffers)?.collect::<Vec<_>>();
 
-                (Interpolation::Step, times)
-            },
+        let sortable = match interpolation_method {
+            Interpolation::Linear | Interpolation::Step => true,
+            Interpolation::CatmullRom | Interpolation::Cubic => false,
         };
 
         let target = channel.target();
-        let joint_index = skins.get_joint_index(target.node().index())
+        let node_index = target.node().index();
+        let (rest_translation, rest_rotation, rest_scale) = target.node().transform().decomposed();
+        let (skin_index, joint_index) = skins.resolve_joint(node_index, animation_skin)
             .ok_or(ConvertError::InvalidJoint)?;
+        let joint_name = skins.skins()[skin_index].get_joint_name(node_index)
+            .ok_or(ConvertError::InvalidJoint)?
+            .to_owned();
 
-        match target.path() {
+        // Spline channels keep every output value, tangents included, in
+        // the order the sampler lists them.
+        let mut my_channel = match target.path() {
             TrsProperty::Translation => {
-                let translations = times.into_iter().zip(channel.translations(
-                    buffers
-                ).unwrap()).map(|(time_stamp, vector)| {
-                    Vector3Data {
-                        time_stamp: time_stamp,
-                        vector: Vector3::from(vector),
-                    }
-                }).collect::<Vec<_>>();
-
-                Ok(Channel::Translation {
+                Channel::Translation {
+                    skin_index: skin_index,
                     joint_index: joint_index,
+                    joint_name: joint_name,
                     interpolation: interpolation_method,
-                    translations: translations,
-                })
+                    time_index: 0,
+                    translations: Keyframes::Float(
+                        channel.translations(buffers)?.unwrap().map(Vector3::from).collect()
+                    ),
+                    rest: Vector3::from(rest_translation),
+                }
             },
             TrsProperty::Rotation => {
-                let rotations = times.into_iter().zip(channel.rotations_f32(
-                    buffers
-                ).unwrap()).map(|(time_stamp, quaternion)| {
-                    QuaternionData {
-                        time_stamp: time_stamp,
-                        quaternion: Quaternion::from(quaternion),
-                    }
-                }).collect::<Vec<_>>();
-
-                Ok(Channel::Rotation {
+                Channel::Rotation {
+                    skin_index: skin_index,
                     joint_index: joint_index,
+                    joint_name: joint_name,
                     interpolation: interpolation_method,
-                    rotations: rotations,
-                })
+                    time_index: 0,
+                    rotations: Keyframes::Float(
+                        channel.rotations_f32(buffers)?.unwrap().map(quaternion_from_xyzw).collect()
+                    ),
+                    rest: quaternion_from_xyzw(rest_rotation),
+                }
             },
             TrsProperty::Scale => {
-                let scales = times.into_iter().zip(channel.scales(
-                    buffers
-                ).unwrap()).map(|(time_stamp, vector)| {
-                    Vector3Data {
-                        time_stamp: time_stamp,
-                        vector: Vector3::from(vector),
-                    }
-                }).collect::<Vec<_>>();
-
-                Ok(Channel::Scale {
+                Channel::Scale {
+                    skin_index: skin_index,
                     joint_index: joint_index,
+                    joint_name: joint_name,
                     interpolation: interpolation_method,
-                    scales: scales,
-                })
+                    time_index: 0,
+                    scales: Keyframes::Float(channel.scales(buffers)?.unwrap().map(Vector3::from).collect()),
+                    rest: Vector3::from(rest_scale),
+                }
             },
             TrsProperty::Weights => {
-                let weights = times.into_iter().zip(channel.weights_f32(
-                    buffers
-                ).unwrap()).map(|(time_stamp, scalar)| {
-                    ScalarData {
-                        time_stamp: time_stamp,
-                        scalar: scalar,
-                    }
-                }).collect::<Vec<_>>();
-
-                Ok(Channel::Weights {
+                Channel::Weights {
+                    skin_index: skin_index,
                     joint_index: joint_index,
+                    joint_name: joint_name,
                     interpolation: interpolation_method,
-                    weights: weights,
-                })
+                    time_index: 0,
+                    weights: Keyframes::Float(channel.weights_f32(buffers)?.unwrap().collect()),
+                }
             },
+        };
+
+        // Spline tangents are tied to the keyframe order, so only linear and
+        // step channels are sorted.
+        if options.reorder_keyframes && sortable && my_channel.sort_keyframes(&mut channel_times) {
+            warnings.push(ConvertWarning::ReorderedKeyframes {
+                animation: animation_name.to_owned(),
+                joint: my_channel.joint_name().to_owned(),
+            });
         }
+        my_channel.set_time_index(pool_times(times, &channel_times));
+
+        Ok(my_channel)
     }).collect::<Result<Vec<_>>>()
 }
 
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub enum Interpolation {
     CatmullRom,
     Cubic,
     Linear,
     Step,
 }
+
+impl Interpolation {
+    /// Position of the value of keyframe `keyframe` among the outputs of a
+    /// channel. Catmull-Rom outputs start and end with a tangent, and cubic
+    /// outputs hold an in-tangent, value and out-tangent per keyframe.
+    fn value_position(self, keyframe: usize) -> usize {
+        match self {
+            Interpolation::CatmullRom => keyframe + 1,
+            Interpolation::Cubic => 3 * keyframe + 1,
+            Interpolation::Linear | Interpolation::Step => keyframe,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use cgmath::Zero;
+
+    use super::*;
+    use super::super::import;
+    use super::super::skin::get as get_skins;
+
+    #[test]
+    fn test_channel_joint_name() {
+        let path = Path::new("testmodels/gltf2/Monster/Monster.gltf");
+        let (gltf, extensions, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let skins = get_skins(gltf.skins(), &buffers, &extensions, &Options::default()).unwrap();
+        let animations = get(gltf.animations(), &skins, &buffers, &Options::default(), &mut Vec::new()).unwrap();
+        let skin = gltf.skins().next().unwrap();
+
+        match animations.animations[0].channels[0] {
+            Channel::Translation { joint_index, ref joint_name, .. } |
+            Channel::Rotation { joint_index, ref joint_name, .. } |
+            Channel::Scale { joint_index, ref joint_name, .. } |
+            Channel::Weights { joint_index, ref joint_name, .. } => {
+                let joint = skin.joints().nth(joint_index as usize).unwrap();
+                assert_eq!(Some(joint_name.as_ref()), joint.name());
+            },
+        }
+    }
+
+    #[test]
+    fn test_morph_channels() {
+        let path = Path::new("testmodels/gltf2/MorphAnimation/MorphAnimation.gltf");
+        let (gltf, extensions, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let skins = get_skins(gltf.skins(), &buffers, &extensions, &Options::default()).unwrap();
+        let animations = get(gltf.animations(), &skins, &buffers, &Options::default(), &mut Vec::new()).unwrap();
+        let animation = &animations.animations()[0];
+
+        assert!(animation.channels().is_empty());
+        assert_eq!(animation.duration(), 1.0);
+
+        let morph_channel = &animation.morph_channels()[0];
+        assert_eq!(morph_channel.node_index(), 0);
+        assert_eq!(morph_channel.target_count(), 2);
+        assert_eq!(morph_channel.target_names(), &[String::from("smile"), String::from("frown")]);
+        assert_eq!(morph_channel.keyframe_count(), 3);
+        assert_eq!(morph_channel.weights()[2].weights(), &[0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_rebase_time() {
+        let path = Path::new("testmodels/gltf2/LateClip/LateClip.gltf");
+        let (gltf, extensions, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let skins = get_skins(gltf.skins(), &buffers, &extensions, &Options::default()).unwrap();
+        let mut options = Options::default();
+        let times = |animations: &AnimationSet| {
+            let animation = &animations.animations[0];
+            animation.times[animation.channels[0].time_index()].clone()
+        };
+
+        let animations = get(gltf.animations(), &skins, &buffers, &options, &mut Vec::new()).unwrap();
+        assert_eq!(times(&animations), vec![2.0, 3.0, 4.5]);
+
+        options.rebase_time = true;
+        let animations = get(gltf.animations(), &skins, &buffers, &options, &mut Vec::new()).unwrap();
+        assert_eq!(times(&animations), vec![0.0, 1.0, 2.5]);
+        assert_eq!(animations.animations[0].duration(), 2.5);
+    }
+
+    #[test]
+    fn test_shared_joint() {
+        let path = Path::new("testmodels/gltf2/SharedJoint/SharedJoint.gltf");
+        let (gltf, extensions, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let skins = get_skins(gltf.skins(), &buffers, &extensions, &Options::default()).unwrap();
+
+        // The hips are joint 0 of the body and joint 1 of the tail.
+        assert_eq!(skins.get_joints(0), &[(0, 0), (1, 1)]);
+
+        // The clip also moves the tail, so the hips resolve to the tail skin.
+        let animations = get(gltf.animations(), &skins, &buffers, &Options::default(), &mut Vec::new()).unwrap();
+        let channels = animations.animations[0].channels();
+        assert_eq!(channels[0].skin_index(), 1);
+        assert_eq!(channels[0].joint_index(), 1);
+        assert_eq!(channels[0].joint_name(), "hips");
+        assert_eq!(channels[1].skin_index(), 1);
+        assert_eq!(channels[1].joint_index(), 0);
+    }
+
+    #[test]
+    fn test_reorder_keyframes() {
+        let path = Path::new("testmodels/gltf2/UnsortedKeyframes/UnsortedKeyframes.gltf");
+        let (gltf, extensions, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let skins = get_skins(gltf.skins(), &buffers, &extensions, &Options::default()).unwrap();
+        let keyframes = |animations: &AnimationSet| {
+            let animation = &animations.animations[0];
+            match animation.channels[0] {
+                Channel::Translation { time_index, ref translations, .. } => {
+                    animation.times[time_index].iter().cloned()
+                        .zip(translations.values().iter().map(|vector| vector.y))
+                        .collect::<Vec<_>>()
+                },
+                _ => Vec::new(),
+            }
+        };
+
+        let mut warnings = Vec::new();
+        let animations = get(gltf.animations(), &skins, &buffers, &Options::default(), &mut warnings).unwrap();
+        assert_eq!(keyframes(&animations), vec![(0.0, 0.0), (2.0, 2.0), (1.0, 1.0), (3.0, 3.0)]);
+        assert!(warnings.is_empty());
+
+        let mut options = Options::default();
+        options.reorder_keyframes = true;
+        let animations = get(gltf.animations(), &skins, &buffers, &options, &mut warnings).unwrap();
+        assert_eq!(keyframes(&animations), vec![(0.0, 0.0), (1.0, 1.0), (2.0, 2.0), (3.0, 3.0)]);
+        assert_eq!(warnings, vec![ConvertWarning::ReorderedKeyframes {
+            animation: String::from("bob"),
+            joint: String::from("hips"),
+        }]);
+    }
+
+    #[test]
+    fn test_shared_times() {
+        let path = Path::new("testmodels/gltf2/SharedTimes/SharedTimes.gltf");
+        let (gltf, extensions, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let skins = get_skins(gltf.skins(), &buffers, &extensions, &Options::default()).unwrap();
+        let animations = get(gltf.animations(), &skins, &buffers, &Options::default(), &mut Vec::new()).unwrap();
+        let animation = &animations.animations()[0];
+
+        // The hips channels share one array, whether or not they share an accessor.
+        assert_eq!(animation.times(), &[vec![0.0, 1.0, 2.0], vec![0.0, 0.5]][..]);
+        let indices = (0..4).map(|channel| animation.time_index(channel).unwrap()).collect::<Vec<_>>();
+        assert_eq!(indices, vec![0, 0, 0, 1]);
+        assert_eq!(animation.time_index(4), None);
+    }
+
+    #[test]
+    fn test_animation_set() {
+        let path = Path::new("testmodels/gltf2/Monster/Monster.gltf");
+        let (gltf, extensions, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let skins = get_skins(gltf.skins(), &buffers, &extensions, &Options::default()).unwrap();
+        let animations = get(gltf.animations(), &skins, &buffers, &Options::default(), &mut Vec::new()).unwrap();
+
+        let expected = gltf.animations()
+            .map(|animation| animation.name().unwrap().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(animations.names(), expected);
+
+        let animation = animations.by_name("animation1").unwrap();
+        assert_eq!(animation.name(), "animation1");
+        assert!(animation.duration() > 0.0);
+        assert!(animations.by_name("missing").is_none());
+
+        for joint in animation.joints() {
+            assert!(animations.joints().contains(&joint));
+        }
+    }
+
+    #[test]
+    fn test_to_additive() {
+        let path = Path::new("testmodels/gltf2/Monster/Monster.gltf");
+        let (gltf, extensions, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let skins = get_skins(gltf.skins(), &buffers, &extensions, &Options::default()).unwrap();
+        let animations = get(gltf.animations(), &skins, &buffers, &Options::default(), &mut Vec::new()).unwrap();
+        let animation = &animations.animations()[0];
+        let additive = animation.to_additive(animation);
+
+        assert_eq!(additive.channels().len(), animation.channels().len());
+        for channel in additive.channels() {
+            match channel {
+                &Channel::Translation { ref translations, .. } => {
+                    assert!(translations.values().iter().all(|vector| *vector == Vector3::zero()));
+                },
+                &Channel::Rotation { ref rotations, .. } => {
+                    assert!(rotations.values().iter().all(|quaternion| {
+                        (quaternion.s - 1.0).abs() < 1e-4 && quaternion.v.magnitude() < 1e-4
+                    }));
+                },
+                &Channel::Scale { ref scales, .. } => {
+                    assert!(scales.values().iter().all(|vector| *vector == Vector3::zero()));
+                },
+                &Channel::Weights { ref weights, .. } => {
+                    assert!(weights.values().iter().all(|&scalar| scalar == 0.0));
+                },
+            }
+        }
+        assert_eq!(additive.times(), animation.times());
+    }
+
+    #[test]
+    fn test_to_additive_spline() {
+        let path = Path::new("testmodels/gltf2/AdditiveSpline/AdditiveSpline.gltf");
+        let (gltf, extensions, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let skins = get_skins(gltf.skins(), &buffers, &extensions, &Options::default()).unwrap();
+        let animations = get(gltf.animations(), &skins, &buffers, &Options::default(), &mut Vec::new()).unwrap();
+        let additive = animations.by_name("sway").unwrap().to_additive(animations.by_name("pose").unwrap());
+        let translations = |channel: &Channel| {
+            match channel {
+                &Channel::Translation { ref translations, .. } => translations.values(),
+                _ => Vec::new(),
+            }
+        };
+
+        // Only the values of the cubic hips channel lose the pose; the
+        // tangents stay as they are.
+        assert_eq!(translations(&additive.channels()[0]), vec![
+            Vector3::new(0.5, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.5, 0.0, 0.0),
+            Vector3::new(0.5, 0.0, 0.0), Vector3::new(2.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 0.0),
+        ]);
+        // The pose does not move the spine, so its rest translation is
+        // subtracted.
+        assert_eq!(translations(&additive.channels()[1]), vec![
+            Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0),
+        ]);
+    }
+
+    #[test]
+    fn test_quantize_keyframes() {
+        let path = Path::new("testmodels/gltf2/Monster/Monster.gltf");
+        let (gltf, extensions, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let skins = get_skins(gltf.skins(), &buffers, &extensions, &Options::default()).unwrap();
+        let animations = get(gltf.animations(), &skins, &buffers, &Options::default(), &mut Vec::new()).unwrap();
+        let mut options = Options::default();
+        options.quantize_keyframes = true;
+        let quantized_animations = get(gltf.animations(), &skins, &buffers, &options, &mut Vec::new()).unwrap();
+        let animation = &animations.animations()[0];
+        let quantized_animation = &quantized_animations.animations()[0];
+        assert_eq!(quantized_animation.channels().len(), animation.channels().len());
+
+        let mut rotation_count = 0;
+        for (channel, quantized) in animation.channels().iter().zip(quantized_animation.channels()) {
+            match (channel, quantized) {
+                (&Channel::Rotation { ref rotations, .. },
+                 &Channel::Rotation { rotations: ref quantized_rotations, .. }) => {
+                    rotation_count += 1;
+                    assert!(quantized_rotations.is_quantized());
+                    assert_eq!(quantized_rotations.len(), rotations.len());
+                    for (quaternion, decoded) in rotations.values().iter().zip(quantized_rotations.values()) {
+                        let dot = quaternion.normalize().dot(decoded).abs().min(1.0);
+                        assert!(2.0 * dot.acos() < 1e-3);
+                    }
+                },
+                (&Channel::Translation { ref translations, .. },
+                 &Channel::Translation { translations: ref quantized_translations, .. }) => {
+                    assert!(quantized_translations.is_quantized());
+                    for (vector, decoded) in translations.values().iter().zip(quantized_translations.values()) {
+                        assert!((vector - decoded).magnitude() < 1e-2);
+                    }
+                },
+                _ => {},
+            }
+        }
+        assert!(rotation_count > 0);
+    }
+
+    #[test]
+    fn test_left_handed() {
+        let path = Path::new("testmodels/gltf2/LeftHanded/LeftHanded.gltf");
+        let (gltf, extensions, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let skins = get_skins(gltf.skins(), &buffers, &extensions, &Options::default()).unwrap();
+        let mut options = Options::default();
+        options.handedness = Handedness::Left;
+        let animations = get(gltf.animations(), &skins, &buffers, &options, &mut Vec::new()).unwrap();
+        let channels = animations.animations[0].channels();
+
+        match channels[0] {
+            Channel::Translation { ref translations, rest, .. } => {
+                assert_eq!(translations.values(), vec![Vector3::new(0.0, 1.0, -2.0), Vector3::new(1.0, 1.0, 3.0)]);
+                assert_eq!(rest, Vector3::new(0.0, 1.0, -2.0));
+            },
+            _ => panic!("Expected translations"),
+        }
+        match channels[1] {
+            Channel::Rotation { ref rotations, rest, .. } => {
+                assert_eq!(rotations.values()[1], Quaternion::new(0.5, -0.5, -0.5, 0.5));
+                assert_eq!(rest, Quaternion::new(0.8660254, -0.5, 0.0, 0.0));
+            },
+            _ => panic!("Expected rotations"),
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use gltf::buffer::{Buffer as GltfBuffer, View as GltfView};
+use gltf::gltf::{Buffers as GltfBuffers, Views as GltfViews};
+use gltf::json::Value;
+use gltf_utils::Source;
+
+use super::super::{Result, Error};
+use super::ConvertError;
+use super::extension::Extensions;
+use super::util::{decode_data_uri, resolve_uri};
+
+#[derive(Clone, Debug)]
+pub struct Buffers {
+    buffers: Vec<Vec<u8>>,
+}
+
+impl Source for Buffers {
+    fn source_buffer(&self, buffer: &GltfBuffer) -> &[u8] {
+        &self.buffers[buffer.index()]
+    }
+}
+
+impl Buffers {
+    pub fn buffer(&self, buffer: &GltfBuffer) -> Option<&[u8]> {
+        self.buffers.get(buffer.index()).map(Vec::as_slice)
+    }
+
+    /// Bytes of the view, or `None` if they do not fit within its buffer.
+    pub fn view(&self, view: &GltfView) -> Option<&[u8]> {
+        let start = view.offset();
+        let end = start.checked_add(view.length());
+        self.buffer(&view.buffer())
+            .and_then(|data| end.and_then(|end| data.get(start .. end)))
+    }
+}
+
+/// Makes sure every buffer view fits within the declared length of its
+/// buffer.
+pub fn check_views(views: GltfViews) -> Result<()> {
+    for view in views {
+        let end = view.offset().checked_add(view.length());
+        if end.map_or(true, |end| end > view.buffer().length()) {
+            return Err(Error::Convert(ConvertError::BufferViewOverrun {
+                view: view.index(),
+            }));
+        }
+    }
+
+    Ok(())
+}
+
+/// Makes sure no buffer view can only be read by decoding
+/// `EXT_meshopt_compression`, as no meshopt decoder is linked. Views whose
+/// buffer still holds the uncompressed data are read as usual.
+pub fn check_meshopt<'a>(
+    views: GltfViews,
+    extensions: &'a Extensions,
+) -> Result<()> {
+    let required = extensions.is_required(MESHOPT_EXTENSION);
+
+    for view in views {
+        if extensions.view(view.index(), MESHOPT_EXTENSION).is_none() {
+            continue;
+        }
+
+        // Fallback buffers only reserve space for the decoded data.
+        let fallback = extensions.buffer(view.buffer().index(), MESHOPT_EXTENSION)
+            .and_then(|extension| extension.get("fallback"))
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        if required || fallback {
+            return Err(Error::Convert(ConvertError::MeshoptNotSupported {
+                view: view.index(),
+            }));
+        }
+    }
+
+    Ok(())
+}
+
+const MESHOPT_EXTENSION: &'static str = "EXT_meshopt_compression";
+
+pub fn get<'a>(
+    base_path: &'a Path,
+    buffers: GltfBuffers,
+    bin: Option<&'a [u8]>,
+) -> Result<Buffers> {
+    let my_buffers = buffers.map(|buffer| {
+        let uri = buffer.uri();
+        let data = if uri == "#bin" {
+            bin.ok_or(ConvertError::InvalidBufferLength)?.to_vec()
+        } else if uri.starts_with("data:") {
+            decode_data_uri(uri)?.1
+        } else {
+            let mut data = Vec::<u8>::new();
+            File::open(resolve_uri(base_path, uri)?)?.read_to_end(&mut data)?;
+            data
+        };
+
+        if data.len() < buffer.length() {
+            return Err(Error::Convert(ConvertError::InvalidBufferLength));
+        }
+
+        Ok(data)
+    }).collect::<Result<Vec<_>>>()?;
+
+    Ok(Buffers {
+        buffers: my_buffers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use gltf::Gltf;
+    use gltf::json::from_slice as json_from_slice;
+
+    use super::*;
+    use super::super::get_with_stats;
+    use super::super::options::Options;
+
+    #[test]
+    fn test_data_uri() {
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "buffers": [
+                { "uri": "data:application/octet-stream;base64,AAECA/8=", "byteLength": 5 }
+            ]
+        }"#;
+        let gltf = Gltf::from_str(json).unwrap().validate_minimally().unwrap();
+        let buffers = get(Path::new("."), gltf.buffers(), None).unwrap();
+        assert_eq!(buffers.buffers[0], vec![0, 1, 2, 3, 255]);
+
+        let (media_type, data) = decode_data_uri("data:image/png;base64,iVBORw==").unwrap();
+        assert_eq!(media_type, "image/png");
+        assert_eq!(data, vec![0x89, b'P', b'N', b'G']);
+
+        for uri in &["data:image/png;base64", "data:text/plain,hello", "data:;base64,%%%"] {
+            match decode_data_uri(uri) {
+                Err(Error::Convert(ConvertError::InvalidDataUri)) => {},
+                _ => panic!("expected InvalidDataUri for {}", uri),
+            }
+        }
+    }
+
+    #[test]
+    fn test_view_overrun() {
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "buffers": [ { "byteLength": 8 } ],
+            "bufferViews": [
+                { "buffer": 0, "byteOffset": 0, "byteLength": 8 },
+                { "buffer": 0, "byteOffset": 4, "byteLength": 8 }
+            ]
+        }"#;
+        let gltf = Gltf::from_str(json).unwrap().validate_minimally().unwrap();
+
+        match check_views(gltf.views()) {
+            Err(Error::Convert(ConvertError::BufferViewOverrun { view })) => assert_eq!(view, 1),
+            _ => panic!("expected BufferViewOverrun"),
+        }
+
+        // Reading the view anyway gives nothing instead of panicking.
+        let buffers = Buffers { buffers: vec![vec![0; 8]] };
+        assert_eq!(buffers.view(&gltf.views().nth(0).unwrap()).map(<[u8]>::len), Some(8));
+        assert_eq!(buffers.view(&gltf.views().nth(1).unwrap()), None);
+    }
+
+    #[test]
+    fn test_meshopt() {
+        let path = Path::new("testmodels/gltf2/Meshopt/Meshopt.gltf");
+        match get_with_stats(path, &Options::default()) {
+            Err(Error::Convert(ConvertError::MeshoptNotSupported { view })) => assert_eq!(view, 0),
+            _ => panic!("expected MeshoptNotSupported"),
+        }
+
+        // Without the extension required, the uncompressed data is still there.
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "extensionsUsed": [ "EXT_meshopt_compression" ],
+            "buffers": [ { "byteLength": 8 }, { "byteLength": 48 } ],
+            "bufferViews": [
+                {
+                    "buffer": 1, "byteLength": 48,
+                    "extensions": { "EXT_meshopt_compression": { "buffer": 0, "byteLength": 8, "byteStride": 12, "count": 4 } }
+                }
+            ]
+        }"#;
+        let root = json_from_slice::<Value>(json.as_bytes()).unwrap();
+        let gltf = Gltf::from_value(root.clone()).unwrap().validate_minimally().unwrap();
+        assert!(check_meshopt(gltf.views(), &Extensions::new(root)).is_ok());
+    }
+
+    #[test]
+    fn test_refuse_parent_directory_uri() {
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "buffers": [ { "uri": "../../../etc/passwd", "byteLength": 4 } ]
+        }"#;
+        let gltf = Gltf::from_str(json).unwrap().validate_minimally().unwrap();
+        let base_path = Path::new("testmodels/gltf2/Box");
+
+        match get(base_path, gltf.buffers(), None) {
+            Err(Error::Convert(ConvertError::UnsafeResourcePath { uri })) => {
+                assert_eq!(uri, "../../../etc/passwd");
+            },
+            _ => assert!(false),
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use cgmath::{Matrix4, SquareMatrix};
+use gltf::{Node, Scene};
+use gltf::camera::Projection as GltfProjection;
+
+use super::extension::Extensions;
+use super::{is_hidden, local_transform};
+use super::options::Options;
+
+/// A camera placed in the scene by a visible node.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Camera {
+    name: Option<String>,
+    node_index: usize,
+    projection: Projection,
+    world_transform: Matrix4<f32>,
+}
+
+impl Camera {
+    /// Name of the glTF camera, not of its node.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_ref().map(String::as_str)
+    }
+
+    /// Index of the camera's node in the glTF nodes.
+    pub fn node_index(&self) -> usize {
+        self.node_index
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    /// World transform of the camera's node. The camera looks down its
+    /// local -Z axis with +Y up.
+    pub fn world_transform(&self) -> &Matrix4<f32> {
+        &self.world_transform
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    Perspective {
+        /// Vertical field of view in radians.
+        yfov: f32,
+        /// Width over height. `None` means the viewport's aspect ratio.
+        aspect_ratio: Option<f32>,
+        znear: f32,
+        /// `None` for an infinite projection.
+        zfar: Option<f32>,
+    },
+    Orthographic {
+        /// Half the width of the view.
+        xmag: f32,
+        /// Half the height of the view.
+        ymag: f32,
+        znear: f32,
+        zfar: f32,
+    },
+}
+
+/// Collects the cameras of the visible nodes in `scene`, in depth first
+/// order.
+pub fn get<'a>(
+    scene: &'a Scene,
+    extensions: &'a Extensions,
+    options: &'a Options,
+) -> Vec<Camera> {
+    let mut cameras = Vec::<Camera>::new();
+    for node in scene.nodes() {
+        get_helper(&node, &Matrix4::identity(), &mut cameras, extensions, options);
+    }
+
+    cameras
+}
+
+fn get_helper<'a>(
+    node: &'a Node,
+    parent_transform: &'a Matrix4<f32>,
+    cameras: &'a mut Vec<Camera>,
+    extensions: &'a Extensions,
+    options: &'a Options,
+) {
+    if is_hidden(node, extensions, options) {
+        return;
+    }
+
+    let world_transform = parent_transform * local_transform(node, options);
+    if let Some(camera) = node.camera() {
+        let projection = match camera.projection() {
+            GltfProjection::Perspective(perspective) => Projection::Perspective {
+                yfov: perspective.yfov(),
+                aspect_ratio: perspective.aspect_ratio(),
+                znear: perspective.znear(),
+                zfar: perspective.zfar(),
+            },
+            GltfProjection::Orthographic(orthographic) => Projection::Orthographic {
+                xmag: orthographic.xmag(),
+                ymag: orthographic.ymag(),
+                znear: orthographic.znear(),
+                zfar: orthographic.zfar(),
+            },
+        };
+
+        cameras.push(Camera {
+            name: camera.name().map(String::from),
+            node_index: node.index(),
+            projection: projection,
+            world_transform: world_transform,
+        });
+    }
+
+    for child in node.children() {
+        get_helper(&child, &world_transform, cameras, extensions, options);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use cgmath::{InnerSpace, Vector4};
+
+    use super::*;
+    use super::super::{get, get_with_options};
+    use super::super::options::Handedness;
+
+    #[test]
+    fn test_cameras() {
+        let path = Path::new("testmodels/gltf2/Cameras/Cameras.gltf");
+        let conversion = get(path).unwrap();
+        let cameras = conversion.cameras();
+
+        assert_eq!(cameras.len(), 2);
+        assert_eq!(cameras[0].name(), Some("wide"));
+        assert_eq!(cameras[0].projection(), Projection::Perspective {
+            yfov: 0.8,
+            aspect_ratio: Some(1.5),
+            znear: 0.1,
+            zfar: Some(100.0),
+        });
+        // The rig moves the lens up by 2, and the lens moves back by 5.
+        assert_eq!(cameras[0].world_transform() * Vector4::new(0.0, 0.0, 0.0, 1.0), Vector4::new(0.0, 2.0, 5.0, 1.0));
+
+        assert_eq!(cameras[1].name(), Some("top"));
+        assert_eq!(cameras[1].projection(), Projection::Orthographic {
+            xmag: 4.0,
+            ymag: 3.0,
+            znear: 0.5,
+            zfar: 50.0,
+        });
+    }
+
+    #[test]
+    fn test_left_handed() {
+        let path = Path::new("testmodels/gltf2/Cameras/Cameras.gltf");
+        let mut options = Options::default();
+        options.handedness = Handedness::Left;
+        let conversion = get_with_options(path, &options).unwrap();
+        let cameras = conversion.cameras();
+
+        // The lens moves back along -Z, and the overhead camera looks down
+        // its +Z axis, as left handed cameras do.
+        assert_eq!(cameras[0].world_transform() * Vector4::new(0.0, 0.0, 0.0, 1.0), Vector4::new(0.0, 2.0, -5.0, 1.0));
+        let forward = cameras[1].world_transform() * Vector4::new(0.0, 0.0, 1.0, 0.0);
+        assert!((forward - Vector4::new(0.0, -1.0, 0.0, 0.0)).magnitude() < 1e-5);
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::BTreeSet;
+use std::fs::{File, create_dir_all, rename};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+use super::super::{Result, Error};
+use super::{ConvertError, Model, deserialize_models, serialize_models};
+use super::options::Options;
+use super::util::name_id;
+
+/// File in the checkpoint directory holding the key of the conversion,
+/// then listing the completed node indices.
+pub const MANIFEST_FILE: &'static str = "manifest";
+
+/// Directory in the checkpoint directory holding the models of each
+/// completed node, in a file named after the node index.
+pub const MODELS_DIRECTORY: &'static str = "models";
+
+/// Progress of a conversion, kept on disk so an interrupted conversion can
+/// resume without converting finished models again.
+pub struct Checkpoint {
+    /// Hash of the input path and options the checkpoint was made with.
+    key: u64,
+    completed: BTreeSet<usize>,
+    directory: PathBuf,
+}
+
+impl Checkpoint {
+    /// Opens the checkpoint in `directory` for converting `path` with
+    /// `options`, creating it if needed. A checkpoint made for another path
+    /// or other options fails with `ConvertError::InvalidCheckpoint`, rather
+    /// than mixing models from both.
+    pub fn open<P: AsRef<Path>, Q: AsRef<Path>>(directory: P, path: Q, options: &Options) -> Result<Checkpoint> {
+        let directory = directory.as_ref();
+        create_dir_all(directory.join(MODELS_DIRECTORY))?;
+
+        let key = conversion_key(path.as_ref(), options);
+        let manifest_path = directory.join(MANIFEST_FILE);
+        let mut completed = BTreeSet::<usize>::new();
+        if manifest_path.exists() {
+            let mut lines = BufReader::new(File::open(&manifest_path)?).lines();
+            let recorded_key = match lines.next() {
+                Some(line) => u64::from_str_radix(line?.trim(), 16).map_err(|_| ConvertError::InvalidCheckpoint)?,
+                None => { return Err(Error::Convert(ConvertError::InvalidCheckpoint)); },
+            };
+            if recorded_key != key {
+                return Err(Error::Convert(ConvertError::InvalidCheckpoint));
+            }
+            for line in lines {
+                let line = line?;
+                let node_index = line.trim().parse::<usize>()
+                    .map_err(|_| ConvertError::InvalidCheckpoint)?;
+                completed.insert(node_index);
+            }
+        }
+
+        Ok(Checkpoint {
+            key: key,
+            completed: completed,
+            directory: directory.to_path_buf(),
+        })
+    }
+
+    /// Whether the model of the node was written by an earlier run.
+    pub fn is_complete(&self, node_index: usize) -> bool {
+        self.completed.contains(&node_index)
+    }
+
+    /// Reads back the models an earlier run recorded for the node.
+    pub fn load(&self, node_index: usize) -> Result<Vec<Model>> {
+        let mut bytes = Vec::<u8>::new();
+        File::open(self.models_path(node_index))?.read_to_end(&mut bytes)?;
+
+        deserialize_models(&bytes)
+    }
+
+    /// Writes the node's models, then marks the node as complete. Both files
+    /// are replaced whole, so an interrupted run leaves either the old or
+    /// the new version, and a node is only listed once it is fully written.
+    pub fn record(&mut self, node_index: usize, models: &[Model]) -> Result<()> {
+        write_atomically(&self.models_path(node_index), &serialize_models(models)?)?;
+
+        self.completed.insert(node_index);
+        let manifest = format!("{:016x}\n", self.key) + &self.completed.iter()
+            .map(|node_index| format!("{}\n", node_index))
+            .collect::<String>();
+        write_atomically(&self.directory.join(MANIFEST_FILE), manifest.as_bytes())
+    }
+
+    fn models_path(&self, node_index: usize) -> PathBuf {
+        self.directory.join(MODELS_DIRECTORY).join(format!("{}.bin", node_index))
+    }
+}
+
+/// Hashes the input path and the options that shape the models. Where the
+/// checkpoint itself lives does not matter.
+fn conversion_key(path: &Path, options: &Options) -> u64 {
+    let mut options = options.clone();
+    options.checkpoint = None;
+
+    name_id(&format!("{}\n{:?}", path.display(), options))
+}
+
+/// Writes `bytes` to a temporary file beside `path`, then renames it over
+/// `path`.
+fn write_atomically(path: &Path, bytes: &[u8]) -> Result<()> {
+    let temporary = path.with_extension("tmp");
+    let mut file = File::create(&temporary)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    rename(&temporary, path)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+    use std::fs::{read_to_string, remove_dir_all};
+
+    use cgmath::Matrix4;
+
+    use super::super::get_with_stats;
+    use super::super::options::Options;
+    use super::*;
+
+    #[test]
+    fn test_resume() {
+        let path = Path::new("testmodels/gltf2/ThreeQuads/ThreeQuads.gltf");
+        let directory = temp_dir().join("wg3d_test_resume");
+        let _ = remove_dir_all(&directory);
+        let mut options = Options::default();
+        options.checkpoint = Some(directory.clone());
+
+        // An earlier run stopped after converting the first two nodes. The
+        // recorded models are marked to tell them from converted ones.
+        let (conversion, _) = get_with_stats(path, &Options::default()).unwrap();
+        let mut models = conversion.models;
+        let marker = Matrix4::from_scale(2.0);
+        for model in models.iter_mut() {
+            model.transform = marker;
+        }
+        let mut checkpoint = Checkpoint::open(&directory, path, &options).unwrap();
+        checkpoint.record(0, &models[0..1]).unwrap();
+        checkpoint.record(1, &models[1..2]).unwrap();
+
+        let (conversion, _) = get_with_stats(path, &options).unwrap();
+        let models = conversion.models();
+        assert_eq!(models.iter().map(Model::node_index).collect::<Vec<_>>(), vec![Some(0), Some(1), Some(2)]);
+        assert_eq!(models[0].transform(), &marker);
+        assert_eq!(models[1].transform(), &marker);
+        assert!(models[2].transform() != &marker);
+        let key = format!("{:016x}\n", conversion_key(path, &options));
+        assert_eq!(read_to_string(directory.join(MANIFEST_FILE)).unwrap(), key + "0\n1\n2\n");
+
+        // Nothing is left to convert, so every model is read back.
+        let (resumed, _) = get_with_stats(path, &options).unwrap();
+        assert_eq!(resumed.models().len(), 3);
+        assert_eq!(resumed.models()[0].transform(), &marker);
+        assert_eq!(resumed.models()[2].transform(), models[2].transform());
+
+        remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_other_conversion() {
+        let path = Path::new("testmodels/gltf2/ThreeQuads/ThreeQuads.gltf");
+        let directory = temp_dir().join("wg3d_test_other_conversion");
+        let _ = remove_dir_all(&directory);
+        let mut options = Options::default();
+        options.checkpoint = Some(directory.clone());
+        get_with_stats(path, &options).unwrap();
+
+        // Resuming with other options or another file is refused.
+        let mut other_options = options.clone();
+        other_options.flat_normals = false;
+        match get_with_stats(path, &other_options) {
+            Err(Error::Convert(ConvertError::InvalidCheckpoint)) => {},
+            _ => panic!("expected InvalidCheckpoint"),
+        }
+        match Checkpoint::open(&directory, "testmodels/gltf2/Hierarchy/Hierarchy.gltf", &options) {
+            Err(Error::Convert(ConvertError::InvalidCheckpoint)) => {},
+            _ => panic!("expected InvalidCheckpoint"),
+        }
+
+        // The directory of the checkpoint is not part of its key.
+        assert!(Checkpoint::open(&directory, path, &Options::default()).is_ok());
+
+        remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,211 @@
+use std::io::Write;
+
+use byteorder::{ByteOrder, LE, WriteBytesExt};
+
+use super::super::{Result, Error};
+use super::{ConvertError, Model};
+use super::texture::Texture;
+
+/// Alignment in bytes of the directory and of every chunk's data.
+pub const CHUNK_ALIGNMENT: usize = 16;
+
+/// Size in bytes of one directory entry.
+const ENTRY_SIZE: usize = 24;
+
+/// Size in bytes of the length prefix before each chunk's data.
+const PREFIX_SIZE: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChunkKind {
+    Vertices,
+    Indices,
+    Texture,
+}
+
+impl ChunkKind {
+    fn code(self) -> u32 {
+        match self {
+            ChunkKind::Vertices => 0,
+            ChunkKind::Indices => 1,
+            ChunkKind::Texture => 2,
+        }
+    }
+
+    fn from_code(code: u32) -> Option<ChunkKind> {
+        match code {
+            0 => Some(ChunkKind::Vertices),
+            1 => Some(ChunkKind::Indices),
+            2 => Some(ChunkKind::Texture),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Chunk {
+    pub kind: ChunkKind,
+    pub data: Vec<u8>,
+}
+
+/// Where a chunk was written, as recorded in the directory.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChunkEntry {
+    pub kind: ChunkKind,
+    /// Byte offset of the chunk's data from the start of the output.
+    pub offset: u64,
+    pub length: u64,
+}
+
+/// Collects the interleaved vertices and `u32` indices of every primitive,
+/// followed by the contents of every texture.
+pub fn get_chunks<'a>(
+    models: &'a [Model],
+    textures: &'a [Texture],
+) -> Vec<Chunk> {
+    let mut chunks = Vec::<Chunk>::new();
+
+    for primitive in models.iter().flat_map(|model| model.mesh().primitives().iter()) {
+        let (_, vertices) = primitive.interleaved_bytes();
+        chunks.push(Chunk {
+            kind: ChunkKind::Vertices,
+            data: vertices,
+        });
+
+        let mut indices = Vec::<u8>::with_capacity(primitive.indices().len() * 4);
+        for &index in primitive.indices() {
+            indices.write_u32::<LE>(index).unwrap();
+        }
+        chunks.push(Chunk {
+            kind: ChunkKind::Indices,
+            data: indices,
+        });
+    }
+
+    for texture in textures {
+        chunks.push(Chunk {
+            kind: ChunkKind::Texture,
+            data: texture.contents().to_vec(),
+        });
+    }
+
+    chunks
+}
+
+/// Writes `chunks` so a loader can memory map the output and slice each
+/// chunk directly.
+///
+/// The output starts with a directory: the `u32` number of chunks, four
+/// reserved bytes, and for each chunk its `u32` kind, four reserved bytes,
+/// and the `u64` offset and length of its data. Each chunk's data is
+/// preceded by its `u64` length and eight reserved bytes. The directory and
+/// all data start on `CHUNK_ALIGNMENT` byte boundaries. Everything is little
+/// endian.
+pub fn write_chunks<W: Write>(
+    writer: &mut W,
+    chunks: &[Chunk],
+) -> Result<Vec<ChunkEntry>> {
+    let mut offset = align(8 + chunks.len() * ENTRY_SIZE);
+    let entries = chunks.iter().map(|chunk| {
+        let entry = ChunkEntry {
+            kind: chunk.kind,
+            offset: (offset + PREFIX_SIZE) as u64,
+            length: chunk.data.len() as u64,
+        };
+        offset = align(offset + PREFIX_SIZE + chunk.data.len());
+        entry
+    }).collect::<Vec<_>>();
+
+    let mut written = 0;
+    writer.write_u32::<LE>(chunks.len() as u32)?;
+    writer.write_u32::<LE>(0)?;
+    written += 8;
+    for entry in &entries {
+        writer.write_u32::<LE>(entry.kind.code())?;
+        writer.write_u32::<LE>(0)?;
+        writer.write_u64::<LE>(entry.offset)?;
+        writer.write_u64::<LE>(entry.length)?;
+        written += ENTRY_SIZE;
+    }
+    written = pad(writer, written)?;
+
+    for chunk in chunks {
+        writer.write_u64::<LE>(chunk.data.len() as u64)?;
+        writer.write_u64::<LE>(0)?;
+        writer.write_all(&chunk.data)?;
+        written = pad(writer, written + PREFIX_SIZE + chunk.data.len())?;
+    }
+
+    Ok(entries)
+}
+
+/// Reads back chunks written by `write_chunks`.
+pub fn read_chunks(bytes: &[u8]) -> Result<Vec<Chunk>> {
+    let invalid = || Error::Convert(ConvertError::InvalidContainer);
+    if bytes.len() < 8 {
+        return Err(invalid());
+    }
+
+    let count = LE::read_u32(&bytes[0..4]) as usize;
+    (0..count).map(|index| {
+        let start = 8 + index * ENTRY_SIZE;
+        let entry = bytes.get(start .. start + ENTRY_SIZE)
+            .ok_or_else(invalid)?;
+        let kind = ChunkKind::from_code(LE::read_u32(&entry[0..4]))
+            .ok_or_else(invalid)?;
+        let offset = LE::read_u64(&entry[8..16]) as usize;
+        let length = LE::read_u64(&entry[16..24]) as usize;
+        let data = offset.checked_add(length)
+            .and_then(|end| bytes.get(offset .. end))
+            .ok_or_else(invalid)?;
+
+        Ok(Chunk {
+            kind: kind,
+            data: data.to_vec(),
+        })
+    }).collect()
+}
+
+fn align(offset: usize) -> usize {
+    (offset + CHUNK_ALIGNMENT - 1) / CHUNK_ALIGNMENT * CHUNK_ALIGNMENT
+}
+
+/// Writes zeros up to the next alignment boundary.
+fn pad<W: Write>(writer: &mut W, written: usize) -> Result<usize> {
+    let aligned = align(written);
+    writer.write_all(&vec![0; aligned - written])?;
+
+    Ok(aligned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_chunks() {
+        let chunks = vec![
+            Chunk { kind: ChunkKind::Vertices, data: vec![1; 36] },
+            Chunk { kind: ChunkKind::Indices, data: vec![2; 12] },
+            Chunk { kind: ChunkKind::Texture, data: vec![3; 5] },
+        ];
+        let mut output = Vec::<u8>::new();
+        let entries = write_chunks(&mut output, &chunks).unwrap();
+
+        assert_eq!(LE::read_u32(&output[0..4]), 3);
+        assert_eq!(output.len() % CHUNK_ALIGNMENT, 0);
+        for (index, (entry, chunk)) in entries.iter().zip(chunks.iter()).enumerate() {
+            let directory = &output[8 + index * ENTRY_SIZE ..];
+            assert_eq!(LE::read_u32(&directory[0..4]), chunk.kind.code());
+            assert_eq!(LE::read_u64(&directory[8..16]), entry.offset);
+            assert_eq!(LE::read_u64(&directory[16..24]), entry.length);
+
+            let offset = entry.offset as usize;
+            assert_eq!(offset % CHUNK_ALIGNMENT, 0);
+            assert_eq!(LE::read_u64(&output[offset - PREFIX_SIZE ..]), chunk.data.len() as u64);
+            assert_eq!(&output[offset .. offset + chunk.data.len()], &chunk.data[..]);
+        }
+
+        assert_eq!(read_chunks(&output).unwrap(), chunks);
+        assert!(read_chunks(&output[..40]).is_err());
+    }
+}
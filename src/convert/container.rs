@@ -0,0 +1,130 @@
+use std::io::{Read, Write};
+
+use byteorder::{LE, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "compression")]
+use deflate::deflate_bytes_zlib;
+#[cfg(feature = "compression")]
+use inflate::inflate_bytes_zlib;
+
+use super::super::{Result, Error};
+use super::ConvertError;
+use super::chunk::{Chunk, read_chunks, write_chunks};
+use super::options::{Compression, Options};
+
+/// Bytes every serialized output starts with. They differ from
+/// `WG3D_MAGIC`, so neither reader mistakes the other format for its own.
+pub const MAGIC: &'static [u8; 4] = b"WG3C";
+
+/// Size in bytes of the header. Uncompressed chunks keep their alignment.
+pub const HEADER_SIZE: usize = 16;
+
+const METHOD_NONE: u8 = 0;
+#[cfg(feature = "compression")]
+const METHOD_DEFLATE: u8 = 1;
+
+/// Writes `chunks` behind a header, compressed with `Options::compression`.
+///
+/// The header holds `MAGIC`, the `u8` compression method, three reserved
+/// bytes and the `u64` length of the uncompressed chunks, little endian.
+pub fn write<'a, W: Write>(
+    writer: &'a mut W,
+    chunks: &'a [Chunk],
+    options: &'a Options,
+) -> Result<()> {
+    let mut bytes = Vec::<u8>::new();
+    write_chunks(&mut bytes, chunks)?;
+    let length = bytes.len() as u64;
+
+    let (method, payload) = match options.compression {
+        Compression::None => (METHOD_NONE, bytes),
+        #[cfg(feature = "compression")]
+        Compression::Deflate => (METHOD_DEFLATE, deflate_bytes_zlib(&bytes)),
+    };
+
+    writer.write_all(MAGIC)?;
+    writer.write_u8(method)?;
+    writer.write_all(&[0; 3])?;
+    writer.write_u64::<LE>(length)?;
+    writer.write_all(&payload)?;
+
+    Ok(())
+}
+
+/// Reads chunks written by `write`, decompressing them if needed.
+pub fn read<R: Read>(reader: &mut R) -> Result<Vec<Chunk>> {
+    let mut header = [0; HEADER_SIZE];
+    reader.read_exact(&mut header)
+        .map_err(|_| Error::Convert(ConvertError::InvalidContainer))?;
+    if &header[0..4] != MAGIC {
+        return Err(Error::Convert(ConvertError::InvalidContainer));
+    }
+    let method = header[4];
+    let length = (&header[8..16]).read_u64::<LE>()? as usize;
+
+    let mut payload = Vec::<u8>::new();
+    reader.read_to_end(&mut payload)?;
+
+    let bytes = match method {
+        METHOD_NONE => payload,
+        #[cfg(feature = "compression")]
+        METHOD_DEFLATE => {
+            inflate_bytes_zlib(&payload).map_err(|_| Error::Convert(ConvertError::InvalidContainer))?
+        },
+        _ => {
+            return Err(Error::Convert(ConvertError::UnsupportedCompression { method: method }));
+        },
+    };
+    if bytes.len() != length {
+        return Err(Error::Convert(ConvertError::InvalidContainer));
+    }
+
+    read_chunks(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use super::super::{get_with_stats, import};
+    use super::super::chunk::get_chunks;
+    use super::super::texture::get as get_textures;
+
+    fn write_to_vec(chunks: &[Chunk], compression: Compression) -> Vec<u8> {
+        let mut options = Options::default();
+        options.compression = compression;
+        let mut output = Vec::<u8>::new();
+        write(&mut output, chunks, &options).unwrap();
+        output
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn test_compression() {
+        let path = Path::new("testmodels/gltf2/Monster/Monster.gltf");
+        let parent = path.parent().unwrap();
+        let (conversion, _) = get_with_stats(path, &Options::default()).unwrap();
+        let (gltf, _, buffers) = import(path, parent).unwrap();
+        let textures = get_textures(parent, gltf.textures(), &buffers, &Options::default(), &mut Vec::new()).unwrap();
+        let chunks = get_chunks(conversion.models(), textures.textures());
+
+        let uncompressed = write_to_vec(&chunks, Compression::None);
+        let compressed = write_to_vec(&chunks, Compression::Deflate);
+
+        assert!(compressed.len() < uncompressed.len());
+        assert_eq!(read(&mut &uncompressed[..]).unwrap(), chunks);
+        assert_eq!(read(&mut &compressed[..]).unwrap(), chunks);
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        let mut output = write_to_vec(&[], Compression::None);
+        output[4] = 9;
+        match read(&mut &output[..]) {
+            Err(Error::Convert(ConvertError::UnsupportedCompression { method: 9 })) => {},
+            _ => panic!("Expected unsupported compression"),
+        }
+
+        assert!(read(&mut &b"glTF"[..]).is_err());
+    }
+}
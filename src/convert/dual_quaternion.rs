@@ -0,0 +1,40 @@
+use cgmath::{InnerSpace, Matrix3, Matrix4, Quaternion, Vector3};
+
+/// A rigid transform for dual quaternion skinning.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DualQuaternion {
+    /// The rotation.
+    pub real: Quaternion<f32>,
+    /// Half the translation times the rotation.
+    pub dual: Quaternion<f32>,
+}
+
+impl DualQuaternion {
+    pub fn from_rotation_translation(
+        rotation: Quaternion<f32>,
+        translation: Vector3<f32>,
+    ) -> DualQuaternion {
+        let real = rotation.normalize();
+        let dual = Quaternion::from_sv(0.0, translation) * real * 0.5;
+
+        DualQuaternion {
+            real: real,
+            dual: dual,
+        }
+    }
+
+    /// Decomposes a rigid transform. Any scale is dropped.
+    pub fn from_matrix(matrix: Matrix4<f32>) -> DualQuaternion {
+        let rotation = Matrix3::from_cols(
+            matrix.x.truncate().normalize(),
+            matrix.y.truncate().normalize(),
+            matrix.z.truncate().normalize(),
+        );
+
+        DualQuaternion::from_rotation_translation(Quaternion::from(rotation), matrix.w.truncate())
+    }
+
+    pub fn translation(&self) -> Vector3<f32> {
+        (self.dual * self.real.conjugate() * 2.0).v
+    }
+}
@@ -0,0 +1,80 @@
+use gltf::json::Value;
+
+/// The raw glTF JSON, kept for reading extensions `gltf` does not parse.
+pub struct Extensions {
+    root: Value,
+}
+
+impl Extensions {
+    pub fn new(root: Value) -> Extensions {
+        Extensions {
+            root: root,
+        }
+    }
+
+    /// Returns the named extension object of the glTF root.
+    pub fn root(&self, name: &str) -> Option<&Value> {
+        self.root.get("extensions")?.get(name)
+    }
+
+    /// Whether the glTF lists the extension as required to load it.
+    pub fn is_required(&self, name: &str) -> bool {
+        self.root.get("extensionsRequired")
+            .and_then(Value::as_array)
+            .map_or(false, |required| required.iter().any(|extension| extension.as_str() == Some(name)))
+    }
+
+    /// Returns the named extension object of the buffer at `index`.
+    pub fn buffer(&self, index: usize, name: &str) -> Option<&Value> {
+        self.get("buffers", index, name)
+    }
+
+    /// Returns the named extension object of the buffer view at `index`.
+    pub fn view(&self, index: usize, name: &str) -> Option<&Value> {
+        self.get("bufferViews", index, name)
+    }
+
+    /// Returns the named extension object of the material at `index`.
+    pub fn material(&self, index: usize, name: &str) -> Option<&Value> {
+        self.get("materials", index, name)
+    }
+
+    /// Returns the named extension object of the node at `index`.
+    pub fn node(&self, index: usize, name: &str) -> Option<&Value> {
+        self.get("nodes", index, name)
+    }
+
+    fn get(&self, array: &str, index: usize, name: &str) -> Option<&Value> {
+        self.root.get(array)?.get(index)?.get("extensions")?.get(name)
+    }
+}
+
+/// Reads the number `key` of an extension object.
+pub fn get_f32<'a>(extension: &'a Value, key: &'a str) -> Option<f32> {
+    extension.get(key).and_then(Value::as_f64).map(|value| value as f32)
+}
+
+/// Reads the three element number array `key` of an extension object.
+pub fn get_f32_3<'a>(extension: &'a Value, key: &'a str) -> Option<[f32; 3]> {
+    let array = extension.get(key)?.as_array()?;
+
+    if array.len() == 3 {
+        Some([
+            array[0].as_f64()? as f32,
+            array[1].as_f64()? as f32,
+            array[2].as_f64()? as f32,
+        ])
+    } else {
+        None
+    }
+}
+
+/// Reads the `textureInfo` object `key` of an extension object, returning
+/// the texture index and texture coordinate set.
+pub fn get_texture_info<'a>(extension: &'a Value, key: &'a str) -> Option<(usize, u32)> {
+    let info = extension.get(key)?;
+    let index = info.get("index")?.as_u64()? as usize;
+    let tex_coord = info.get("texCoord").and_then(Value::as_u64).unwrap_or(0) as u32;
+
+    Some((index, tex_coord))
+}
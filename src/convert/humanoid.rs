@@ -0,0 +1,196 @@
+use std::collections::BTreeMap;
+
+use gltf::json::Value;
+
+use super::extension::Extensions;
+use super::skin::Skin;
+
+/// Maps standard humanoid bones to joint indices of a skin.
+pub type HumanoidMap = BTreeMap<HumanBone, u16>;
+
+/// Humanoid bones defined by VRM.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum HumanBone {
+    Hips,
+    Spine,
+    Chest,
+    UpperChest,
+    Neck,
+    Head,
+    LeftEye,
+    RightEye,
+    Jaw,
+    LeftUpperLeg,
+    LeftLowerLeg,
+    LeftFoot,
+    LeftToes,
+    RightUpperLeg,
+    RightLowerLeg,
+    RightFoot,
+    RightToes,
+    LeftShoulder,
+    LeftUpperArm,
+    LeftLowerArm,
+    LeftHand,
+    RightShoulder,
+    RightUpperArm,
+    RightLowerArm,
+    RightHand,
+    LeftThumbMetacarpal,
+    LeftThumbProximal,
+    LeftThumbDistal,
+    LeftIndexProximal,
+    LeftIndexIntermediate,
+    LeftIndexDistal,
+    LeftMiddleProximal,
+    LeftMiddleIntermediate,
+    LeftMiddleDistal,
+    LeftRingProximal,
+    LeftRingIntermediate,
+    LeftRingDistal,
+    LeftLittleProximal,
+    LeftLittleIntermediate,
+    LeftLittleDistal,
+    RightThumbMetacarpal,
+    RightThumbProximal,
+    RightThumbDistal,
+    RightIndexProximal,
+    RightIndexIntermediate,
+    RightIndexDistal,
+    RightMiddleProximal,
+    RightMiddleIntermediate,
+    RightMiddleDistal,
+    RightRingProximal,
+    RightRingIntermediate,
+    RightRingDistal,
+    RightLittleProximal,
+    RightLittleIntermediate,
+    RightLittleDistal,
+}
+
+/// VRM 1.0 bone names.
+const BONES: &'static [(&'static str, HumanBone)] = &[
+    ("hips", HumanBone::Hips),
+    ("spine", HumanBone::Spine),
+    ("chest", HumanBone::Chest),
+    ("upperChest", HumanBone::UpperChest),
+    ("neck", HumanBone::Neck),
+    ("head", HumanBone::Head),
+    ("leftEye", HumanBone::LeftEye),
+    ("rightEye", HumanBone::RightEye),
+    ("jaw", HumanBone::Jaw),
+    ("leftUpperLeg", HumanBone::LeftUpperLeg),
+    ("leftLowerLeg", HumanBone::LeftLowerLeg),
+    ("leftFoot", HumanBone::LeftFoot),
+    ("leftToes", HumanBone::LeftToes),
+    ("rightUpperLeg", HumanBone::RightUpperLeg),
+    ("rightLowerLeg", HumanBone::RightLowerLeg),
+    ("rightFoot", HumanBone::RightFoot),
+    ("rightToes", HumanBone::RightToes),
+    ("leftShoulder", HumanBone::LeftShoulder),
+    ("leftUpperArm", HumanBone::LeftUpperArm),
+    ("leftLowerArm", HumanBone::LeftLowerArm),
+    ("leftHand", HumanBone::LeftHand),
+    ("rightShoulder", HumanBone::RightShoulder),
+    ("rightUpperArm", HumanBone::RightUpperArm),
+    ("rightLowerArm", HumanBone::RightLowerArm),
+    ("rightHand", HumanBone::RightHand),
+    ("leftThumbMetacarpal", HumanBone::LeftThumbMetacarpal),
+    ("leftThumbProximal", HumanBone::LeftThumbProximal),
+    ("leftThumbDistal", HumanBone::LeftThumbDistal),
+    ("leftIndexProximal", HumanBone::LeftIndexProximal),
+    ("leftIndexIntermediate", HumanBone::LeftIndexIntermediate),
+    ("leftIndexDistal", HumanBone::LeftIndexDistal),
+    ("leftMiddleProximal", HumanBone::LeftMiddleProximal),
+    ("leftMiddleIntermediate", HumanBone::LeftMiddleIntermediate),
+    ("leftMiddleDistal", HumanBone::LeftMiddleDistal),
+    ("leftRingProximal", HumanBone::LeftRingProximal),
+    ("leftRingIntermediate", HumanBone::LeftRingIntermediate),
+    ("leftRingDistal", HumanBone::LeftRingDistal),
+    ("leftLittleProximal", HumanBone::LeftLittleProximal),
+    ("leftLittleIntermediate", HumanBone::LeftLittleIntermediate),
+    ("leftLittleDistal", HumanBone::LeftLittleDistal),
+    ("rightThumbMetacarpal", HumanBone::RightThumbMetacarpal),
+    ("rightThumbProximal", HumanBone::RightThumbProximal),
+    ("rightThumbDistal", HumanBone::RightThumbDistal),
+    ("rightIndexProximal", HumanBone::RightIndexProximal),
+    ("rightIndexIntermediate", HumanBone::RightIndexIntermediate),
+    ("rightIndexDistal", HumanBone::RightIndexDistal),
+    ("rightMiddleProximal", HumanBone::RightMiddleProximal),
+    ("rightMiddleIntermediate", HumanBone::RightMiddleIntermediate),
+    ("rightMiddleDistal", HumanBone::RightMiddleDistal),
+    ("rightRingProximal", HumanBone::RightRingProximal),
+    ("rightRingIntermediate", HumanBone::RightRingIntermediate),
+    ("rightRingDistal", HumanBone::RightRingDistal),
+    ("rightLittleProximal", HumanBone::RightLittleProximal),
+    ("rightLittleIntermediate", HumanBone::RightLittleIntermediate),
+    ("rightLittleDistal", HumanBone::RightLittleDistal),
+];
+
+/// VRM 0.x names its thumb bones one joint further down the hand.
+const VRM0_THUMBS: &'static [(&'static str, HumanBone)] = &[
+    ("leftThumbProximal", HumanBone::LeftThumbMetacarpal),
+    ("leftThumbIntermediate", HumanBone::LeftThumbProximal),
+    ("leftThumbDistal", HumanBone::LeftThumbDistal),
+    ("rightThumbProximal", HumanBone::RightThumbMetacarpal),
+    ("rightThumbIntermediate", HumanBone::RightThumbProximal),
+    ("rightThumbDistal", HumanBone::RightThumbDistal),
+];
+
+impl HumanBone {
+    /// Looks up a bone by its VRM 1.0 name.
+    pub fn from_name(name: &str) -> Option<HumanBone> {
+        find(BONES, name)
+    }
+}
+
+fn find(table: &[(&str, HumanBone)], name: &str) -> Option<HumanBone> {
+    table.iter().find(|&&(bone_name, _)| bone_name == name).map(|&(_, bone)| bone)
+}
+
+/// Reads the VRM humanoid bone map, keeping only bones that are joints of
+/// `skin`. Both `VRMC_vrm` (VRM 1.0) and `VRM` (VRM 0.x) are supported.
+pub fn get<'a>(
+    extensions: &'a Extensions,
+    skin: &'a Skin,
+) -> HumanoidMap {
+    let mut map = HumanoidMap::new();
+
+    if let Some(bones) = extensions.root("VRMC_vrm")
+        .and_then(|vrm| vrm.get("humanoid"))
+        .and_then(|humanoid| humanoid.get("humanBones"))
+        .and_then(Value::as_object)
+    {
+        for (name, bone) in bones.iter() {
+            let node = bone.get("node").and_then(Value::as_u64);
+            insert(&mut map, skin, HumanBone::from_name(name), node);
+        }
+    } else if let Some(bones) = extensions.root("VRM")
+        .and_then(|vrm| vrm.get("humanoid"))
+        .and_then(|humanoid| humanoid.get("humanBones"))
+        .and_then(Value::as_array)
+    {
+        for bone in bones.iter() {
+            let bone_type = bone.get("bone").and_then(Value::as_str).and_then(|name| {
+                find(VRM0_THUMBS, name).or_else(|| HumanBone::from_name(name))
+            });
+            let node = bone.get("node").and_then(Value::as_u64);
+            insert(&mut map, skin, bone_type, node);
+        }
+    }
+
+    map
+}
+
+fn insert<'a>(
+    map: &'a mut HumanoidMap,
+    skin: &'a Skin,
+    bone: Option<HumanBone>,
+    node: Option<u64>,
+) {
+    if let (Some(bone), Some(node)) = (bone, node) {
+        if let Some(joint_index) = skin.get_joint_index(node as usize) {
+            map.insert(bone, joint_index);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use std::io::Cursor;
+
+use byteorder::{LE, ReadBytesExt};
+
+use super::super::{Result, Error};
+use super::ConvertError;
+use super::texture::Format;
+
+const IDENTIFIER: &'static [u8] = b"\xABKTX 20\xBB\r\n\x1A\n";
+
+/// Block compressed contents of a KTX2 texture.
+pub struct Ktx2 {
+    pub format: Format,
+    pub width: u32,
+    pub height: u32,
+    /// Blocks of the base mip level.
+    pub contents: Vec<u8>,
+    /// Blocks of each smaller mip level the container provides, largest
+    /// first.
+    pub mip_levels: Vec<Vec<u8>>,
+}
+
+pub fn is_ktx2(data: &[u8]) -> bool {
+    data.starts_with(IDENTIFIER)
+}
+
+/// Reads a KTX2 container holding BC1-BC7 blocks, keeping them compressed.
+pub fn get(data: &[u8]) -> Result<Ktx2> {
+    if !is_ktx2(data) {
+        return Err(Error::Convert(ConvertError::UnsupportedKtx2));
+    }
+
+    let mut cursor = Cursor::new(&data[IDENTIFIER.len()..]);
+    let vk_format = cursor.read_u32::<LE>()?;
+    let _type_size = cursor.read_u32::<LE>()?;
+    let width = cursor.read_u32::<LE>()?;
+    let height = cursor.read_u32::<LE>()?;
+    let _depth = cursor.read_u32::<LE>()?;
+    let _layer_count = cursor.read_u32::<LE>()?;
+    let _face_count = cursor.read_u32::<LE>()?;
+    let level_count = cursor.read_u32::<LE>()?;
+    let supercompression_scheme = cursor.read_u32::<LE>()?;
+
+    // Skip the data format descriptor, key/value data and supercompression
+    // global data indices.
+    for _ in 0..4 {
+        cursor.read_u32::<LE>()?;
+    }
+    for _ in 0..2 {
+        cursor.read_u64::<LE>()?;
+    }
+
+    // Level 0 is the base level. A level count of zero asks the loader to
+    // generate the mip chain, so only the base level is stored.
+    let levels = (0..level_count.max(1)).map(|_| {
+        let offset = cursor.read_u64::<LE>()? as usize;
+        let length = cursor.read_u64::<LE>()? as usize;
+        let _uncompressed_length = cursor.read_u64::<LE>()?;
+        Ok((offset, length))
+    }).collect::<Result<Vec<_>>>()?;
+
+    let format = match vk_format {
+        131 | 132 | 133 | 134 => Format::Bc1,
+        135 | 136 => Format::Bc2,
+        137 | 138 => Format::Bc3,
+        139 | 140 => Format::Bc4,
+        141 | 142 => Format::Bc5,
+        143 | 144 => Format::Bc6,
+        145 | 146 => Format::Bc7,
+        _ => { return Err(Error::Convert(ConvertError::UnsupportedKtx2)); },
+    };
+    if supercompression_scheme != 0 {
+        return Err(Error::Convert(ConvertError::UnsupportedKtx2));
+    }
+
+    let mut contents = levels.iter().map(|&(offset, length)| {
+        data.get(offset..offset + length)
+            .map(|level| level.to_vec())
+            .ok_or(Error::Convert(ConvertError::UnsupportedKtx2))
+    }).collect::<Result<Vec<_>>>()?;
+    let mip_levels = contents.split_off(1);
+
+    Ok(Ktx2 {
+        format: format,
+        width: width,
+        height: height,
+        contents: contents.remove(0),
+        mip_levels: mip_levels,
+    })
+}
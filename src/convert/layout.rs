@@ -0,0 +1,95 @@
+use byteorder::{LE, WriteBytesExt};
+use cgmath::{Vector2, Vector3, Vector4};
+
+/// Describes how vertex attributes are interleaved in a vertex buffer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VertexLayout {
+    /// Size in bytes of one vertex.
+    pub stride: usize,
+    pub attributes: Vec<VertexAttribute>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct VertexAttribute {
+    pub semantic: Semantic,
+    pub format: AttributeFormat,
+    /// Byte offset of the attribute from the start of a vertex.
+    pub offset: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Semantic {
+    Position,
+    Normal,
+    TexCoord0,
+    TexCoord1,
+    Tangent,
+    Joints,
+    Weights,
+    Color,
+}
+
+/// Little endian component types of an attribute.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AttributeFormat {
+    F32x2,
+    F32x3,
+    F32x4,
+    U16x4,
+}
+
+impl AttributeFormat {
+    pub fn size(self) -> usize {
+        match self {
+            AttributeFormat::F32x2 => 8,
+            AttributeFormat::F32x3 => 12,
+            AttributeFormat::F32x4 => 16,
+            AttributeFormat::U16x4 => 8,
+        }
+    }
+}
+
+impl VertexLayout {
+    /// Packs `attributes` tightly in the given order.
+    pub fn new(attributes: &[(Semantic, AttributeFormat)]) -> VertexLayout {
+        let mut offset = 0;
+        let attributes = attributes.iter().map(|&(semantic, format)| {
+            let attribute = VertexAttribute {
+                semantic: semantic,
+                format: format,
+                offset: offset,
+            };
+            offset += format.size();
+            attribute
+        }).collect::<Vec<_>>();
+
+        VertexLayout {
+            stride: offset,
+            attributes: attributes,
+        }
+    }
+}
+
+pub fn write_vec2(bytes: &mut Vec<u8>, vector: &Vector2<f32>) {
+    for &value in [vector.x, vector.y].iter() {
+        bytes.write_f32::<LE>(value).unwrap();
+    }
+}
+
+pub fn write_vec3(bytes: &mut Vec<u8>, vector: &Vector3<f32>) {
+    for &value in [vector.x, vector.y, vector.z].iter() {
+        bytes.write_f32::<LE>(value).unwrap();
+    }
+}
+
+pub fn write_vec4(bytes: &mut Vec<u8>, vector: &Vector4<f32>) {
+    for &value in [vector.x, vector.y, vector.z, vector.w].iter() {
+        bytes.write_f32::<LE>(value).unwrap();
+    }
+}
+
+pub fn write_u16x4(bytes: &mut Vec<u8>, vector: &Vector4<u16>) {
+    for &value in [vector.x, vector.y, vector.z, vector.w].iter() {
+        bytes.write_u16::<LE>(value).unwrap();
+    }
+}
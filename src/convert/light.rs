@@ -0,0 +1,194 @@
+use std::f32::consts::FRAC_PI_4;
+
+use cgmath::{Matrix4, SquareMatrix};
+use gltf::{Node, Scene};
+use gltf::json::Value;
+
+use super::extension::{Extensions, get_f32, get_f32_3};
+use super::{is_hidden, local_transform};
+use super::options::Options;
+
+const LIGHTS_EXTENSION: &'static str = "KHR_lights_punctual";
+
+/// A `KHR_lights_punctual` light placed in the scene by a visible node.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Light {
+    name: Option<String>,
+    node_index: usize,
+    kind: LightKind,
+    color: [f32; 3],
+    intensity: f32,
+    range: Option<f32>,
+    world_transform: Matrix4<f32>,
+}
+
+impl Light {
+    /// Name of the light, not of its node.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_ref().map(String::as_str)
+    }
+
+    /// Index of the light's node in the glTF nodes.
+    pub fn node_index(&self) -> usize {
+        self.node_index
+    }
+
+    pub fn kind(&self) -> LightKind {
+        self.kind
+    }
+
+    /// Linear RGB color.
+    pub fn color(&self) -> [f32; 3] {
+        self.color
+    }
+
+    /// Lux for directional lights, candela for point and spot lights.
+    pub fn intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    /// Distance at which the light stops, `None` for no cutoff. Directional
+    /// lights have no range.
+    pub fn range(&self) -> Option<f32> {
+        self.range
+    }
+
+    /// World transform of the light's node. Directional and spot lights
+    /// shine down its local -Z axis.
+    pub fn world_transform(&self) -> &Matrix4<f32> {
+        &self.world_transform
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LightKind {
+    Directional,
+    Point,
+    Spot {
+        /// Angles from the spot direction in radians, where the falloff
+        /// starts and ends.
+        inner_cone_angle: f32,
+        outer_cone_angle: f32,
+    },
+}
+
+/// Collects the lights of the visible nodes in `scene`, in depth first
+/// order. Files without `KHR_lights_punctual` have none.
+pub fn get<'a>(
+    scene: &'a Scene,
+    extensions: &'a Extensions,
+    options: &'a Options,
+) -> Vec<Light> {
+    let definitions = match extensions.root(LIGHTS_EXTENSION).and_then(|root| root.get("lights")).and_then(Value::as_array) {
+        Some(definitions) => definitions,
+        None => { return Vec::new(); },
+    };
+
+    let mut lights = Vec::<Light>::new();
+    for node in scene.nodes() {
+        get_helper(&node, &Matrix4::identity(), definitions, &mut lights, extensions, options);
+    }
+
+    lights
+}
+
+fn get_helper<'a>(
+    node: &'a Node,
+    parent_transform: &'a Matrix4<f32>,
+    definitions: &'a [Value],
+    lights: &'a mut Vec<Light>,
+    extensions: &'a Extensions,
+    options: &'a Options,
+) {
+    if is_hidden(node, extensions, options) {
+        return;
+    }
+
+    let world_transform = parent_transform * local_transform(node, options);
+    let definition = extensions.node(node.index(), LIGHTS_EXTENSION)
+        .and_then(|extension| extension.get("light"))
+        .and_then(Value::as_u64)
+        .and_then(|index| definitions.get(index as usize));
+    if let Some(light) = definition.and_then(|definition| get_light(definition, node.index(), world_transform)) {
+        lights.push(light);
+    }
+
+    for child in node.children() {
+        get_helper(&child, &world_transform, definitions, lights, extensions, options);
+    }
+}
+
+/// Reads a light definition, filling in the defaults of the extension.
+/// Lights of unknown types are skipped.
+fn get_light(definition: &Value, node_index: usize, world_transform: Matrix4<f32>) -> Option<Light> {
+    let kind = match definition.get("type").and_then(Value::as_str) {
+        Some("directional") => LightKind::Directional,
+        Some("point") => LightKind::Point,
+        Some("spot") => {
+            let spot = definition.get("spot");
+            LightKind::Spot {
+                inner_cone_angle: spot.and_then(|spot| get_f32(spot, "innerConeAngle")).unwrap_or(0.0),
+                outer_cone_angle: spot.and_then(|spot| get_f32(spot, "outerConeAngle")).unwrap_or(FRAC_PI_4),
+            }
+        },
+        _ => { return None; },
+    };
+
+    Some(Light {
+        name: definition.get("name").and_then(Value::as_str).map(String::from),
+        node_index: node_index,
+        kind: kind,
+        color: get_f32_3(definition, "color").unwrap_or([1.0, 1.0, 1.0]),
+        intensity: get_f32(definition, "intensity").unwrap_or(1.0),
+        range: match kind {
+            LightKind::Directional => None,
+            _ => get_f32(definition, "range"),
+        },
+        world_transform: world_transform,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use cgmath::{InnerSpace, Vector4};
+
+    use super::*;
+    use super::super::get;
+
+    #[test]
+    fn test_lights() {
+        let path = Path::new("testmodels/gltf2/Lights/Lights.gltf");
+        let conversion = get(path).unwrap();
+        let lights = conversion.lights();
+
+        assert_eq!(lights.len(), 3);
+        assert_eq!(lights[0].name(), Some("Sun"));
+        assert_eq!(lights[0].kind(), LightKind::Directional);
+        assert_eq!(lights[0].intensity(), 3.0);
+        assert_eq!(lights[0].range(), None);
+
+        assert_eq!(lights[1].name(), Some("Point"));
+        assert_eq!(lights[1].kind(), LightKind::Point);
+        assert_eq!(lights[1].color(), [1.0, 0.5, 0.25]);
+        assert_eq!(lights[1].range(), Some(20.0));
+        assert_eq!(lights[1].world_transform() * Vector4::new(0.0, 0.0, 0.0, 1.0), Vector4::new(4.0, 5.0, 1.0, 1.0));
+
+        // The spot light gives no cone angles, so it gets the defaults, and
+        // shines straight down from its node.
+        assert_eq!(lights[2].name(), Some("Spot"));
+        assert_eq!(lights[2].kind(), LightKind::Spot {
+            inner_cone_angle: 0.0,
+            outer_cone_angle: FRAC_PI_4,
+        });
+        assert_eq!(lights[2].intensity(), 1.0);
+        assert_eq!(lights[2].range(), None);
+        let direction = lights[2].world_transform() * Vector4::new(0.0, 0.0, -1.0, 0.0);
+        assert!((direction - Vector4::new(0.0, -1.0, 0.0, 0.0)).magnitude() < 1e-5);
+
+        // Files without the extension have no lights.
+        let path = Path::new("testmodels/gltf2/Cameras/Cameras.gltf");
+        assert!(get(path).unwrap().lights().is_empty());
+    }
+}
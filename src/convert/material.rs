@@ -1,26 +1,232 @@
 use gltf::gltf::Materials as GltfMaterials;
 use gltf::material::{AlphaMode as GltfAlphaMode, Material as GltfMaterial};
 
-use super::super::Result;
-use super::ConvertError;
-use super::texture::Textures;
+use gltf::json::Value;
+
+use super::super::{Result, Error};
+use super::{ConvertError, ConvertWarning};
+use super::extension::{Extensions, get_f32, get_f32_3, get_texture_info};
+use super::texture::{Texture, Textures, apply_occlusion_strength};
+use super::util::name_id;
 
 pub struct Materials {
     materials: Vec<Material>,
+    /// Position in `materials` of each glTF material.
+    indices: Vec<usize>,
 }
 
 impl Materials {
     pub fn get(&self, index: usize) -> Option<&str> {
-        match self.materials.iter().nth(index) {
+        match self.indices.get(index).and_then(|&position| self.materials.get(position)) {
             Some(material) => Some(material.name.as_ref()),
             None => None,
         }
     }
+
+    pub fn materials(&self) -> &[Material] {
+        &self.materials
+    }
+
+    pub fn into_materials(self) -> Vec<Material> {
+        self.materials
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&Material> {
+        self.materials.iter().find(|material| material.name == name)
+    }
+
+    /// Lists the material slots every texture feeds, in texture order.
+    pub fn texture_bindings(&self, textures: &Textures) -> Vec<TextureBinding> {
+        bind_textures(&self.materials, textures.textures())
+    }
+
+    /// Points every slot sampling a texture renamed in `names`, as pairs of
+    /// old and new names, at the new name.
+    pub fn rename_textures(&mut self, names: &[(String, String)]) {
+        for material in self.materials.iter_mut() {
+            for (_, texture) in material.texture_names_mut() {
+                if let Some(&(_, ref new_name)) = names.iter().find(|&&(ref old_name, _)| old_name == texture) {
+                    *texture = new_name.clone();
+                }
+            }
+        }
+    }
+
+    /// Makes sure the material at `index` only samples texture coordinate
+    /// sets below `set_count`. With `downgrade`, textures using a missing set
+    /// are rebound to set 0 with a warning instead of failing.
+    pub fn check_tex_coords<'a>(
+        &'a mut self,
+        index: usize,
+        set_count: u32,
+        downgrade: bool,
+        warnings: &'a mut Vec<ConvertWarning>,
+    ) -> Result<()> {
+        let position = match self.indices.get(index) {
+            Some(&position) => position,
+            None => { return Ok(()); },
+        };
+        let material = match self.materials.get_mut(position) {
+            Some(material) => material,
+            None => { return Ok(()); },
+        };
+        let name = material.name.clone();
+
+        for (slot, tex_coord) in material.tex_coords_mut() {
+            if *tex_coord < set_count {
+                continue;
+            }
+
+            if downgrade && set_count > 0 {
+                warnings.push(ConvertWarning::TexCoordDowngraded {
+                    material: name.clone(),
+                    slot: slot,
+                    set: *tex_coord,
+                });
+                *tex_coord = 0;
+            } else {
+                return Err(Error::Convert(ConvertError::MissingTexCoordSet {
+                    material: name,
+                    slot: slot,
+                    set: *tex_coord,
+                }));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Bakes the strength of every occlusion map into its texture and resets
+    /// it to 1. Textures shared by occlusion maps of different strengths,
+    /// and textures whose format the strength cannot be applied to, are left
+    /// alone along with their maps; the latter with
+    /// `ConvertWarning::OcclusionStrengthNotBaked`.
+    pub fn bake_occlusion_strength<'a>(
+        &'a mut self,
+        textures: &'a mut Textures,
+        warnings: &'a mut Vec<ConvertWarning>,
+    ) {
+        let mut baked = Vec::<String>::new();
+
+        for material in self.materials.iter() {
+            let (name, strength) = match material.occlusion_map {
+                Some(ref map) => (&map.name, map.strength),
+                None => { continue; },
+            };
+            if strength == 1.0 || baked.contains(name) {
+                continue;
+            }
+
+            let shared_strengths = self.materials.iter()
+                .filter_map(|other| other.occlusion_map.as_ref())
+                .filter(|other| other.name == *name)
+                .all(|other| other.strength == strength);
+            if !shared_strengths {
+                continue;
+            }
+
+            if let Some(texture) = textures.by_name_mut(name) {
+                if texture.bake_occlusion_strength(strength) {
+                    baked.push(name.clone());
+                } else {
+                    warnings.push(ConvertWarning::OcclusionStrengthNotBaked { texture: name.clone() });
+                }
+            }
+        }
+
+        for material in self.materials.iter_mut() {
+            if let Some(ref mut map) = material.occlusion_map {
+                if baked.contains(&map.name) {
+                    map.strength = 1.0;
+                }
+            }
+        }
+    }
+
+    /// Flips the green channel of every normal map texture once, for
+    /// consumers expecting DirectX normal maps. Textures whose format the
+    /// channel cannot be flipped in are left as they are, with
+    /// `ConvertWarning::NormalMapNotFlipped`.
+    pub fn flip_normal_maps<'a>(&'a self, textures: &'a mut Textures, warnings: &'a mut Vec<ConvertWarning>) {
+        let mut names = self.materials.iter()
+            .filter_map(|material| material.normal_map.as_ref())
+            .map(|map| map.name.as_str())
+            .collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+
+        for name in names {
+            if let Some(texture) = textures.by_name_mut(name) {
+                if !texture.flip_green() {
+                    warnings.push(ConvertWarning::NormalMapNotFlipped { texture: String::from(name) });
+                }
+            }
+        }
+    }
+
+    /// Adds the glTF default material under `name`, unless a material of that
+    /// name exists.
+    pub fn add_default(&mut self, name: &str) {
+        if self.by_name(name).is_some() {
+            return;
+        }
+
+        self.materials.push(Material {
+            name: name.to_owned(),
+            name_id: None,
+            alpha_cutoff: None,
+            alpha_mode: AlphaMode::Opaque,
+            double_sided: false,
+            base_color: BaseColor::Factor([1.0, 1.0, 1.0, 1.0]),
+            metal_roughness: MetallicRoughness::Factor {
+                metallicity: 1.0,
+                roughness: 1.0,
+            },
+            normal_map: None,
+            occlusion_map: None,
+            emission_map: None,
+            sheen: None,
+            anisotropy: None,
+            iridescence: None,
+            dispersion: None,
+        });
+    }
+
+    /// Stores the hashed ID of every material's name alongside it.
+    pub fn assign_name_ids(&mut self) {
+        for material in self.materials.iter_mut() {
+            material.name_id = Some(name_id(&material.name));
+        }
+    }
+
+    /// Merges materials that are identical but for their name into the
+    /// first of them, so primitives using any of them share its name.
+    pub fn dedup(&mut self) {
+        let mut kept = Vec::<Material>::new();
+        let mut positions = Vec::<usize>::with_capacity(self.materials.len());
+
+        for material in self.materials.drain(..) {
+            match kept.iter().position(|other| *other == material) {
+                Some(position) => positions.push(position),
+                None => {
+                    positions.push(kept.len());
+                    kept.push(material);
+                },
+            }
+        }
+
+        self.indices = self.indices.iter().map(|&index| positions[index]).collect();
+        self.materials = kept;
+    }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Material {
     name: String,
-    alpha_cutoff: f32,
+    /// Hashed ID of the name, with `Options::name_ids`.
+    name_id: Option<u64>,
+    /// Only present for `AlphaMode::Mask`, the one mode it applies to.
+    alpha_cutoff: Option<f32>,
     alpha_mode: AlphaMode,
     double_sided: bool,
     base_color: BaseColor,
@@ -28,36 +234,278 @@ pub struct Material {
     normal_map: Option<NormalMap>,
     occlusion_map: Option<OcclusionMap>,
     emission_map: Option<EmissionMap>,
+    sheen: Option<Sheen>,
+    anisotropy: Option<Anisotropy>,
+    iridescence: Option<Iridescence>,
+    /// `KHR_materials_dispersion` strength, 0 for none.
+    dispersion: Option<f32>,
 }
 
+impl Material {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Renames the material, rehashing its name ID if it has one.
+    pub fn rename(&mut self, name: String) {
+        if self.name_id.is_some() {
+            self.name_id = Some(name_id(&name));
+        }
+        self.name = name;
+    }
+
+    pub fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+
+    pub fn double_sided(&self) -> bool {
+        self.double_sided
+    }
+
+    pub fn name_id(&self) -> Option<u64> {
+        self.name_id
+    }
+
+    /// Dispersion of light passing through the volume, from
+    /// `KHR_materials_dispersion`. `None` without the extension.
+    pub fn dispersion(&self) -> Option<f32> {
+        self.dispersion
+    }
+
+    /// Returns the name of every texture, by slot.
+    pub fn texture_names(&self) -> Vec<(&'static str, &str)> {
+        let mut names = Vec::<(&'static str, &str)>::new();
+
+        if let BaseColor::Texture { ref name, .. } = self.base_color {
+            names.push(("base_color", name));
+        }
+        if let MetallicRoughness::Texture { ref name, .. } = self.metal_roughness {
+            names.push(("metallic_roughness", name));
+        }
+        if let Some(NormalMap { ref name, .. }) = self.normal_map {
+            names.push(("normal", name));
+        }
+        if let Some(OcclusionMap { ref name, .. }) = self.occlusion_map {
+            names.push(("occlusion", name));
+        }
+        if let Some(EmissionMap::Texture { ref name, .. }) = self.emission_map {
+            names.push(("emission", name));
+        }
+        if let Some(ref sheen) = self.sheen {
+            if let Some(TextureInfo { ref name, .. }) = sheen.color_texture {
+                names.push(("sheen_color", name));
+            }
+            if let Some(TextureInfo { ref name, .. }) = sheen.roughness_texture {
+                names.push(("sheen_roughness", name));
+            }
+        }
+        if let Some(Anisotropy { texture: Some(TextureInfo { ref name, .. }), .. }) = self.anisotropy {
+            names.push(("anisotropy", name));
+        }
+        if let Some(ref iridescence) = self.iridescence {
+            if let Some(TextureInfo { ref name, .. }) = iridescence.texture {
+                names.push(("iridescence", name));
+            }
+            if let Some(TextureInfo { ref name, .. }) = iridescence.thickness_texture {
+                names.push(("iridescence_thickness", name));
+            }
+        }
+
+        names
+    }
+
+    /// Returns the name of every texture for renaming, by slot.
+    fn texture_names_mut(&mut self) -> Vec<(&'static str, &mut String)> {
+        let mut names = Vec::<(&'static str, &mut String)>::new();
+
+        if let BaseColor::Texture { ref mut name, .. } = self.base_color {
+            names.push(("base_color", name));
+        }
+        if let MetallicRoughness::Texture { ref mut name, .. } = self.metal_roughness {
+            names.push(("metallic_roughness", name));
+        }
+        if let Some(NormalMap { ref mut name, .. }) = self.normal_map {
+            names.push(("normal", name));
+        }
+        if let Some(OcclusionMap { ref mut name, .. }) = self.occlusion_map {
+            names.push(("occlusion", name));
+        }
+        if let Some(EmissionMap::Texture { ref mut name, .. }) = self.emission_map {
+            names.push(("emission", name));
+        }
+        if let Some(ref mut sheen) = self.sheen {
+            if let Some(TextureInfo { ref mut name, .. }) = sheen.color_texture {
+                names.push(("sheen_color", name));
+            }
+            if let Some(TextureInfo { ref mut name, .. }) = sheen.roughness_texture {
+                names.push(("sheen_roughness", name));
+            }
+        }
+        if let Some(Anisotropy { texture: Some(TextureInfo { ref mut name, .. }), .. }) = self.anisotropy {
+            names.push(("anisotropy", name));
+        }
+        if let Some(ref mut iridescence) = self.iridescence {
+            if let Some(TextureInfo { ref mut name, .. }) = iridescence.texture {
+                names.push(("iridescence", name));
+            }
+            if let Some(TextureInfo { ref mut name, .. }) = iridescence.thickness_texture {
+                names.push(("iridescence_thickness", name));
+            }
+        }
+
+        names
+    }
+
+    /// Returns the texture coordinate set of every texture, by slot.
+    fn tex_coords_mut(&mut self) -> Vec<(&'static str, &mut u32)> {
+        let mut tex_coords = Vec::<(&'static str, &mut u32)>::new();
+
+        if let BaseColor::Texture { ref mut tex_coord, .. } = self.base_color {
+            tex_coords.push(("base_color", tex_coord));
+        }
+        if let MetallicRoughness::Texture { ref mut tex_coord, .. } = self.metal_roughness {
+            tex_coords.push(("metallic_roughness", tex_coord));
+        }
+        if let Some(NormalMap { ref mut tex_coord, .. }) = self.normal_map {
+            tex_coords.push(("normal", tex_coord));
+        }
+        if let Some(OcclusionMap { ref mut tex_coord, .. }) = self.occlusion_map {
+            tex_coords.push(("occlusion", tex_coord));
+        }
+        if let Some(EmissionMap::Texture { ref mut tex_coord, .. }) = self.emission_map {
+            tex_coords.push(("emission", tex_coord));
+        }
+        if let Some(ref mut sheen) = self.sheen {
+            if let Some(TextureInfo { ref mut tex_coord, .. }) = sheen.color_texture {
+                tex_coords.push(("sheen_color", tex_coord));
+            }
+            if let Some(TextureInfo { ref mut tex_coord, .. }) = sheen.roughness_texture {
+                tex_coords.push(("sheen_roughness", tex_coord));
+            }
+        }
+        if let Some(Anisotropy { texture: Some(TextureInfo { ref mut tex_coord, .. }), .. }) = self.anisotropy {
+            tex_coords.push(("anisotropy", tex_coord));
+        }
+        if let Some(ref mut iridescence) = self.iridescence {
+            if let Some(TextureInfo { ref mut tex_coord, .. }) = iridescence.texture {
+                tex_coords.push(("iridescence", tex_coord));
+            }
+            if let Some(TextureInfo { ref mut tex_coord, .. }) = iridescence.thickness_texture {
+                tex_coords.push(("iridescence_thickness", tex_coord));
+            }
+        }
+
+        tex_coords
+    }
+}
+
+/// Materials are equal when everything but their name matches.
+impl PartialEq for Material {
+    fn eq(&self, other: &Material) -> bool {
+        self.alpha_cutoff == other.alpha_cutoff &&
+            self.alpha_mode == other.alpha_mode &&
+            self.double_sided == other.double_sided &&
+            self.base_color == other.base_color &&
+            self.metal_roughness == other.metal_roughness &&
+            self.normal_map == other.normal_map &&
+            self.occlusion_map == other.occlusion_map &&
+            self.emission_map == other.emission_map &&
+            self.sheen == other.sheen &&
+            self.anisotropy == other.anisotropy &&
+            self.iridescence == other.iridescence &&
+            self.dispersion == other.dispersion
+    }
+}
+
+/// The material slots a texture is used in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextureBinding {
+    texture: String,
+    uses: Vec<(String, &'static str)>,
+}
+
+impl TextureBinding {
+    pub fn texture(&self) -> &str {
+        &self.texture
+    }
+
+    /// Material name and slot of every use of the texture.
+    pub fn uses(&self) -> &[(String, &'static str)] {
+        &self.uses
+    }
+}
+
+/// Lists the slots of `materials` every one of `textures` feeds, in
+/// texture order.
+pub fn bind_textures(materials: &[Material], textures: &[Texture]) -> Vec<TextureBinding> {
+    textures.iter().map(|texture| {
+        let uses = materials.iter().flat_map(|material| {
+            material.texture_names().into_iter()
+                .filter(|&(_, name)| name == texture.name())
+                .map(move |(slot, _)| (material.name.clone(), slot))
+        }).collect();
+
+        TextureBinding {
+            texture: texture.name().to_owned(),
+            uses: uses,
+        }
+    }).collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum AlphaMode {
     Blend,
     Mask,
     Opaque,
 }
 
+impl AlphaMode {
+    /// Position in the order primitives are drawn: opaque, mask, then blend.
+    pub fn draw_order(self) -> u8 {
+        match self {
+            AlphaMode::Opaque => 0,
+            AlphaMode::Mask => 1,
+            AlphaMode::Blend => 2,
+        }
+    }
+}
+
 pub fn get<'a>(
     materials: GltfMaterials,
     textures: &'a Textures, 
+    extensions: &'a Extensions,
 ) -> Result<Materials> {
     let my_materials = materials.map(|material| {
         let name = material.name().ok_or(ConvertError::NoName)?;
-        let alpha_cutoff = material.alpha_cutoff();
-        let alpha_mode = match material.alpha_mode() {
-            GltfAlphaMode::Blend => AlphaMode::Blend,
-            GltfAlphaMode::Mask => AlphaMode::Mask,
-            GltfAlphaMode::Opaque => AlphaMode::Opaque,
+        let (alpha_mode, alpha_cutoff) = match material.alpha_mode() {
+            GltfAlphaMode::Blend => (AlphaMode::Blend, None),
+            GltfAlphaMode::Mask => (AlphaMode::Mask, Some(material.alpha_cutoff())),
+            GltfAlphaMode::Opaque => (AlphaMode::Opaque, None),
         };
         let double_sided = material.double_sided();
 
-        let base_color = get_base_color(&material, textures)?;
-        let metal_roughness = get_metallic_roughness(&material, textures)?;
-        let normal_map = get_normal_map(&material, textures)?;
-        let occlusion_map = get_occlusion_map(&material, textures)?;
-        let emission_map = get_emission_map(&material, textures)?;
-        
+        let base_color = get_base_color(&material, name, textures)?;
+        let metal_roughness = get_metallic_roughness(&material, name, textures)?;
+        let normal_map = get_normal_map(&material, name, textures)?;
+        let occlusion_map = get_occlusion_map(&material, name, textures)?;
+        let emission_map = get_emission_map(&material, name, textures)?;
+        if let Some(ref map) = occlusion_map {
+            if map.strength < 0.0 || map.strength > 1.0 {
+                return Err(Error::Convert(ConvertError::InvalidOcclusionStrength {
+                    material: name.to_owned(),
+                    strength: map.strength,
+                }));
+            }
+        }
+        let sheen = get_sheen(&material, name, textures, extensions)?;
+        let anisotropy = get_anisotropy(&material, name, textures, extensions)?;
+        let iridescence = get_iridescence(&material, name, textures, extensions)?;
+        let dispersion = get_extension(&material, extensions, "KHR_materials_dispersion")
+            .map(|extension| get_f32(extension, "dispersion").unwrap_or(0.0));
+
         Ok(Material {
             name: name.to_owned(),
+            name_id: None,
             alpha_cutoff: alpha_cutoff,
             alpha_mode: alpha_mode,
             double_sided: double_sided,
@@ -66,14 +514,68 @@ pub fn get<'a>(
             normal_map: normal_map,
             occlusion_map: occlusion_map,
             emission_map: emission_map,
+            sheen: sheen,
+            anisotropy: anisotropy,
+            iridescence: iridescence,
+            dispersion: dispersion,
         })
     }).collect::<Result<Vec<_>>>()?;
 
     Ok(Materials {
+        indices: (0..my_materials.len()).collect(),
         materials: my_materials,
     })
 }
 
+fn get_texture_name<'a>(
+    textures: &'a Textures,
+    texture_index: usize,
+    material_name: &'a str,
+    slot: &'static str,
+) -> Result<&'a str> {
+    textures.get(texture_index).ok_or(Error::Convert(ConvertError::MaterialTextureMissing {
+        material: material_name.to_owned(),
+        slot: slot,
+        texture_index: texture_index,
+    }))
+}
+
+/// A texture referenced from a material extension.
+#[derive(PartialEq, Serialize, Deserialize)]
+pub struct TextureInfo {
+    tex_coord: u32,
+    name: String,
+}
+
+fn get_extension<'a>(
+    material: &'a GltfMaterial,
+    extensions: &'a Extensions,
+    name: &'a str,
+) -> Option<&'a Value> {
+    material.index().and_then(|index| extensions.material(index, name))
+}
+
+fn get_extension_texture<'a>(
+    extension: &'a Value,
+    key: &'a str,
+    material_name: &'a str,
+    slot: &'static str,
+    textures: &'a Textures,
+) -> Result<Option<TextureInfo>> {
+    match get_texture_info(extension, key) {
+        Some((index, tex_coord)) => {
+            let name = get_texture_name(textures, index, material_name, slot)?;
+
+            Ok(Some(TextureInfo {
+                tex_coord: tex_coord,
+                name: name.to_owned(),
+            }))
+        },
+        None => Ok(None),
+    }
+}
+
+#[derive(PartialEq, Serialize, Deserialize)]
 pub enum BaseColor {
     Factor([f32; 4]),
     Texture {
@@ -84,6 +586,7 @@ pub enum BaseColor {
 
 fn get_base_color<'a>(
     material: &'a GltfMaterial,
+    material_name: &'a str,
     textures: &'a Textures, 
 ) -> Result<BaseColor> {
     let pbr = material.pbr_metallic_roughness();
@@ -91,8 +594,7 @@ fn get_base_color<'a>(
     match pbr.base_color_texture() {
         Some(tex) => {
             let tex_coord = tex.tex_coord();
-            let name = textures.get(tex.texture().index())
-                .ok_or(ConvertError::MissingImageBuffer)?;
+            let name = get_texture_name(textures, tex.texture().index(), material_name, "base_color")?;
             
             Ok(BaseColor::Texture {
                 tex_coord: tex_coord,
@@ -103,6 +605,7 @@ fn get_base_color<'a>(
     }
 }
 
+#[derive(PartialEq, Serialize, Deserialize)]
 pub enum MetallicRoughness {
     Factor {
         metallicity: f32,
@@ -116,6 +619,7 @@ pub enum MetallicRoughness {
 
 fn get_metallic_roughness<'a>(
     material: &'a GltfMaterial,
+    material_name: &'a str,
     textures: &'a Textures, 
 ) -> Result<MetallicRoughness> {
     let pbr = material.pbr_metallic_roughness();
@@ -123,8 +627,7 @@ fn get_metallic_roughness<'a>(
     match pbr.metallic_roughness_texture() {
         Some(tex) => {
             let tex_coord = tex.tex_coord();
-            let name = textures.get(tex.texture().index())
-                .ok_or(ConvertError::MissingImageBuffer)?;
+            let name = get_texture_name(textures, tex.texture().index(), material_name, "metallic_roughness")?;
             
             Ok(MetallicRoughness::Texture {
                 tex_coord: tex_coord,
@@ -138,6 +641,7 @@ fn get_metallic_roughness<'a>(
     }
 }
 
+#[derive(PartialEq, Serialize, Deserialize)]
 pub struct NormalMap {
     scale: f32,
     tex_coord: u32,
@@ -146,14 +650,14 @@ pub struct NormalMap {
 
 fn get_normal_map<'a>(
     material: &'a GltfMaterial,
+    material_name: &'a str,
     textures: &'a Textures, 
 ) -> Result<Option<NormalMap>> {
     match material.normal_texture() {
         Some(tex) => {
             let scale = tex.scale();
             let tex_coord = tex.tex_coord();
-            let name = textures.get(tex.texture().index())
-                .ok_or(ConvertError::MissingImageBuffer)?;
+            let name = get_texture_name(textures, tex.texture().index(), material_name, "normal")?;
 
             Ok(Some(NormalMap {
                 scale: scale,
@@ -165,22 +669,37 @@ fn get_normal_map<'a>(
     }
 }
 
+#[derive(PartialEq, Serialize, Deserialize)]
 pub struct OcclusionMap {
     strength: f32,
     tex_coord: u32,
     name: String,
 }
 
+impl OcclusionMap {
+    /// Strength in `[0, 1]` to apply to sampled occlusion values with
+    /// `apply`.
+    pub fn strength(&self) -> f32 {
+        self.strength
+    }
+
+    /// Occlusion for a value sampled from the texture's red channel:
+    /// `1.0 + strength * (sampled - 1.0)`.
+    pub fn apply(&self, sampled: f32) -> f32 {
+        apply_occlusion_strength(sampled, self.strength)
+    }
+}
+
 fn get_occlusion_map<'a>(
     material: &'a GltfMaterial,
+    material_name: &'a str,
     textures: &'a Textures, 
 ) -> Result<Option<OcclusionMap>> {
     match material.occlusion_texture() {
         Some(tex) => {
             let strength = tex.strength();
             let tex_coord = tex.tex_coord();
-            let name = textures.get(tex.texture().index())
-                .ok_or(ConvertError::MissingImageBuffer)?;
+            let name = get_texture_name(textures, tex.texture().index(), material_name, "occlusion")?;
 
             Ok(Some(OcclusionMap {
                 strength: strength,
@@ -192,6 +711,7 @@ fn get_occlusion_map<'a>(
     }
 }
 
+#[derive(PartialEq, Serialize, Deserialize)]
 pub enum EmissionMap {
     Factor([f32; 3]),
     Texture {
@@ -202,13 +722,13 @@ pub enum EmissionMap {
 
 fn get_emission_map<'a>(
     material: &'a GltfMaterial,
+    material_name: &'a str,
     textures: &'a Textures, 
 ) -> Result<Option<EmissionMap>> {
     match material.emissive_texture() {
         Some(tex) => {
             let tex_coord = tex.tex_coord();
-            let name = textures.get(tex.texture().index())
-                .ok_or(ConvertError::MissingImageBuffer)?;
+            let name = get_texture_name(textures, tex.texture().index(), material_name, "emission")?;
             
             Ok(Some(EmissionMap::Texture {
                 tex_coord: tex_coord,
@@ -225,3 +745,341 @@ fn get_emission_map<'a>(
         },
     }
 }
+
+/// `KHR_materials_sheen` parameters.
+#[derive(PartialEq, Serialize, Deserialize)]
+pub struct Sheen {
+    color_factor: [f32; 3],
+    roughness_factor: f32,
+    color_texture: Option<TextureInfo>,
+    roughness_texture: Option<TextureInfo>,
+}
+
+fn get_sheen<'a>(
+    material: &'a GltfMaterial,
+    material_name: &'a str,
+    textures: &'a Textures,
+    extensions: &'a Extensions,
+) -> Result<Option<Sheen>> {
+    let extension = match get_extension(material, extensions, "KHR_materials_sheen") {
+        Some(extension) => extension,
+        None => { return Ok(None); },
+    };
+
+    Ok(Some(Sheen {
+        color_factor: get_f32_3(extension, "sheenColorFactor").unwrap_or([0.0, 0.0, 0.0]),
+        roughness_factor: get_f32(extension, "sheenRoughnessFactor").unwrap_or(0.0),
+        color_texture: get_extension_texture(
+            extension,
+            "sheenColorTexture",
+            material_name,
+            "sheen_color",
+            textures,
+        )?,
+        roughness_texture: get_extension_texture(
+            extension,
+            "sheenRoughnessTexture",
+            material_name,
+            "sheen_roughness",
+            textures,
+        )?,
+    }))
+}
+
+/// `KHR_materials_anisotropy` parameters.
+#[derive(PartialEq, Serialize, Deserialize)]
+pub struct Anisotropy {
+    strength: f32,
+    /// Rotation of the anisotropy direction in radians, counter-clockwise
+    /// from the tangent.
+    rotation: f32,
+    texture: Option<TextureInfo>,
+}
+
+fn get_anisotropy<'a>(
+    material: &'a GltfMaterial,
+    material_name: &'a str,
+    textures: &'a Textures,
+    extensions: &'a Extensions,
+) -> Result<Option<Anisotropy>> {
+    let extension = match get_extension(material, extensions, "KHR_materials_anisotropy") {
+        Some(extension) => extension,
+        None => { return Ok(None); },
+    };
+
+    Ok(Some(Anisotropy {
+        strength: get_f32(extension, "anisotropyStrength").unwrap_or(0.0),
+        rotation: get_f32(extension, "anisotropyRotation").unwrap_or(0.0),
+        texture: get_extension_texture(
+            extension,
+            "anisotropyTexture",
+            material_name,
+            "anisotropy",
+            textures,
+        )?,
+    }))
+}
+
+/// `KHR_materials_iridescence` parameters.
+#[derive(PartialEq, Serialize, Deserialize)]
+pub struct Iridescence {
+    factor: f32,
+    /// Index of refraction of the thin film.
+    ior: f32,
+    /// Thin film thickness range in nanometers, which the thickness texture
+    /// interpolates between.
+    thickness_min: f32,
+    thickness_max: f32,
+    texture: Option<TextureInfo>,
+    thickness_texture: Option<TextureInfo>,
+}
+
+fn get_iridescence<'a>(
+    material: &'a GltfMaterial,
+    material_name: &'a str,
+    textures: &'a Textures,
+    extensions: &'a Extensions,
+) -> Result<Option<Iridescence>> {
+    let extension = match get_extension(material, extensions, "KHR_materials_iridescence") {
+        Some(extension) => extension,
+        None => { return Ok(None); },
+    };
+
+    Ok(Some(Iridescence {
+        factor: get_f32(extension, "iridescenceFactor").unwrap_or(0.0),
+        ior: get_f32(extension, "iridescenceIor").unwrap_or(1.3),
+        thickness_min: get_f32(extension, "iridescenceThicknessMinimum").unwrap_or(100.0),
+        thickness_max: get_f32(extension, "iridescenceThicknessMaximum").unwrap_or(400.0),
+        texture: get_extension_texture(
+            extension,
+            "iridescenceTexture",
+            material_name,
+            "iridescence",
+            textures,
+        )?,
+        thickness_texture: get_extension_texture(
+            extension,
+            "iridescenceThicknessTexture",
+            material_name,
+            "iridescence_thickness",
+            textures,
+        )?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use gltf::Gltf;
+
+    use super::*;
+    use super::super::super::Error;
+    use super::super::import;
+    use super::super::options::Options;
+    use super::super::texture::get as get_textures;
+
+    #[test]
+    fn test_missing_normal_texture() {
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "images": [ { "uri": "normal.png" } ],
+            "textures": [ { "source": 0 } ],
+            "materials": [ {
+                "name": "scratched",
+                "normalTexture": { "index": 0 }
+            } ]
+        }"#;
+        let gltf = Gltf::from_str(json).unwrap().validate_minimally().unwrap();
+        let textures = Textures::default();
+        let extensions = Extensions::new(Value::Null);
+
+        match get(gltf.materials(), &textures, &extensions) {
+            Err(Error::Convert(ConvertError::MaterialTextureMissing {
+                material,
+                slot,
+                texture_index,
+            })) => {
+                assert_eq!(material, "scratched");
+                assert_eq!(slot, "normal");
+                assert_eq!(texture_index, 0);
+            },
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn test_alpha_cutoff() {
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "materials": [
+                { "name": "solid", "alphaCutoff": 0.3 },
+                { "name": "foliage", "alphaMode": "MASK", "alphaCutoff": 0.3 }
+            ]
+        }"#;
+        let gltf = Gltf::from_str(json).unwrap().validate_minimally().unwrap();
+        let materials = get(gltf.materials(), &Textures::default(), &Extensions::new(Value::Null)).unwrap();
+
+        assert_eq!(materials.materials[0].alpha_cutoff, None);
+        assert_eq!(materials.materials[1].alpha_cutoff, Some(0.3));
+    }
+
+    #[test]
+    fn test_anisotropy() {
+        let path = Path::new("testmodels/gltf2/Anisotropy/Anisotropy.gltf");
+        let parent = path.parent().unwrap();
+        let (gltf, extensions, buffers) = import(path, parent).unwrap();
+        let textures = get_textures(parent, gltf.textures(), &buffers, &Options::default(), &mut Vec::new()).unwrap();
+        let materials = get(gltf.materials(), &textures, &extensions).unwrap();
+        let anisotropy = materials.materials[0].anisotropy.as_ref().unwrap();
+
+        assert_eq!(anisotropy.strength, 0.75);
+        assert_eq!(anisotropy.rotation, 1.5);
+        assert_eq!(anisotropy.texture.as_ref().unwrap().name, "brushed");
+        assert!(materials.materials[1].anisotropy.is_none());
+    }
+
+    #[test]
+    fn test_tex_coord_downgrade() {
+        let path = Path::new("testmodels/gltf2/TexCoordDowngrade/TexCoordDowngrade.gltf");
+        let parent = path.parent().unwrap();
+        let (gltf, extensions, buffers) = import(path, parent).unwrap();
+        let textures = get_textures(parent, gltf.textures(), &buffers, &Options::default(), &mut Vec::new()).unwrap();
+        let mut materials = get(gltf.materials(), &textures, &extensions).unwrap();
+        let mut warnings = Vec::new();
+
+        match materials.check_tex_coords(0, 1, false, &mut warnings) {
+            Err(Error::Convert(ConvertError::MissingTexCoordSet { slot, set, .. })) => {
+                assert_eq!(slot, "base_color");
+                assert_eq!(set, 1);
+            },
+            _ => assert!(false),
+        }
+
+        materials.check_tex_coords(0, 1, true, &mut warnings).unwrap();
+        match materials.materials[0].base_color {
+            BaseColor::Texture { tex_coord, .. } => assert_eq!(tex_coord, 0),
+            _ => assert!(false),
+        }
+        assert_eq!(warnings, vec![ConvertWarning::TexCoordDowngraded {
+            material: String::from("decal"),
+            slot: "base_color",
+            set: 1,
+        }]);
+    }
+
+    #[test]
+    fn test_bake_occlusion_strength() {
+        let path = Path::new("testmodels/gltf2/Occlusion/Occlusion.gltf");
+        let parent = path.parent().unwrap();
+        let (gltf, extensions, buffers) = import(path, parent).unwrap();
+        let mut textures = get_textures(parent, gltf.textures(), &buffers, &Options::default(), &mut Vec::new()).unwrap();
+        let mut materials = get(gltf.materials(), &textures, &extensions).unwrap();
+
+        let map = materials.materials[0].occlusion_map.as_ref().unwrap();
+        assert_eq!(map.apply(100.0 / 255.0), 1.0 + 0.5 * (100.0 / 255.0 - 1.0));
+        assert_eq!(map.apply(1.0), 1.0);
+
+        materials.bake_occlusion_strength(&mut textures, &mut Vec::new());
+        let contents = textures.textures()[0].contents();
+        // 255 * (1 + 0.5 * (100 / 255 - 1)) = 177.5
+        assert_eq!(&contents[0..4], &[178, 60, 200, 255]);
+        assert_eq!(materials.materials[0].occlusion_map.as_ref().unwrap().strength(), 1.0);
+
+        // Encoded textures cannot be baked, so their maps keep the strength.
+        let mut options = Options::default();
+        options.keep_encoded_images = true;
+        let mut textures = get_textures(parent, gltf.textures(), &buffers, &options, &mut Vec::new()).unwrap();
+        let mut materials = get(gltf.materials(), &textures, &extensions).unwrap();
+        let encoded = textures.textures()[0].contents().to_vec();
+        let mut warnings = Vec::new();
+        materials.bake_occlusion_strength(&mut textures, &mut warnings);
+        assert_eq!(textures.textures()[0].contents(), &encoded[..]);
+        assert_eq!(materials.materials[0].occlusion_map.as_ref().unwrap().strength(), 0.5);
+        assert_eq!(warnings, vec![ConvertWarning::OcclusionStrengthNotBaked {
+            texture: textures.textures()[0].name().to_owned(),
+        }]);
+
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "images": [ { "uri": "ao.png" } ],
+            "textures": [ { "name": "ao", "source": 0 } ],
+            "materials": [ { "name": "stone", "occlusionTexture": { "index": 0, "strength": 1.5 } } ]
+        }"#;
+        let gltf = Gltf::from_str(json).unwrap().validate_minimally().unwrap();
+        let textures = get_textures(parent, gltf.textures(), &buffers, &Options::default(), &mut Vec::new()).unwrap();
+        match get(gltf.materials(), &textures, &extensions) {
+            Err(Error::Convert(ConvertError::InvalidOcclusionStrength { strength, .. })) => {
+                assert_eq!(strength, 1.5);
+            },
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn test_flip_normal_maps() {
+        let path = Path::new("testmodels/gltf2/NormalMap/NormalMap.gltf");
+        let parent = path.parent().unwrap();
+        let (gltf, extensions, buffers) = import(path, parent).unwrap();
+        let mut textures = get_textures(parent, gltf.textures(), &buffers, &Options::default(), &mut Vec::new()).unwrap();
+        let materials = get(gltf.materials(), &textures, &extensions).unwrap();
+
+        // Both materials share the texture, which is only flipped once.
+        let mut warnings = Vec::new();
+        materials.flip_normal_maps(&mut textures, &mut warnings);
+        assert_eq!(&textures.textures()[0].contents()[0..4], &[128, 155, 255, 255]);
+        assert!(warnings.is_empty());
+
+        // Encoded textures cannot be flipped, so they are left with a warning.
+        let mut options = Options::default();
+        options.keep_encoded_images = true;
+        let mut textures = get_textures(parent, gltf.textures(), &buffers, &options, &mut Vec::new()).unwrap();
+        let encoded = textures.textures()[0].contents().to_vec();
+        materials.flip_normal_maps(&mut textures, &mut warnings);
+        assert_eq!(textures.textures()[0].contents(), &encoded[..]);
+        assert_eq!(warnings, vec![ConvertWarning::NormalMapNotFlipped {
+            texture: textures.textures()[0].name().to_owned(),
+        }]);
+    }
+
+    #[test]
+    fn test_sheen() {
+        let path = Path::new("testmodels/gltf2/Sheen/Sheen.gltf");
+        let parent = path.parent().unwrap();
+        let (gltf, extensions, buffers) = import(path, parent).unwrap();
+        let textures = get_textures(parent, gltf.textures(), &buffers, &Options::default(), &mut Vec::new()).unwrap();
+        let materials = get(gltf.materials(), &textures, &extensions).unwrap();
+        let sheen = materials.materials[0].sheen.as_ref().unwrap();
+
+        assert_eq!(sheen.color_factor, [0.8, 0.2, 0.4]);
+        assert_eq!(sheen.roughness_factor, 0.6);
+        assert_eq!(sheen.color_texture.as_ref().unwrap().name, "sheen");
+        assert!(sheen.roughness_texture.is_none());
+    }
+
+    #[test]
+    fn test_iridescence() {
+        let path = Path::new("testmodels/gltf2/Iridescence/Iridescence.gltf");
+        let parent = path.parent().unwrap();
+        let (gltf, extensions, buffers) = import(path, parent).unwrap();
+        let textures = get_textures(parent, gltf.textures(), &buffers, &Options::default(), &mut Vec::new()).unwrap();
+        let materials = get(gltf.materials(), &textures, &extensions).unwrap();
+        let iridescence = materials.materials[0].iridescence.as_ref().unwrap();
+
+        assert_eq!(iridescence.factor, 1.0);
+        assert_eq!(iridescence.ior, 1.4);
+        assert_eq!((iridescence.thickness_min, iridescence.thickness_max), (200.0, 800.0));
+        assert!(iridescence.texture.is_none());
+        assert_eq!(iridescence.thickness_texture.as_ref().unwrap().name, "thickness");
+    }
+
+    #[test]
+    fn test_dispersion() {
+        let path = Path::new("testmodels/gltf2/Dispersion/Dispersion.gltf");
+        let (gltf, extensions, _) = import(path, path.parent().unwrap()).unwrap();
+        let materials = get(gltf.materials(), &Textures::default(), &extensions).unwrap();
+        let dispersions = materials.materials.iter().map(Material::dispersion).collect::<Vec<_>>();
+
+        assert_eq!(dispersions, vec![Some(0.56), Some(0.0), None]);
+    }
+}
@@ -1,23 +1,108 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use gltf::mesh::Mesh as GltfMesh;
-use gltf_importer::Buffers;
 
-use super::super::Result;
+use super::super::{Result, Error};
+use super::{ConvertError, ConvertWarning};
+use super::buffer::Buffers;
 use super::primitive::{Primitive, get as get_primitives};
-use super::material::Materials;
+use super::material::{AlphaMode, Materials};
+use super::options::{NameIds, Options};
+use super::util::name_id;
+
+/// Primitives already converted, by glTF mesh index, whether they were
+/// converted skinned and the skin whose sorted joints they were remapped to,
+/// so meshes instanced by several nodes are decoded once. Each entry keeps
+/// the warnings converting it raised.
+pub type MeshCache = HashMap<(usize, bool, Option<usize>), (Arc<Vec<Primitive>>, Vec<ConvertWarning>)>;
 
+#[derive(Serialize, Deserialize)]
 pub struct Mesh {
-    name: String,
-    primitives: Vec<Primitive>,
+    name: Option<String>,
+    name_id: Option<u64>,
+    /// Shared by every node instancing the glTF mesh. Not serialized with
+    /// the mesh, so that shared lists are written once; see
+    /// `share_primitives`.
+    #[serde(skip)]
+    primitives: Arc<Vec<Primitive>>,
+}
+
+impl Mesh {
+    /// Name of the mesh's node, unless `Options::name_ids` replaced it.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_ref().map(String::as_str)
+    }
+
+    /// Hashed ID of the name, with `Options::name_ids`.
+    pub fn name_id(&self) -> Option<u64> {
+        self.name_id
+    }
+
+    pub fn primitives(&self) -> &[Primitive] {
+        &self.primitives
+    }
+
+    /// Whether both meshes use the same decoded primitives, as nodes
+    /// instancing one glTF mesh do.
+    pub fn shares_primitives(&self, other: &Mesh) -> bool {
+        Arc::ptr_eq(&self.primitives, &other.primitives)
+    }
+
+    /// Moves the primitives with blended materials into a new mesh of the
+    /// same name. Returns `None`, leaving the mesh as it is, unless it has
+    /// both blended and other primitives.
+    pub fn split_blended(&mut self, materials: &Materials) -> Option<Mesh> {
+        let (blended, others): (Vec<&Primitive>, Vec<&Primitive>) = self.primitives.iter().partition(|primitive| {
+            materials.by_name(primitive.material())
+                .map_or(false, |material| material.alpha_mode() == AlphaMode::Blend)
+        });
+
+        if blended.is_empty() || others.is_empty() {
+            return None;
+        }
+
+        // The halves are copies, leaving any other instances whole.
+        let blended = blended.into_iter().cloned().collect();
+        self.primitives = Arc::new(others.into_iter().cloned().collect());
+        Some(Mesh {
+            name: self.name.clone(),
+            name_id: self.name_id,
+            primitives: Arc::new(blended),
+        })
+    }
 }
 
 pub fn get<'a>(
     mesh: &'a GltfMesh,
     name: &'a str,
     node_weights: Option<&'a [f32]>,
-    has_joints: bool,
+    has_skin: bool,
+    joint_mapping: Option<(usize, &'a [u16])>,
     buffers: &'a Buffers,
     materials: &'a Materials,
+    options: &'a Options,
+    cache: &'a mut MeshCache,
+    warnings: &'a mut Vec<ConvertWarning>,
 ) -> Result<Mesh> {
+    let my_mesh = |primitives| Mesh {
+        name: match options.name_ids {
+            NameIds::Instead => None,
+            _ => Some(String::from(name)),
+        },
+        name_id: match options.name_ids {
+            NameIds::Off => None,
+            _ => Some(name_id(name)),
+        },
+        primitives: primitives,
+    };
+    let key = (mesh.index(), has_skin, joint_mapping.map(|(skin_index, _)| skin_index));
+    if let Some(&(ref primitives, ref cached_warnings)) = cache.get(&key) {
+        warnings.extend(cached_warnings.iter().filter_map(|warning| for_node(warning, name)));
+        return Ok(my_mesh(primitives.clone()));
+    }
+    let first_warning = warnings.len();
+
     let weights = if let Some(weights) = mesh.weights() {
         Some(weights)
     } else if let Some(weights2) = node_weights {
@@ -26,17 +111,121 @@ pub fn get<'a>(
         None
     };
 
-    let primitives = get_primitives(
+    let mut primitives = get_primitives(
         mesh.primitives(),
+        name,
         weights,
-        has_joints,
+        has_skin,
         buffers,
-        materials
+        materials,
+        options,
+        warnings,
     )?;
 
-    Ok(Mesh {
-        name: String::from(name),
-        primitives: primitives,
-    })
+    if let Some((_, mapping)) = joint_mapping {
+        for primitive in primitives.iter_mut() {
+            primitive.remap_joints(mapping);
+        }
+    }
+
+    // Stable, so primitives keep their order within an alpha mode.
+    if options.sort_by_alpha_mode {
+        primitives.sort_by_key(|primitive| {
+            materials.by_name(primitive.material())
+                .map(|material| material.alpha_mode().draw_order())
+                .unwrap_or(0)
+        });
+    }
+
+    let primitives = Arc::new(primitives);
+    cache.insert(key, (primitives.clone(), warnings[first_warning..].to_vec()));
+
+    Ok(my_mesh(primitives))
+}
+
+/// `warning` as the node `name` raises it, for the warnings about the node
+/// rather than the mesh it instances.
+fn for_node(warning: &ConvertWarning, name: &str) -> Option<ConvertWarning> {
+    match warning {
+        &ConvertWarning::SkinMismatch { has_skin, .. } => {
+            Some(ConvertWarning::SkinMismatch {
+                node: String::from(name),
+                has_skin: has_skin,
+            })
+        },
+        &ConvertWarning::UnsupportedAttributeSet { name: attribute, index, .. } => {
+            Some(ConvertWarning::UnsupportedAttributeSet {
+                node: String::from(name),
+                name: attribute,
+                index: index,
+            })
+        },
+        _ => None,
+    }
+}
+
+/// Collects the distinct primitive lists of `meshes`, each once however
+/// many meshes share it, and the index of each mesh's list among them.
+pub fn share_primitives<'a, I: Iterator<Item = &'a Mesh>>(meshes: I) -> (Vec<&'a Vec<Primitive>>, Vec<usize>) {
+    let mut lists = Vec::<&'a Vec<Primitive>>::new();
+    let mut positions = HashMap::<*const Vec<Primitive>, usize>::new();
+    let indices = meshes.map(|mesh| {
+        let list: &'a Vec<Primitive> = &mesh.primitives;
+        *positions.entry(list as *const Vec<Primitive>).or_insert_with(|| {
+            lists.push(list);
+            lists.len() - 1
+        })
+    }).collect();
+
+    (lists, indices)
+}
+
+/// Switches the primitives of `meshes` using a material renamed in `names`,
+/// as pairs of old and new names, to the new name. Meshes that shared a
+/// primitive list still share the renamed one.
+pub fn rename_materials<'a, I: Iterator<Item = &'a mut Mesh>>(meshes: I, names: &[(String, String)]) {
+    let mut renamed = Vec::<(Arc<Vec<Primitive>>, Arc<Vec<Primitive>>)>::new();
+
+    for mesh in meshes {
+        let position = renamed.iter().position(|&(ref list, _)| Arc::ptr_eq(list, &mesh.primitives));
+        let list = match position {
+            Some(position) => renamed[position].1.clone(),
+            None => {
+                let list = Arc::new(mesh.primitives.iter().map(|primitive| {
+                    let mut primitive = primitive.clone();
+                    let new_name = names.iter()
+                        .find(|&&(ref old_name, _)| old_name == primitive.material())
+                        .map(|&(_, ref new_name)| new_name.clone());
+                    if let Some(new_name) = new_name {
+                        primitive.rename_material(&new_name);
+                    }
+                    primitive
+                }).collect());
+                renamed.push((mesh.primitives.clone(), list));
+                renamed[renamed.len() - 1].1.clone()
+            },
+        };
+        mesh.primitives = list;
+    }
+}
+
+/// Gives each of `meshes` the list in `lists` at its index in `indices`,
+/// undoing `share_primitives`. Meshes sharing an index share the list.
+/// Fails with `ConvertError::InvalidContainer` when the indices do not fit.
+pub fn restore_primitives<'a, I: ExactSizeIterator<Item = &'a mut Mesh>>(
+    meshes: I,
+    lists: Vec<Vec<Primitive>>,
+    indices: &'a [usize],
+) -> Result<()> {
+    if meshes.len() != indices.len() {
+        return Err(Error::Convert(ConvertError::InvalidContainer));
+    }
+
+    let lists = lists.into_iter().map(Arc::new).collect::<Vec<_>>();
+    for (mesh, &index) in meshes.zip(indices.iter()) {
+        mesh.primitives = lists.get(index).ok_or(ConvertError::InvalidContainer)?.clone();
+    }
+
+    Ok(())
 }
 
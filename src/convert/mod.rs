@@ -1,100 +1,789 @@
 use std::env::current_dir;
 use std::error;
 use std::fmt;
+use std::fs::File;
+use std::io::{Read, Write};
 use std::path::Path;
 
-use cgmath::{Matrix4, SquareMatrix};
-use gltf::{Scene, Node};
-use gltf_importer::{import, Buffers};
+use bincode::{Infinite, deserialize_from, serialize};
+use byteorder::{LE, ReadBytesExt, WriteBytesExt};
+use cgmath::{Matrix, Matrix3, Matrix4, SquareMatrix};
+use gltf::{Error as GltfError, Gltf, Glb, Scene, Node};
+use gltf::gltf::Meshes;
+use gltf::json::{Value, from_slice as json_from_slice};
+use gltf::mesh::Semantic;
+use image::DynamicImage;
 
-use super::Result;
+use super::{Result, Error};
 
 pub mod animation;
+pub mod buffer;
+pub mod camera;
+pub mod checkpoint;
+pub mod chunk;
+pub mod container;
+pub mod dual_quaternion;
+pub mod extension;
+pub mod humanoid;
+mod ktx2;
+pub mod layout;
+pub mod light;
 pub mod material;
 pub mod mesh;
-mod morph_target;
+pub mod morph_target;
+pub mod options;
 pub mod primitive;
+pub mod quantize;
+pub mod scene;
 pub mod skin;
 mod util;
 pub mod texture;
+mod vertex_cache;
 
-use self::animation::get as get_animations;
-use self::material::{Materials, get as get_materials};
-use self::mesh::{Mesh, get as get_mesh};
-use self::skin::get as get_skins;
-use self::texture::{Textures, get as get_textures};
+use self::animation::{Animation, AnimationSet, get as get_animations};
+use self::buffer::{Buffers, check_meshopt, check_views, get as get_buffers};
+use self::camera::{Camera, get as get_cameras};
+use self::checkpoint::Checkpoint;
+pub use self::container::{read, write};
+pub use self::util::name_id;
+use self::extension::Extensions;
+use self::light::{Light, get as get_lights};
+use self::material::{Material, Materials, TextureBinding, bind_textures, get as get_materials};
+use self::mesh::{Mesh, MeshCache, get as get_mesh, rename_materials, restore_primitives, share_primitives};
+use self::options::{Handedness, NameIds, NormalMapConvention, Options};
+use self::primitive::Primitive;
+use self::scene::{SceneNode, get as get_scene_nodes};
+use self::skin::{Skin, Skins, check_bind_poses, get as get_skins};
+use self::texture::{Texture, Textures, get as get_textures, load_image};
 
+#[derive(Serialize, Deserialize)]
 pub struct Model {
+    node_index: Option<usize>,
+    transform: Matrix4<f32>,
     mesh: Mesh,
+    skin: Option<Skin>,
+    animations: Vec<Animation>,
+}
+
+impl Model {
+    /// Index of the model's node in the glTF nodes. `None` for the meshes
+    /// of a file without scenes.
+    pub fn node_index(&self) -> Option<usize> {
+        self.node_index
+    }
+
+    /// World transform of the model's node, composed from the scene root.
+    /// Vertices are kept in mesh space, so instances still share them.
+    pub fn transform(&self) -> &Matrix4<f32> {
+        &self.transform
+    }
+
+    /// Inverse transpose of `transform`, for normals. Tangents use the
+    /// upper 3x3 of `transform` itself.
+    pub fn normal_matrix(&self) -> Matrix3<f32> {
+        let transform = self.transform;
+        let linear = Matrix3::from_cols(transform.x.truncate(), transform.y.truncate(), transform.z.truncate());
+        linear.invert().unwrap_or(Matrix3::identity()).transpose()
+    }
+
+    pub fn mesh(&self) -> &Mesh {
+        &self.mesh
+    }
+
+    /// The skin of the model's node, if it has one.
+    pub fn skin(&self) -> Option<&Skin> {
+        self.skin.as_ref()
+    }
+
+    /// The channels of every animation that drive the model's skin.
+    pub fn animations(&self) -> &[Animation] {
+        &self.animations
+    }
+}
+
+/// Everything converted from a glTF file.
+pub struct Conversion {
+    models: Vec<Model>,
+    scene: Vec<SceneNode>,
+    cameras: Vec<Camera>,
+    lights: Vec<Light>,
+    animations: AnimationSet,
+    extras: Option<Value>,
+    asset: AssetInfo,
+    warnings: Vec<ConvertWarning>,
+}
+
+impl Conversion {
+    pub fn models(&self) -> &[Model] {
+        &self.models
+    }
+
+    /// Root nodes of the scene hierarchy.
+    pub fn scene(&self) -> &[SceneNode] {
+        &self.scene
+    }
+
+    /// Cameras of the visible nodes, with their world transforms.
+    pub fn cameras(&self) -> &[Camera] {
+        &self.cameras
+    }
+
+    /// `KHR_lights_punctual` lights of the visible nodes, with their world
+    /// transforms.
+    pub fn lights(&self) -> &[Light] {
+        &self.lights
+    }
+
+    pub fn animations(&self) -> &AnimationSet {
+        &self.animations
+    }
+
+    /// Application specific data attached to the converted scene.
+    pub fn extras(&self) -> Option<&Value> {
+        self.extras.as_ref()
+    }
+
+    /// Provenance of the source glTF file.
+    pub fn asset(&self) -> &AssetInfo {
+        &self.asset
+    }
+
+    /// Problems that did not stop the conversion.
+    pub fn warnings(&self) -> &[ConvertWarning] {
+        &self.warnings
+    }
+}
+
+/// The `asset` block of a glTF file, kept for licensing audits.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssetInfo {
+    pub generator: Option<String>,
+    pub copyright: Option<String>,
+    /// glTF version the file targets.
+    pub version: String,
+}
+
+/// Totals over a whole conversion, for build reports.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConvertStats {
+    pub vertices: usize,
+    pub triangles: usize,
+    pub textures: usize,
+    /// Size of all decoded texture contents.
+    pub texture_bytes: usize,
+    pub materials: usize,
+    pub joints: usize,
+    pub animations: usize,
+    pub keyframes: usize,
 }
 
 pub fn get<P: AsRef<Path>>(
     path: P,
-) -> Result<Vec<Model>> {
+) -> Result<Conversion> {
+    get_with_options(path, &Options::default())
+}
+
+pub fn get_with_options<'a, P: AsRef<Path>>(
+    path: P,
+    options: &'a Options,
+) -> Result<Conversion> {
+    get_with_stats(path, options).map(|(conversion, _)| conversion)
+}
+
+/// Converts a glTF file like `get_with_options`, also counting what was
+/// converted.
+pub fn get_with_stats<'a, P: AsRef<Path>>(
+    path: P,
+    options: &'a Options,
+) -> Result<(Conversion, ConvertStats)> {
+    get_with_textures(path, options).map(|(conversion, stats, _, _)| (conversion, stats))
+}
+
+/// Converts the models of several glTF files into one list, along with a
+/// single pool of their materials, one of their textures, and the material
+/// slots each pooled texture feeds. Textures with identical contents are
+/// stored once, under the name they were first pooled with, so files sharing
+/// a texture library do not repeat it. Different textures of the same name
+/// are renamed with a numbered suffix, e.g. `bricks_1`, and so are different
+/// materials of the same name. Materials and models are updated to use the
+/// pooled names.
+pub fn get_many<'a, P: AsRef<Path>>(
+    paths: &[P],
+    options: &'a Options,
+) -> Result<(Vec<Model>, Vec<Material>, Vec<Texture>, Vec<TextureBinding>)> {
+    let mut models = Vec::<Model>::new();
+    let mut materials = Vec::<Material>::new();
+    let mut pool = Vec::<Texture>::new();
+    let mut hashes = Vec::<u64>::new();
+
+    for path in paths {
+        let (conversion, _, textures, mut file_materials) = get_with_textures(path, options)?;
+
+        let mut texture_names = Vec::<(String, String)>::new();
+        for mut texture in textures.into_textures() {
+            let hash = texture.content_hash();
+            let pooled = hashes.iter().zip(pool.iter())
+                .position(|(&pooled_hash, pooled)| pooled_hash == hash && pooled.same_contents(&texture));
+            let name = match pooled {
+                Some(position) => pool[position].name().to_owned(),
+                None => unique_name(texture.name(), |name| pool.iter().any(|texture| texture.name() == name)),
+            };
+            if name != texture.name() {
+                texture_names.push((texture.name().to_owned(), name.clone()));
+            }
+            if pooled.is_none() {
+                texture.rename(name);
+                pool.push(texture);
+                hashes.push(hash);
+            }
+        }
+        file_materials.rename_textures(&texture_names);
+
+        // A material of the same name from another file is only reused if
+        // it matches, textures included.
+        let mut material_names = Vec::<(String, String)>::new();
+        for mut material in file_materials.into_materials() {
+            if materials.iter().any(|pooled| pooled.name() == material.name() && *pooled == material) {
+                continue;
+            }
+            let name = unique_name(material.name(), |name| materials.iter().any(|material| material.name() == name));
+            if name != material.name() {
+                material_names.push((material.name().to_owned(), name.clone()));
+                material.rename(name);
+            }
+            materials.push(material);
+        }
+
+        let mut file_models = conversion.models;
+        rename_materials(file_models.iter_mut().map(|model| &mut model.mesh), &material_names);
+        models.extend(file_models);
+    }
+
+    let bindings = bind_textures(&materials, &pool);
+
+    Ok((models, materials, pool, bindings))
+}
+
+/// `name`, or the first of `name_1`, `name_2`, ... that is not `taken`.
+fn unique_name<F: Fn(&str) -> bool>(name: &str, taken: F) -> String {
+    if !taken(name) {
+        return name.to_owned();
+    }
+
+    (1..).map(|suffix| format!("{}_{}", name, suffix))
+        .find(|candidate| !taken(candidate))
+        .unwrap()
+}
+
+fn get_with_textures<'a, P: AsRef<Path>>(
+    path: P,
+    options: &'a Options,
+) -> Result<(Conversion, ConvertStats, Textures, Materials)> {
+    let mut warnings = Vec::<ConvertWarning>::new();
+
     // Read in all relevant data.
     let cwd = current_dir()?;
     let parent = path.as_ref().parent().unwrap_or(&cwd);
-    let (gltf, buffers) = import(&path)?;
-    let textures = get_textures(&parent, gltf.textures(), &buffers)?;
-    let materials = get_materials(gltf.materials(), &textures)?;
+    let (gltf, extensions, buffers) = import(&path, &parent)?;
+    let mut textures = get_textures(&parent, gltf.textures(), &buffers, options, &mut warnings)?;
+    let mut materials = get_materials(gltf.materials(), &textures, &extensions)?;
+    if options.bake_occlusion_strength {
+        materials.bake_occlusion_strength(&mut textures, &mut warnings);
+    }
+    if options.normal_map_convention == NormalMapConvention::DirectX {
+        materials.flip_normal_maps(&mut textures, &mut warnings);
+    }
 
-    // Retrieve default scene from gltf.
-    let scene = gltf.default_scene().ok_or(ConvertError::NoDefaultScene)?;
+    // Make sure materials only sample texture coordinate sets their
+    // primitives provide.
+    for mesh in gltf.meshes() {
+        for primitive in mesh.primitives() {
+            if let Some(index) = primitive.material().index() {
+                let set_count = (0..)
+                    .take_while(|&set| primitive.get(&Semantic::TexCoords(set)).is_some())
+                    .count();
+                materials.check_tex_coords(
+                    index,
+                    set_count as u32,
+                    options.downgrade_tex_coords,
+                    &mut warnings,
+                )?;
+            }
+        }
+    }
+
+    if options.dedup_materials {
+        materials.dedup();
+    }
+    if let Some(ref name) = options.default_material_name {
+        let needs_default = gltf.meshes()
+            .any(|mesh| mesh.primitives().any(|primitive| primitive.material().index().is_none()));
+        if needs_default {
+            materials.add_default(name);
+        }
+    }
+    if options.name_ids != NameIds::Off {
+        materials.assign_name_ids();
+    }
+
+    // Retrieve default scene from gltf. Files without scenes are mesh
+    // libraries.
+    let scene = gltf.default_scene();
+    if scene.is_none() && gltf.scenes().len() > 0 {
+        return Err(Error::Convert(ConvertError::NoDefaultScene));
+    }
 
     // Retrieve skins.
-    let skins = get_skins(gltf.skins(), &buffers)?;
+    let skins = get_skins(gltf.skins(), &buffers, &extensions, options)?;
+    if options.validate_bind_poses {
+        check_bind_poses(&skins, &mut warnings);
+    }
 
     // Retrieve animations.
-    let animations = get_animations(gltf.animations(), &skins, &buffers)?;
+    let animations = get_animations(gltf.animations(), &skins, &buffers, options, &mut warnings)?;
 
     // Retrieve models.
-    let models = get_models(&scene, &buffers, &materials)?;
+    let mut checkpoint = match options.checkpoint {
+        Some(ref directory) => Some(Checkpoint::open(directory, &path, options)?),
+        None => None,
+    };
+    let models = match scene {
+        Some(ref scene) => get_models(
+            scene,
+            &buffers,
+            &materials,
+            &skins,
+            &animations,
+            &extensions,
+            options,
+            checkpoint.as_mut(),
+            &mut warnings,
+        )?,
+        None => get_library_models(gltf.meshes(), &buffers, &materials, options, &mut warnings)?,
+    };
+    let scene_nodes = scene.as_ref()
+        .map_or(Vec::new(), |scene| get_scene_nodes(scene, &models, &extensions, options));
+    let cameras = scene.as_ref()
+        .map_or(Vec::new(), |scene| get_cameras(scene, &extensions, options));
+    let lights = scene.as_ref()
+        .map_or(Vec::new(), |scene| get_lights(scene, &extensions, options));
+
+    let primitives = models.iter()
+        .flat_map(|model| model.mesh.primitives().iter())
+        .collect::<Vec<_>>();
+    let stats = ConvertStats {
+        vertices: primitives.iter().map(|primitive| primitive.vertex_count()).sum(),
+        triangles: primitives.iter().map(|primitive| primitive.triangles().count()).sum(),
+        textures: textures.textures().len(),
+        texture_bytes: textures.textures().iter().map(|texture| texture.contents().len()).sum(),
+        materials: materials.materials().len(),
+        joints: skins.skins().iter().map(|skin| skin.joints().len()).sum(),
+        animations: animations.animations().len(),
+        keyframes: animations.animations().iter()
+            .flat_map(|animation| animation.channels().iter())
+            .map(|channel| channel.keyframe_count())
+            .sum(),
+    };
+
+    Ok((Conversion {
+        models: models,
+        scene: scene_nodes,
+        cameras: cameras,
+        lights: lights,
+        animations: animations,
+        extras: scene.as_ref().and_then(|scene| scene.extras().clone()),
+        asset: AssetInfo {
+            generator: gltf.as_json().asset.generator.clone(),
+            copyright: gltf.as_json().asset.copyright.clone(),
+            version: gltf.as_json().asset.version.clone(),
+        },
+        warnings: warnings,
+    }, stats, textures, materials))
+}
+
+/// Bytes the wg3d format starts with. They differ from `container::MAGIC`,
+/// so neither reader mistakes the other format for its own.
+pub const WG3D_MAGIC: &'static [u8; 4] = b"WG3D";
+
+/// Major version of the wg3d format. Readers reject other major versions.
+pub const WG3D_MAJOR_VERSION: u16 = 1;
+
+/// Minor version of the wg3d format. Minor versions stay readable.
+pub const WG3D_MINOR_VERSION: u16 = 0;
+
+/// Encodes `models` with bincode. The primitives of meshes are written
+/// after the models, each list once however many meshes share it, followed
+/// by the index of each mesh's list.
+pub fn serialize_models(models: &[Model]) -> Result<Vec<u8>> {
+    let (primitives, indices) = share_primitives(models.iter().map(Model::mesh));
+
+    Ok(serialize(&(models, primitives, indices), Infinite)?)
+}
+
+/// Decodes models encoded by `serialize_models`. Meshes that shared
+/// primitives share them again. Bytes left over after the models fail with
+/// `ConvertError::InvalidContainer`.
+pub fn deserialize_models(bytes: &[u8]) -> Result<Vec<Model>> {
+    let mut reader = bytes;
+    let (mut models, primitives, indices): (Vec<Model>, Vec<Vec<Primitive>>, Vec<usize>) =
+        deserialize_from(&mut reader, Infinite)?;
+    if !reader.is_empty() {
+        return Err(Error::Convert(ConvertError::InvalidContainer));
+    }
+    restore_primitives(models.iter_mut().map(|model| &mut model.mesh), primitives, &indices)?;
 
     Ok(models)
 }
 
+/// Writes `models` to `writer` in the wg3d format: `WG3D_MAGIC`, the
+/// `u16` major and minor versions, the `u64` length of the models encoded
+/// by `serialize_models`, all little endian, then the encoded models.
+pub fn write_wg3d<W: Write>(
+    models: &[Model],
+    writer: &mut W,
+) -> Result<()> {
+    let bytes = serialize_models(models)?;
+    writer.write_all(WG3D_MAGIC)?;
+    writer.write_u16::<LE>(WG3D_MAJOR_VERSION)?;
+    writer.write_u16::<LE>(WG3D_MINOR_VERSION)?;
+    writer.write_u64::<LE>(bytes.len() as u64)?;
+    writer.write_all(&bytes)?;
+
+    Ok(())
+}
+
+/// Reads models written by `write_wg3d`. Input without the magic, shorter
+/// than its recorded length, or whose models do not fill it, fails with
+/// `ConvertError::InvalidContainer`, and another major version with
+/// `ConvertError::UnsupportedVersion`.
+pub fn read_wg3d<R: Read>(
+    reader: &mut R,
+) -> Result<Vec<Model>> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)
+        .map_err(|_| Error::Convert(ConvertError::InvalidContainer))?;
+    if &magic != WG3D_MAGIC {
+        return Err(Error::Convert(ConvertError::InvalidContainer));
+    }
+    let major = reader.read_u16::<LE>()
+        .map_err(|_| Error::Convert(ConvertError::InvalidContainer))?;
+    reader.read_u16::<LE>()
+        .map_err(|_| Error::Convert(ConvertError::InvalidContainer))?;
+    if major != WG3D_MAJOR_VERSION {
+        return Err(Error::Convert(ConvertError::UnsupportedVersion {
+            found: major,
+            expected: WG3D_MAJOR_VERSION,
+        }));
+    }
+
+    let length = reader.read_u64::<LE>()
+        .map_err(|_| Error::Convert(ConvertError::InvalidContainer))?;
+    let mut bytes = Vec::<u8>::new();
+    reader.take(length).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != length {
+        return Err(Error::Convert(ConvertError::InvalidContainer));
+    }
+
+    deserialize_models(&bytes)
+}
+
+/// Converts only the skins of a glTF file and the animations of each,
+/// without decoding any meshes, materials or textures. Each skin gets the
+/// part of every animation that drives it, as from `Animation::for_skin`.
+pub fn get_rig<P: AsRef<Path>>(
+    path: P,
+) -> Result<Vec<(Skin, Vec<Animation>)>> {
+    let cwd = current_dir()?;
+    let parent = path.as_ref().parent().unwrap_or(&cwd);
+    let (gltf, extensions, buffers) = import(&path, &parent)?;
+
+    let options = Options::default();
+    let skins = get_skins(gltf.skins(), &buffers, &extensions, &options)?;
+    let animations = get_animations(gltf.animations(), &skins, &buffers, &options, &mut Vec::new())?;
+
+    Ok(skins.into_skins().into_iter().enumerate().map(|(index, skin)| {
+        let skin_animations = animations.animations().iter()
+            .filter_map(|animation| animation.for_skin(index))
+            .collect::<Vec<_>>();
+
+        (skin, skin_animations)
+    }).collect())
+}
+
+/// Which base color texture `thumbnail` returns.
+#[derive(Clone, Debug)]
+pub enum ThumbnailSlot {
+    /// The base color texture of the first material that has one.
+    FirstBaseColor,
+    /// The base color texture of the named material.
+    Material(String),
+}
+
+/// Decodes a single base color texture for previewing an asset, without
+/// converting any geometry.
+pub fn thumbnail<P: AsRef<Path>>(
+    path: P,
+    slot: ThumbnailSlot,
+) -> Result<DynamicImage> {
+    let cwd = current_dir()?;
+    let parent = path.as_ref().parent().unwrap_or(&cwd);
+    let (gltf, _, buffers) = import(&path, &parent)?;
+
+    let texture = match slot {
+        ThumbnailSlot::FirstBaseColor => {
+            gltf.materials()
+                .filter_map(|material| material.pbr_metallic_roughness().base_color_texture())
+                .next()
+                .ok_or(ConvertError::NoThumbnail)?
+        },
+        ThumbnailSlot::Material(name) => {
+            gltf.materials()
+                .find(|material| material.name() == Some(name.as_ref()))
+                .ok_or_else(|| ConvertError::MaterialNotFound { name: name.clone() })?
+                .pbr_metallic_roughness()
+                .base_color_texture()
+                .ok_or(ConvertError::NoThumbnail)?
+        },
+    };
+
+    load_image(&parent, &texture.texture().source(), &buffers)
+}
+
+/// Lists the material slots each texture of a glTF file feeds, for
+/// texture artists.
+pub fn texture_bindings<P: AsRef<Path>>(
+    path: P,
+) -> Result<Vec<TextureBinding>> {
+    let cwd = current_dir()?;
+    let parent = path.as_ref().parent().unwrap_or(&cwd);
+    let (gltf, extensions, buffers) = import(&path, &parent)?;
+
+    let options = Options::default();
+    let textures = get_textures(&parent, gltf.textures(), &buffers, &options, &mut Vec::new())?;
+    let materials = get_materials(gltf.materials(), &textures, &extensions)?;
+
+    Ok(materials.texture_bindings(&textures))
+}
+
+/// Reads a `.gltf` or `.glb` file along with all of its buffers.
+pub fn import<'a, P: AsRef<Path>>(
+    path: P,
+    base_path: &'a Path,
+) -> Result<(Gltf, Extensions, Buffers)> {
+    let mut data = Vec::<u8>::new();
+    File::open(path)?.read_to_end(&mut data)?;
+
+    let (json, bin) = if data.starts_with(b"glTF") {
+        let glb = Glb::from_slice(&data)?;
+        (glb.json, glb.bin)
+    } else {
+        (&data[..], None)
+    };
+
+    let root = json_from_slice::<Value>(json).map_err(GltfError::from)?;
+    let gltf = Gltf::from_value(root.clone())?.validate_minimally()?;
+    let extensions = Extensions::new(root);
+    check_views(gltf.views())?;
+    check_meshopt(gltf.views(), &extensions)?;
+    let buffers = get_buffers(base_path, gltf.buffers(), bin)?;
+
+    Ok((gltf, extensions, buffers))
+}
+
 pub fn get_models<'a>(
     scene: &'a Scene,
     buffers: &'a Buffers,
     materials: &'a Materials,
+    skins: &'a Skins,
+    animations: &'a AnimationSet,
+    extensions: &'a Extensions,
+    options: &'a Options,
+    mut checkpoint: Option<&'a mut Checkpoint>,
+    warnings: &'a mut Vec<ConvertWarning>,
 ) -> Result<Vec<Model>> {
     let mut models = Vec::<Model>::new();
+    let mut mesh_cache = MeshCache::new();
 
     for root_node in scene.nodes() {
         get_models_helper(
             &root_node,
+            &Matrix4::identity(),
             &mut models,
             buffers,
             materials,
+            skins,
+            animations,
+            extensions,
+            options,
+            &mut checkpoint,
+            &mut mesh_cache,
+            warnings,
         )?;
     }
 
     Ok(models)
 }
 
+/// Converts every mesh of a file without scenes, untransformed and
+/// unskinned.
+fn get_library_models<'a>(
+    meshes: Meshes,
+    buffers: &'a Buffers,
+    materials: &'a Materials,
+    options: &'a Options,
+    warnings: &'a mut Vec<ConvertWarning>,
+) -> Result<Vec<Model>> {
+    meshes.map(|mesh| {
+        let name = mesh.name().ok_or(ConvertError::NoName)?;
+        let mesh = get_mesh(&mesh, name, None, false, None, buffers, materials, options, &mut MeshCache::new(), warnings)?;
+
+        Ok(Model {
+            node_index: None,
+            transform: Matrix4::identity(),
+            mesh: mesh,
+            skin: None,
+            animations: Vec::new(),
+        })
+    }).collect()
+}
+
 fn get_models_helper<'a>(
     node: &'a Node,
+    parent_transform: &'a Matrix4<f32>,
     models: &'a mut Vec<Model>,
     buffers: &'a Buffers,
     materials: &'a Materials,
+    skins: &'a Skins,
+    animations: &'a AnimationSet,
+    extensions: &'a Extensions,
+    options: &'a Options,
+    checkpoint: &'a mut Option<&mut Checkpoint>,
+    mesh_cache: &'a mut MeshCache,
+    warnings: &'a mut Vec<ConvertWarning>,
 ) -> Result<()> {
-    // Add model if mesh is present.
-    if let Some(mesh) = node.mesh() {
+    // Skip hidden nodes along with all of their children.
+    if is_hidden(node, extensions, options) {
+        warnings.push(ConvertWarning::HiddenNode {
+            index: node.index(),
+            name: node.name().map(String::from),
+        });
+        return Ok(());
+    }
+
+    let transform = parent_transform * local_transform(node, options);
+    let recorded = match *checkpoint {
+        Some(ref checkpoint) if checkpoint.is_complete(node.index()) => Some(checkpoint.load(node.index())?),
+        _ => None,
+    };
+
+    // Add model if mesh is present, reusing the models of an earlier run.
+    if let Some(recorded) = recorded {
+        models.extend(recorded);
+    } else if let Some(mesh) = node.mesh() {
         let name = node.name().ok_or(ConvertError::NoName)?;
         let weights = node.weights();
-        let has_bones = node.skin().is_some();
-        let mesh = get_mesh(&mesh, name, weights, has_bones, buffers, materials)?;
-        models.push(Model { mesh: mesh });
+        let has_skin = node.skin().is_some();
+        let joint_mapping = node.skin().and_then(|skin| {
+            skins.joint_mapping(skin.index()).map(|mapping| (skin.index(), mapping))
+        });
+        let mut mesh = get_mesh(
+            &mesh,
+            name,
+            weights,
+            has_skin,
+            joint_mapping,
+            buffers,
+            materials,
+            options,
+            mesh_cache,
+            warnings,
+        )?;
+        let blended = match options.split_by_alpha_mode {
+            true => mesh.split_blended(materials),
+            false => None,
+        };
+
+        let skin_index = node.skin().map(|skin| skin.index());
+        let skin = skin_index.and_then(|index| skins.skins().get(index));
+        let node_animations = skin_index.map_or(Vec::new(), |index| {
+            animations.animations().iter()
+                .filter_map(|animation| animation.for_skin(index))
+                .collect::<Vec<_>>()
+        });
+        let node_models = Some(mesh).into_iter().chain(blended).map(|mesh| {
+            Model {
+                node_index: Some(node.index()),
+                transform: transform,
+                mesh: mesh,
+                skin: skin.cloned(),
+                animations: node_animations.clone(),
+            }
+        }).collect::<Vec<_>>();
+
+        if let Some(ref mut checkpoint) = *checkpoint {
+            checkpoint.record(node.index(), &node_models)?;
+        }
+        models.extend(node_models);
     }
     
     // Try to find models in child nodes.
     for node in node.children() {
-        get_models_helper(&node, models, buffers, materials)?;
+        get_models_helper(
+            &node,
+            &transform,
+            models,
+            buffers,
+            materials,
+            skins,
+            animations,
+            extensions,
+            options,
+            checkpoint,
+            mesh_cache,
+            warnings,
+        )?;
     }
 
     Ok(())
 }
 
+fn is_hidden<'a>(
+    node: &'a Node,
+    extensions: &'a Extensions,
+    options: &'a Options,
+) -> bool {
+    let invisible = extensions.node(node.index(), "KHR_node_visibility")
+        .and_then(|ext| ext.get("visible"))
+        .and_then(Value::as_bool)
+        .map_or(false, |visible| !visible);
+    let prefixed = match (node.name(), options.hidden_prefix.as_ref()) {
+        (Some(name), Some(prefix)) => name.starts_with(prefix.as_str()),
+        _ => false,
+    };
+
+    invisible || prefixed
+}
+
+/// Transform of a node relative to its parent, mirrored along the Z axis
+/// for left handed output.
+fn local_transform<'a>(
+    node: &'a Node,
+    options: &'a Options,
+) -> Matrix4<f32> {
+    let transform = Matrix4::from(node.transform().matrix());
+    match options.handedness {
+        Handedness::Right => transform,
+        Handedness::Left => mirror_z(transform),
+    }
+}
+
+/// Mirrors a transform along the Z axis on both sides, turning a right
+/// handed transform into the matching left handed one.
+fn mirror_z(transform: Matrix4<f32>) -> Matrix4<f32> {
+    let mirror = Matrix4::from_nonuniform_scale(1.0, 1.0, -1.0);
+    mirror * transform * mirror
+}
+
 /// Error container for handling Wg3d
 #[derive(Debug)]
 pub enum ConvertError {
@@ -102,6 +791,32 @@ pub enum ConvertError {
     MissingAttributes,
     /// Image buffer not present
     MissingImageBuffer,
+    /// Buffer data shorter than its declared length
+    InvalidBufferLength,
+    /// Buffer view extends past the end of its buffer
+    BufferViewOverrun {
+        view: usize,
+    },
+    /// Accessor items are not the type they are read as
+    InvalidAccessor {
+        accessor: usize,
+    },
+    /// Accessor items extend past the end of its buffer view
+    AccessorOverrun {
+        accessor: usize,
+    },
+    /// `data:` URI is not valid base64 data
+    InvalidDataUri,
+    /// Resource URI escapes the glTF's base directory
+    UnsafeResourcePath {
+        uri: String,
+    },
+    /// Material references a texture that was not converted
+    MaterialTextureMissing {
+        material: String,
+        slot: &'static str,
+        texture_index: usize,
+    },
     /// No specified root node of skeleton for a skin
     NoSkeleton,
     /// No default scene present
@@ -112,8 +827,63 @@ pub enum ConvertError {
     InvalidJoint,
     /// Too many joints
     TooManyJoints,
-    /// No material assigned
+    /// Material samples a texture coordinate set its primitive lacks
+    MissingTexCoordSet {
+        material: String,
+        slot: &'static str,
+        set: u32,
+    },
+    /// Occlusion strength outside of `[0, 1]`
+    InvalidOcclusionStrength {
+        material: String,
+        strength: f32,
+    },
+    /// Checkpoint manifest is malformed, or was left by a run with another
+    /// input file or other options
+    InvalidCheckpoint,
+    /// Serialized output is truncated or malformed
+    InvalidContainer,
+    /// Serialized output uses a compression method this build cannot read
+    UnsupportedCompression {
+        method: u8,
+    },
+    /// Serialized output has a major version this build cannot read
+    UnsupportedVersion {
+        found: u16,
+        expected: u16,
+    },
+    /// Vertex normal is not unit length
+    NonUnitNormal {
+        vertex: usize,
+    },
+    /// Primitive has no material
     NoMaterial,
+    /// No material has the requested name
+    MaterialNotFound {
+        name: String,
+    },
+    /// Primitive has more vertices than `u32` indices can address
+    VertexCountOverflow {
+        count: u64,
+    },
+    /// Primitive index names a vertex the primitive does not have
+    IndexOutOfRange {
+        index: u32,
+        vertex_count: usize,
+    },
+    /// No base color texture to use as a thumbnail
+    NoThumbnail,
+    /// KTX2 texture is malformed or not BC1-BC7 compressed
+    UnsupportedKtx2,
+    /// Buffer view is only readable by decoding `EXT_meshopt_compression`
+    MeshoptNotSupported {
+        view: usize,
+    },
+    /// Attribute set index is past the four glTF allows
+    UnsupportedAttributeSet {
+        name: &'static str,
+        index: u32,
+    },
     /// Something weird
     Other,
 }
@@ -127,6 +897,33 @@ impl fmt::Display for ConvertError {
             ConvertError::MissingImageBuffer => {
                 write!(fmt, "Missing image buffer")
             },
+            ConvertError::InvalidBufferLength => {
+                write!(fmt, "Buffer length does not match file size")
+            },
+            ConvertError::BufferViewOverrun { view } => {
+                write!(fmt, "Buffer view {} extends past the end of its buffer", view)
+            },
+            ConvertError::InvalidAccessor { accessor } => {
+                write!(fmt, "Accessor {} has an unexpected component type or dimensions", accessor)
+            },
+            ConvertError::AccessorOverrun { accessor } => {
+                write!(fmt, "Accessor {} extends past the end of its buffer view", accessor)
+            },
+            ConvertError::InvalidDataUri => {
+                write!(fmt, "Data URI is not valid base64 data")
+            },
+            ConvertError::UnsafeResourcePath { ref uri } => {
+                write!(fmt, "Resource path {} escapes the base directory", uri)
+            },
+            ConvertError::MaterialTextureMissing { ref material, slot, texture_index } => {
+                write!(
+                    fmt,
+                    "Material {} references missing {} texture {}",
+                    material,
+                    slot,
+                    texture_index,
+                )
+            },
             ConvertError::NoSkeleton => {
                 write!(fmt, "No specified root node of skeleton for a skin")
             },
@@ -142,8 +939,56 @@ impl fmt::Display for ConvertError {
             ConvertError::TooManyJoints => {
                 write!(fmt, "Too many joints")
             },
+            ConvertError::MissingTexCoordSet { ref material, slot, set } => {
+                write!(
+                    fmt,
+                    "Material {} samples its {} texture with missing texture coordinate set {}",
+                    material,
+                    slot,
+                    set,
+                )
+            },
+            ConvertError::InvalidOcclusionStrength { ref material, strength } => {
+                write!(fmt, "Material {} has occlusion strength {} outside of [0, 1]", material, strength)
+            },
+            ConvertError::InvalidCheckpoint => {
+                write!(fmt, "Checkpoint manifest is malformed or belongs to another conversion")
+            },
+            ConvertError::InvalidContainer => {
+                write!(fmt, "Serialized output is truncated or malformed")
+            },
+            ConvertError::UnsupportedCompression { method } => {
+                write!(fmt, "Serialized output uses unsupported compression method {}", method)
+            },
+            ConvertError::UnsupportedVersion { found, expected } => {
+                write!(fmt, "Serialized output has major version {}, expected {}", found, expected)
+            },
+            ConvertError::NonUnitNormal { vertex } => {
+                write!(fmt, "Normal of vertex {} is not unit length", vertex)
+            },
             ConvertError::NoMaterial => {
-                write!(fmt, "No material assigned")
+                write!(fmt, "Primitive has no material")
+            },
+            ConvertError::MaterialNotFound { ref name } => {
+                write!(fmt, "No material named {}", name)
+            },
+            ConvertError::VertexCountOverflow { count } => {
+                write!(fmt, "Primitive has {} vertices, more than u32 indices can address", count)
+            },
+            ConvertError::IndexOutOfRange { index, vertex_count } => {
+                write!(fmt, "Primitive index {} is out of range for {} vertices", index, vertex_count)
+            },
+            ConvertError::NoThumbnail => {
+                write!(fmt, "No base color texture to use as a thumbnail")
+            },
+            ConvertError::MeshoptNotSupported { view } => {
+                write!(fmt, "Buffer view {} uses EXT_meshopt_compression, which is not supported", view)
+            },
+            ConvertError::UnsupportedAttributeSet { name, index } => {
+                write!(fmt, "Attribute {}_{} is past the four sets glTF allows", name, index)
+            },
+            ConvertError::UnsupportedKtx2 => {
+                write!(fmt, "KTX2 texture is malformed or not BC1-BC7 compressed")
             },
             ConvertError::Other => {
                 write!(fmt, "Something weird happened")
@@ -156,12 +1001,33 @@ impl error::Error for ConvertError {
     fn description(&self) -> &str {
         static MISSING_ATTRIBUTES: &'static str = "Primitive missing required attributes";
         static MISSING_IMAGE_BUFFER: &'static str = "Missing image buffer";
+        static INVALID_BUFFER_LENGTH: &'static str = "Buffer length does not match file size";
+        static BUFFER_VIEW_OVERRUN: &'static str = "Buffer view extends past the end of its buffer";
+        static INVALID_ACCESSOR: &'static str = "Accessor has an unexpected component type or dimensions";
+        static ACCESSOR_OVERRUN: &'static str = "Accessor extends past the end of its buffer view";
+        static INVALID_DATA_URI: &'static str = "Data URI is not valid base64 data";
+        static UNSAFE_RESOURCE_PATH: &'static str = "Resource path escapes the base directory";
+        static MATERIAL_TEXTURE_MISSING: &'static str = "Material references missing texture";
         static NO_SKELETON: &'static str = "No specified root node of skeleton for a skin";
         static NO_DEFAULT_SCENE: &'static str = "No default scene present";
         static NO_NAME: &'static str = "No name for a mesh, skin, or animation";
         static INVALID_JOINT: &'static str = "Invalid skeleton joint index";
         static TOO_MANY_JOINTS: &'static str = "Too many joints";
-        static NO_MATERIAL: &'static str = "No material assigned";
+        static MISSING_TEX_COORD_SET: &'static str = "Material samples a missing texture coordinate set";
+        static INVALID_OCCLUSION_STRENGTH: &'static str = "Occlusion strength outside of [0, 1]";
+        static NON_UNIT_NORMAL: &'static str = "Vertex normal is not unit length";
+        static NO_MATERIAL: &'static str = "Primitive has no material";
+        static MATERIAL_NOT_FOUND: &'static str = "No material has the requested name";
+        static INVALID_CHECKPOINT: &'static str = "Checkpoint manifest is malformed or belongs to another conversion";
+        static INVALID_CONTAINER: &'static str = "Serialized output is truncated or malformed";
+        static UNSUPPORTED_COMPRESSION: &'static str = "Serialized output uses an unsupported compression method";
+        static UNSUPPORTED_VERSION: &'static str = "Serialized output has an unsupported major version";
+        static VERTEX_COUNT_OVERFLOW: &'static str = "Primitive has more vertices than u32 indices can address";
+        static INDEX_OUT_OF_RANGE: &'static str = "Primitive index is out of range";
+        static NO_THUMBNAIL: &'static str = "No base color texture to use as a thumbnail";
+        static UNSUPPORTED_KTX2: &'static str = "KTX2 texture is malformed or not BC1-BC7 compressed";
+        static MESHOPT_NOT_SUPPORTED: &'static str = "Buffer view uses EXT_meshopt_compression, which is not supported";
+        static UNSUPPORTED_ATTRIBUTE_SET: &'static str = "Attribute set is past the four glTF allows";
         static OTHER: &'static str = "Something weird happened";
 
         match *self {
@@ -171,6 +1037,27 @@ impl error::Error for ConvertError {
             ConvertError::MissingImageBuffer => {
                 MISSING_IMAGE_BUFFER
             },
+            ConvertError::InvalidBufferLength => {
+                INVALID_BUFFER_LENGTH
+            },
+            ConvertError::BufferViewOverrun { .. } => {
+                BUFFER_VIEW_OVERRUN
+            },
+            ConvertError::InvalidAccessor { .. } => {
+                INVALID_ACCESSOR
+            },
+            ConvertError::AccessorOverrun { .. } => {
+                ACCESSOR_OVERRUN
+            },
+            ConvertError::InvalidDataUri => {
+                INVALID_DATA_URI
+            },
+            ConvertError::UnsafeResourcePath { .. } => {
+                UNSAFE_RESOURCE_PATH
+            },
+            ConvertError::MaterialTextureMissing { .. } => {
+                MATERIAL_TEXTURE_MISSING
+            },
             ConvertError::NoSkeleton => {
                 NO_SKELETON
             },
@@ -186,9 +1073,51 @@ impl error::Error for ConvertError {
             ConvertError::TooManyJoints => {
                 TOO_MANY_JOINTS
             },
+            ConvertError::MissingTexCoordSet { .. } => {
+                MISSING_TEX_COORD_SET
+            },
+            ConvertError::InvalidOcclusionStrength { .. } => {
+                INVALID_OCCLUSION_STRENGTH
+            },
+            ConvertError::InvalidCheckpoint => {
+                INVALID_CHECKPOINT
+            },
+            ConvertError::InvalidContainer => {
+                INVALID_CONTAINER
+            },
+            ConvertError::UnsupportedCompression { .. } => {
+                UNSUPPORTED_COMPRESSION
+            },
+            ConvertError::UnsupportedVersion { .. } => {
+                UNSUPPORTED_VERSION
+            },
+            ConvertError::NonUnitNormal { .. } => {
+                NON_UNIT_NORMAL
+            },
             ConvertError::NoMaterial => {
                 NO_MATERIAL
             }
+            ConvertError::MaterialNotFound { .. } => {
+                MATERIAL_NOT_FOUND
+            },
+            ConvertError::VertexCountOverflow { .. } => {
+                VERTEX_COUNT_OVERFLOW
+            },
+            ConvertError::IndexOutOfRange { .. } => {
+                INDEX_OUT_OF_RANGE
+            },
+            ConvertError::NoThumbnail => {
+                NO_THUMBNAIL
+            },
+            ConvertError::UnsupportedKtx2 => {
+                UNSUPPORTED_KTX2
+            },
+            ConvertError::MeshoptNotSupported { .. } => {
+                MESHOPT_NOT_SUPPORTED
+            },
+            ConvertError::UnsupportedAttributeSet { .. } => {
+                UNSUPPORTED_ATTRIBUTE_SET
+            },
             ConvertError::Other => {
                 OTHER
             },
@@ -198,8 +1127,129 @@ impl error::Error for ConvertError {
     fn cause(&self) -> Option<&error::Error> { None }
 }
 
+/// A problem found during conversion that did not stop it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConvertWarning {
+    /// Hidden node skipped along with its children
+    HiddenNode {
+        index: usize,
+        name: Option<String>,
+    },
+    /// Texture rebound to texture coordinate set 0
+    TexCoordDowngraded {
+        material: String,
+        slot: &'static str,
+        set: u32,
+    },
+    /// Joint transform does not match its inverse bind matrix
+    BindPoseMismatch {
+        joint: String,
+    },
+    /// Node has a skin but its mesh lacks joints and weights, or the other
+    /// way around, so the mesh was converted unskinned
+    SkinMismatch {
+        node: String,
+        has_skin: bool,
+    },
+    /// Channel keyframes were not in time order and were sorted
+    ReorderedKeyframes {
+        animation: String,
+        joint: String,
+    },
+    /// Texture image could not be loaded and was replaced by a placeholder
+    PlaceholderTexture {
+        texture: String,
+    },
+    /// Attribute set past the four glTF allows was left out
+    UnsupportedAttributeSet {
+        node: String,
+        name: &'static str,
+        index: u32,
+    },
+    /// Camera channel without linear interpolation was left out
+    NonLinearCameraChannel {
+        animation: String,
+        node_index: usize,
+    },
+    /// Texture exceeds `Options::max_texture_size` but its format cannot be
+    /// resized, so it was kept as it is
+    OversizedTexture {
+        texture: String,
+    },
+    /// Occlusion strength could not be applied to the texture's format, so
+    /// the texture and its occlusion maps were left as they are
+    OcclusionStrengthNotBaked {
+        texture: String,
+    },
+    /// Green channel of the normal map could not be flipped in the
+    /// texture's format, so it was left in the OpenGL convention
+    NormalMapNotFlipped {
+        texture: String,
+    },
+}
+
+impl fmt::Display for ConvertWarning {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConvertWarning::HiddenNode { index, ref name } => {
+                match *name {
+                    Some(ref name) => write!(fmt, "Skipped hidden node {}", name),
+                    None => write!(fmt, "Skipped hidden node {}", index),
+                }
+            },
+            ConvertWarning::TexCoordDowngraded { ref material, slot, set } => {
+                write!(
+                    fmt,
+                    "Rebound {} texture of material {} from missing texture coordinate set {} to 0",
+                    slot,
+                    material,
+                    set,
+                )
+            },
+            ConvertWarning::BindPoseMismatch { ref joint } => {
+                write!(fmt, "Joint {} does not match its inverse bind matrix", joint)
+            },
+            ConvertWarning::SkinMismatch { ref node, has_skin } => {
+                if has_skin {
+                    write!(fmt, "Node {} has a skin but its mesh lacks joints and weights", node)
+                } else {
+                    write!(fmt, "Node {} has no skin but its mesh has joints and weights", node)
+                }
+            },
+            ConvertWarning::ReorderedKeyframes { ref animation, ref joint } => {
+                write!(fmt, "Sorted out of order keyframes of joint {} in animation {}", joint, animation)
+            },
+            ConvertWarning::PlaceholderTexture { ref texture } => {
+                write!(fmt, "Replaced texture {} with a placeholder as its image could not be loaded", texture)
+            },
+            ConvertWarning::UnsupportedAttributeSet { ref node, name, index } => {
+                write!(fmt, "Left out attribute {}_{} of node {}, past the four sets glTF allows", name, index, node)
+            },
+            ConvertWarning::NonLinearCameraChannel { ref animation, node_index } => {
+                write!(
+                    fmt,
+                    "Left out channel of camera node {} in animation {}, as only linear interpolation is supported",
+                    node_index,
+                    animation,
+                )
+            },
+            ConvertWarning::OversizedTexture { ref texture } => {
+                write!(fmt, "Kept texture {} over the maximum texture size, as its format cannot be resized", texture)
+            },
+            ConvertWarning::OcclusionStrengthNotBaked { ref texture } => {
+                write!(fmt, "Left occlusion strength of texture {} unbaked, as its format cannot be changed", texture)
+            },
+            ConvertWarning::NormalMapNotFlipped { ref texture } => {
+                write!(fmt, "Left normal map {} in the OpenGL convention, as its format cannot be changed", texture)
+            },
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use cgmath::Vector3;
+
     use super::*;
 
     #[test]
@@ -213,4 +1263,577 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_primitive_materials() {
+        let path = Path::new("testmodels/gltf2/Monster/Monster.gltf");
+        let conversion = get(path).unwrap();
+        let primitives = conversion.models().iter()
+            .flat_map(|model| model.mesh().primitives())
+            .collect::<Vec<_>>();
+
+        assert!(!primitives.is_empty());
+        assert!(primitives.iter().all(|primitive| primitive.material() == "monster-effect"));
+    }
+
+    #[test]
+    fn test_model_skin_and_animations() {
+        let path = Path::new("testmodels/gltf2/Monster/Monster.gltf");
+        let conversion = get(path).unwrap();
+        let model = &conversion.models()[0];
+
+        assert!(!model.skin().unwrap().joints().is_empty());
+        assert!(!model.animations().is_empty());
+        assert!(model.animations().iter().all(|animation| !animation.channels().is_empty()));
+
+        // Models without skins carry no animations.
+        let path = Path::new("testmodels/gltf2/ThreeQuads/ThreeQuads.gltf");
+        let conversion = get(path).unwrap();
+        assert!(conversion.models()[0].skin().is_none());
+        assert!(conversion.models()[0].animations().is_empty());
+    }
+
+    #[test]
+    fn test_stats() {
+        use image::{GenericImage, open as open_image};
+
+        let path = Path::new("testmodels/gltf2/Monster/Monster.gltf");
+        let (_, stats) = get_with_stats(path, &Options::default()).unwrap();
+        let (gltf, _, _) = import(path, path.parent().unwrap()).unwrap();
+
+        let mesh = gltf.meshes().next().unwrap();
+        let vertices = mesh.primitives()
+            .map(|primitive| primitive.get(&Semantic::Positions).unwrap().count())
+            .sum::<usize>();
+        let indices = mesh.primitives()
+            .map(|primitive| primitive.indices().unwrap().count())
+            .sum::<usize>();
+        let (width, height) = open_image("testmodels/gltf2/Monster/Monster.jpg").unwrap().dimensions();
+        let keyframes = gltf.animations()
+            .flat_map(|animation| animation.channels().map(|channel| channel.sampler().input().count()).collect::<Vec<_>>())
+            .sum::<usize>();
+
+        assert_eq!(stats, ConvertStats {
+            vertices: vertices,
+            triangles: indices / 3,
+            textures: 1,
+            texture_bytes: (width * height * 3) as usize,
+            materials: gltf.materials().count(),
+            joints: gltf.skins().next().unwrap().joints().count(),
+            animations: gltf.animations().count(),
+            keyframes: keyframes,
+        });
+    }
+
+    #[test]
+    fn test_scene_extras() {
+        let path = Path::new("testmodels/gltf2/SceneExtras/SceneExtras.gltf");
+        let conversion = get(path).unwrap();
+        let extras = conversion.extras().unwrap();
+
+        assert!(conversion.models().is_empty());
+        assert_eq!(extras["gravity"].as_f64(), Some(-9.8));
+        assert_eq!(extras["ambientColor"][2].as_f64(), Some(0.2));
+    }
+
+    #[test]
+    fn test_asset_info() {
+        let path = Path::new("testmodels/gltf2/Monster/Monster.gltf");
+        let conversion = get(path).unwrap();
+
+        assert_eq!(conversion.asset(), &AssetInfo {
+            generator: Some(String::from("COLLADA2GLTF")),
+            copyright: None,
+            version: String::from("2.0"),
+        });
+    }
+
+    #[test]
+    fn test_sort_by_alpha_mode() {
+        let path = Path::new("testmodels/gltf2/MixedAlpha/MixedAlpha.gltf");
+        let material_names = |conversion: &Conversion| {
+            conversion.models()[0].mesh().primitives().iter()
+                .map(|primitive| primitive.material().to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        let conversion = get(path).unwrap();
+        assert_eq!(material_names(&conversion), vec!["glass", "stone", "leaves", "rock"]);
+
+        let mut options = Options::default();
+        options.sort_by_alpha_mode = true;
+        let conversion = get_with_options(path, &options).unwrap();
+        assert_eq!(material_names(&conversion), vec!["stone", "rock", "leaves", "glass"]);
+    }
+
+    #[test]
+    fn test_split_by_alpha_mode() {
+        let path = Path::new("testmodels/gltf2/MixedAlpha/MixedAlpha.gltf");
+        let mut options = Options::default();
+        options.split_by_alpha_mode = true;
+        let conversion = get_with_options(path, &options).unwrap();
+        let material_names = |model: &Model| {
+            model.mesh().primitives().iter()
+                .map(|primitive| primitive.material().to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        // Masked primitives stay with the opaque ones.
+        assert_eq!(conversion.models().len(), 2);
+        let (opaque, blended) = (&conversion.models()[0], &conversion.models()[1]);
+        assert_eq!(material_names(opaque), vec!["stone", "leaves", "rock"]);
+        assert_eq!(material_names(blended), vec!["glass"]);
+        assert_eq!(opaque.node_index(), blended.node_index());
+        assert_eq!(opaque.mesh().name(), blended.mesh().name());
+
+        // Meshes with a single kind of primitive are not split.
+        let path = Path::new("testmodels/gltf2/ThreeQuads/ThreeQuads.gltf");
+        let conversion = get_with_options(path, &options).unwrap();
+        assert_eq!(conversion.models().len(), 3);
+    }
+
+    #[test]
+    fn test_get_rig() {
+        let path = Path::new("testmodels/gltf2/Monster/Monster.gltf");
+        let conversion = get(path).unwrap();
+        let rig = get_rig(path).unwrap();
+
+        assert_eq!(rig.len(), 1);
+        let (ref skin, ref animations) = rig[0];
+        assert_eq!(skin.joints().len(), 32);
+        assert_eq!(
+            animations.iter().map(Animation::name).collect::<Vec<_>>(),
+            conversion.animations().names(),
+        );
+
+        // The sway moves the spine of the body and the tail of the tail, so
+        // each skin gets its own part of it.
+        let rig = get_rig("testmodels/gltf2/SharedJoint/SharedJoint.gltf").unwrap();
+        let clips = |index: usize| {
+            rig[index].1.iter()
+                .map(|animation| (animation.name().to_owned(), animation.channels().len()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(clips(0), vec![(String::from("sway"), 1)]);
+        assert_eq!(clips(1), vec![(String::from("wag"), 2), (String::from("sway"), 1)]);
+    }
+
+    #[test]
+    fn test_dedup_materials() {
+        let path = Path::new("testmodels/gltf2/DuplicateMaterial/DuplicateMaterial.gltf");
+        let mut options = Options::default();
+        options.dedup_materials = true;
+        let (conversion, stats) = get_with_stats(path, &options).unwrap();
+        let material_names = conversion.models()[0].mesh().primitives().iter()
+            .map(|primitive| primitive.material())
+            .collect::<Vec<_>>();
+
+        assert_eq!(stats.materials, 2);
+        assert_eq!(material_names, vec!["red", "red", "blue"]);
+    }
+
+    #[test]
+    fn test_skin_without_joints() {
+        use self::layout::Semantic as LayoutSemantic;
+
+        let path = Path::new("testmodels/gltf2/SkinWithoutJoints/SkinWithoutJoints.gltf");
+        let conversion = get(path).unwrap();
+        let (layout, _) = conversion.models()[0].mesh().primitives()[0].interleaved_bytes();
+
+        assert!(layout.attributes.iter().all(|attribute| attribute.semantic != LayoutSemantic::Joints));
+        assert_eq!(conversion.warnings(), &[ConvertWarning::SkinMismatch {
+            node: String::from("body"),
+            has_skin: true,
+        }]);
+    }
+
+    #[test]
+    fn test_sort_joints() {
+        use self::layout::Semantic as LayoutSemantic;
+
+        // Node of the joint each vertex is weighted to.
+        fn vertex_joints(model: &Model) -> Vec<usize> {
+            let skin = model.skin().unwrap();
+            let (layout, bytes) = model.mesh().primitives()[0].interleaved_bytes();
+            let offset = layout.attributes.iter()
+                .find(|attribute| attribute.semantic == LayoutSemantic::Joints)
+                .unwrap()
+                .offset;
+            bytes.chunks(layout.stride).map(|vertex| {
+                let joint = (&vertex[offset..]).read_u16::<LE>().unwrap();
+                (1..4).find(|&node| skin.get_joint_index(node) == Some(joint)).unwrap()
+            }).collect()
+        }
+
+        let path = Path::new("testmodels/gltf2/UnsortedJoints/UnsortedJoints.gltf");
+        let unsorted = get(path).unwrap();
+        let mut options = Options::default();
+        options.sort_joints = true;
+        let sorted = get_with_options(path, &options).unwrap();
+        let model = &sorted.models()[0];
+        let skin = model.skin().unwrap();
+        let indices = (1..4).map(|node| skin.get_joint_index(node).unwrap()).collect::<Vec<_>>();
+
+        // Nodes 1 to 3 are the hips, spine and head.
+        assert_eq!(indices, vec![0, 1, 2]);
+        assert_eq!(vertex_joints(model), vertex_joints(&unsorted.models()[0]));
+
+        let channel = &model.animations()[0].channels()[0];
+        assert_eq!(channel.joint_name(), "head");
+        assert_eq!(channel.joint_index(), 2);
+    }
+
+    #[test]
+    fn test_camera_animation() {
+        let path = Path::new("testmodels/gltf2/AnimatedCamera/AnimatedCamera.gltf");
+        let conversion = get(path).unwrap();
+        let animations = conversion.animations();
+
+        assert_eq!(animations.animations()[0].channels().len(), 0);
+        assert_eq!(animations.camera_animations().len(), 1);
+        let camera = &animations.camera_animations()[0];
+        assert_eq!(camera.animation(), "flythrough");
+        assert_eq!(camera.node_index(), 0);
+        assert_eq!(camera.keyframe_count(), 3);
+
+        // The cubic spline rotation channel is left out.
+        assert_eq!(conversion.warnings(), &[ConvertWarning::NonLinearCameraChannel {
+            animation: String::from("flythrough"),
+            node_index: 0,
+        }]);
+    }
+
+    #[test]
+    fn test_accessor_overrun() {
+        let path = Path::new("testmodels/gltf2/AccessorOverrun/AccessorOverrun.gltf");
+        match get(path) {
+            Err(Error::Convert(ConvertError::AccessorOverrun { accessor })) => assert_eq!(accessor, 0),
+            _ => panic!("expected AccessorOverrun"),
+        }
+    }
+
+    #[test]
+    fn test_default_material_name() {
+        let path = Path::new("testmodels/gltf2/NoMaterial/NoMaterial.gltf");
+        match get(path) {
+            Err(Error::Convert(ConvertError::NoMaterial)) => {},
+            _ => panic!("expected NoMaterial"),
+        }
+
+        let mut options = Options::default();
+        options.default_material_name = Some(String::from("engine_default"));
+        let (conversion, stats) = get_with_stats(path, &options).unwrap();
+        let primitive = &conversion.models()[0].mesh().primitives()[0];
+
+        assert_eq!(primitive.material(), "engine_default");
+        assert_eq!(stats.materials, 1);
+    }
+
+    #[test]
+    fn test_mesh_library() {
+        let path = Path::new("testmodels/gltf2/MeshLibrary/MeshLibrary.gltf");
+        let (conversion, stats) = get_with_stats(path, &Options::default()).unwrap();
+
+        assert_eq!(conversion.models().len(), 2);
+        assert!(conversion.scene().is_empty());
+        assert_eq!(stats.vertices, 8);
+        for model in conversion.models() {
+            assert_eq!(model.node_index(), None);
+            // The quads keep their own coordinates, at the origin.
+            let positions = model.mesh().primitives()[0].positions();
+            assert_eq!(positions[0], Vector3::new(0.0, 0.0, 0.0));
+            assert_eq!(positions[2], Vector3::new(1.0, 1.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn test_hidden_nodes() {
+        let path = Path::new("testmodels/gltf2/HiddenNode/HiddenNode.gltf");
+
+        let conversion = get(path).unwrap();
+        assert_eq!(conversion.models().len(), 2);
+        assert_eq!(conversion.warnings(), &[ConvertWarning::HiddenNode {
+            index: 1,
+            name: Some(String::from("proxy")),
+        }]);
+
+        let mut options = Options::default();
+        options.hidden_prefix = Some(String::from("_hidden"));
+        let conversion = get_with_options(path, &options).unwrap();
+        assert_eq!(conversion.models().len(), 1);
+        assert_eq!(conversion.warnings().len(), 2);
+    }
+
+    #[test]
+    fn test_thumbnail() {
+        use image::GenericImage;
+
+        let path = Path::new("testmodels/gltf2/Thumbnail/Thumbnail.gltf");
+
+        let first = thumbnail(path, ThumbnailSlot::FirstBaseColor).unwrap();
+        assert_eq!(first.dimensions(), (4, 2));
+
+        let named = thumbnail(path, ThumbnailSlot::Material(String::from("brick"))).unwrap();
+        assert_eq!(named.dimensions(), (8, 8));
+
+        match thumbnail(path, ThumbnailSlot::Material(String::from("glass"))) {
+            Err(Error::Convert(ConvertError::NoThumbnail)) => {},
+            _ => panic!("expected NoThumbnail"),
+        }
+        match thumbnail(path, ThumbnailSlot::Material(String::from("stone"))) {
+            Err(Error::Convert(ConvertError::MaterialNotFound { name })) => assert_eq!(name, "stone"),
+            _ => panic!("expected MaterialNotFound"),
+        }
+    }
+
+    #[test]
+    fn test_texture_bindings() {
+        let path = Path::new("testmodels/gltf2/SharedTexture/SharedTexture.gltf");
+        let bindings = texture_bindings(path).unwrap();
+
+        assert_eq!(bindings.len(), 2);
+        assert_eq!(bindings[0].texture(), "paint");
+        assert_eq!(bindings[0].uses(), &[
+            (String::from("glow"), "base_color"),
+            (String::from("glow"), "emission"),
+            (String::from("hull"), "metallic_roughness"),
+        ][..]);
+        assert_eq!(bindings[1].texture(), "rust");
+        assert!(bindings[1].uses().is_empty());
+    }
+
+    #[test]
+    fn test_serialize() {
+        use bincode::{Infinite, deserialize, serialize};
+
+        let path = Path::new("testmodels/gltf2/Monster/Monster.gltf");
+        let (conversion, _, textures, _) = get_with_textures(path, &Options::default()).unwrap();
+
+        // Serializing the deserialized values again gives the same bytes.
+        let bytes = serialize_models(&conversion.models).unwrap();
+        let models = deserialize_models(&bytes).unwrap();
+        assert_eq!(serialize_models(&models).unwrap(), bytes);
+
+        let bytes = serialize(textures.textures(), Infinite).unwrap();
+        let textures: Vec<Texture> = deserialize(&bytes).unwrap();
+        assert_eq!(serialize(&textures, Infinite).unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_embedded() {
+        // The buffer and the image are both data URIs beside the .gltf file.
+        let path = Path::new("testmodels/gltf2/Embedded/Embedded.gltf");
+        let (conversion, _, textures, _) = get_with_textures(path, &Options::default()).unwrap();
+
+        assert_eq!(conversion.models().len(), 1);
+        assert_eq!(conversion.models()[0].mesh().primitives()[0].vertex_count(), 4);
+        assert_eq!(textures.get(0), Some("red"));
+        assert_eq!(&textures.textures()[0].contents()[..4], &[255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_glb() {
+        // The binary version embeds the buffer and the normal map.
+        let gltf_path = Path::new("testmodels/gltf2/NormalMapQuad/NormalMapQuad.gltf");
+        let glb_path = Path::new("testmodels/gltf2/NormalMapQuad/NormalMapQuad.glb");
+        let (gltf, _, gltf_textures, _) = get_with_textures(gltf_path, &Options::default()).unwrap();
+        let (glb, _, glb_textures, _) = get_with_textures(glb_path, &Options::default()).unwrap();
+
+        assert_eq!(glb.models().len(), 1);
+        assert_eq!(serialize_models(glb.models()).unwrap(), serialize_models(gltf.models()).unwrap());
+        assert_eq!(glb_textures.textures(), gltf_textures.textures());
+    }
+
+    #[test]
+    fn test_write_wg3d() {
+        let path = Path::new("testmodels/gltf2/Monster/Monster.gltf");
+        let conversion = get(path).unwrap();
+
+        let mut bytes = Vec::<u8>::new();
+        write_wg3d(conversion.models(), &mut bytes).unwrap();
+        let models = read_wg3d(&mut &bytes[..]).unwrap();
+
+        assert_eq!(models.len(), conversion.models().len());
+        assert_eq!(models[0].node_index(), conversion.models()[0].node_index());
+        assert_eq!(models[0].mesh().primitives()[0].indices(), conversion.models()[0].mesh().primitives()[0].indices());
+
+        assert!(read_wg3d(&mut &bytes[..4]).is_err());
+    }
+
+    #[test]
+    fn test_read_wg3d() {
+        let path = Path::new("testmodels/gltf2/ThreeQuads/ThreeQuads.gltf");
+        let conversion = get(path).unwrap();
+        let mut bytes = Vec::<u8>::new();
+        write_wg3d(conversion.models(), &mut bytes).unwrap();
+
+        let models = read_wg3d(&mut &bytes[..]).unwrap();
+        let names = |models: &[Model]| {
+            models.iter().map(|model| model.mesh().name().map(String::from)).collect::<Vec<_>>()
+        };
+        assert_eq!(names(&models), names(conversion.models()));
+
+        // Truncated input is reported rather than decoded.
+        match read_wg3d(&mut &bytes[..bytes.len() - 1]) {
+            Err(Error::Convert(ConvertError::InvalidContainer)) => {},
+            _ => panic!("Expected an invalid container"),
+        }
+
+        // So are bytes the models do not use.
+        let mut padded = bytes.clone();
+        padded.push(0);
+        let length = (padded.len() - 16) as u64;
+        (&mut padded[8..16]).write_u64::<LE>(length).unwrap();
+        match read_wg3d(&mut &padded[..]) {
+            Err(Error::Convert(ConvertError::InvalidContainer)) => {},
+            _ => panic!("Expected an invalid container"),
+        }
+    }
+
+    #[test]
+    fn test_wg3d_header() {
+        let path = Path::new("testmodels/gltf2/ThreeQuads/ThreeQuads.gltf");
+        let conversion = get(path).unwrap();
+        let mut bytes = Vec::<u8>::new();
+        write_wg3d(conversion.models(), &mut bytes).unwrap();
+
+        assert_eq!(&bytes[0..4], b"WG3D");
+        assert_eq!(&bytes[4..8], &[1, 0, 0, 0]);
+        assert_eq!(read_wg3d(&mut &bytes[..]).unwrap().len(), conversion.models().len());
+
+        // Neither format reads the other's files.
+        match container::read(&mut &bytes[..]) {
+            Err(Error::Convert(ConvertError::InvalidContainer)) => {},
+            _ => panic!("Expected an invalid container"),
+        }
+        let mut container_bytes = Vec::<u8>::new();
+        container::write(&mut container_bytes, &chunk::get_chunks(conversion.models(), &[]), &Options::default()).unwrap();
+        match read_wg3d(&mut &container_bytes[..]) {
+            Err(Error::Convert(ConvertError::InvalidContainer)) => {},
+            _ => panic!("Expected an invalid container"),
+        }
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        match read_wg3d(&mut &bad_magic[..]) {
+            Err(Error::Convert(ConvertError::InvalidContainer)) => {},
+            _ => panic!("Expected an invalid container"),
+        }
+
+        let mut future = bytes.clone();
+        future[4] = 2;
+        match read_wg3d(&mut &future[..]) {
+            Err(Error::Convert(ConvertError::UnsupportedVersion { found: 2, expected: 1 })) => {},
+            _ => panic!("Expected an unsupported version"),
+        }
+
+        // A newer minor version is still readable.
+        let mut minor = bytes.clone();
+        minor[6] = 3;
+        assert!(read_wg3d(&mut &minor[..]).is_ok());
+    }
+
+    #[test]
+    fn test_model_transform() {
+        let path = Path::new("testmodels/gltf2/Hierarchy/Hierarchy.gltf");
+        let conversion = get(path).unwrap();
+        let world = |model: &Model, index: usize| {
+            let position = model.mesh().primitives()[0].positions()[index];
+            (model.transform() * position.extend(1.0)).truncate()
+        };
+
+        // The group moves both quads by 10, then the left one by -2.
+        let left = &conversion.models()[0];
+        assert_eq!(world(left, 1), left.mesh().primitives()[0].positions()[1] + Vector3::new(8.0, 0.0, 0.0));
+
+        // The right quad is doubled, so its normals are halved before renormalizing.
+        let right = &conversion.models()[1];
+        assert_eq!(world(right, 2), Vector3::new(12.0, 7.0, 0.0));
+        assert_eq!(right.normal_matrix() * Vector3::new(0.0, 0.0, 1.0), Vector3::new(0.0, 0.0, 0.5));
+    }
+
+    #[test]
+    fn test_instanced_mesh() {
+        let path = Path::new("testmodels/gltf2/InstancedMesh/InstancedMesh.gltf");
+        let conversion = get(path).unwrap();
+        let models = conversion.models();
+
+        // Both nodes use the primitives decoded for the first.
+        assert_eq!(models.len(), 2);
+        assert!(models[0].mesh().shares_primitives(models[1].mesh()));
+        assert_eq!(models[0].mesh().name(), Some("crate_a"));
+        assert_eq!(models[1].mesh().name(), Some("crate_b"));
+        assert_ne!(models[0].node_index(), models[1].node_index());
+
+        // Each node still reports the attribute set left out of the mesh.
+        let nodes = conversion.warnings().iter().map(|warning| {
+            match warning {
+                &ConvertWarning::UnsupportedAttributeSet { ref node, .. } => node.as_str(),
+                _ => "",
+            }
+        }).collect::<Vec<_>>();
+        assert_eq!(nodes, vec!["crate_a", "crate_b"]);
+
+        // The shared primitives are written once, and shared again when read.
+        let bytes = serialize_models(models).unwrap();
+        let single = serialize_models(&models[..1]).unwrap();
+        assert!(bytes.len() < 2 * single.len());
+        let read_models = deserialize_models(&bytes).unwrap();
+        assert!(read_models[0].mesh().shares_primitives(read_models[1].mesh()));
+        assert_eq!(read_models[1].mesh().primitives().len(), 1);
+
+        // Conversions can be handed to another thread.
+        fn assert_send<T: Send>() {}
+        assert_send::<Conversion>();
+    }
+
+    #[test]
+    fn test_get_many() {
+        let paths = [
+            Path::new("testmodels/gltf2/TextureLibrary/Wall.gltf"),
+            Path::new("testmodels/gltf2/TextureLibrary/Floor.gltf"),
+        ];
+        let (models, _, textures, _) = get_many(&paths, &Options::default()).unwrap();
+
+        // Both files use bricks, which is only stored once.
+        assert_eq!(models.len(), 2);
+        let names = textures.iter().map(Texture::name).collect::<Vec<_>>();
+        assert_eq!(names, vec!["bricks", "moss"]);
+        assert_eq!(models[1].mesh().primitives()[0].material(), "floor");
+    }
+
+    #[test]
+    fn test_get_many_renamed() {
+        let paths = [
+            Path::new("testmodels/gltf2/TextureLibrary/Wall.gltf"),
+            Path::new("testmodels/gltf2/TextureLibrary/Ceiling.gltf"),
+        ];
+        let (models, materials, textures, bindings) = get_many(&paths, &Options::default()).unwrap();
+
+        // The ceiling's bricks are tiles, while its slate is the wall's
+        // bricks under another name.
+        let names = textures.iter().map(Texture::name).collect::<Vec<_>>();
+        assert_eq!(names, vec!["bricks", "moss", "bricks_1"]);
+        let texture_names = bindings.iter().map(TextureBinding::texture).collect::<Vec<_>>();
+        assert_eq!(texture_names, names);
+
+        // The ceiling's own wall material samples the tiles, so it is not
+        // the wall's, and the ceiling mesh follows it to its new name.
+        let material_names = materials.iter().map(Material::name).collect::<Vec<_>>();
+        assert_eq!(material_names, vec!["wall", "moss", "wall_1", "trim"]);
+        assert_eq!(materials[2].texture_names(), vec![("base_color", "bricks_1")]);
+        assert_eq!(materials[3].texture_names(), vec![("base_color", "bricks")]);
+        assert_eq!(models[1].mesh().primitives()[0].material(), "wall_1");
+        assert_eq!(bindings[0].uses(), &[
+            (String::from("wall"), "base_color"),
+            (String::from("trim"), "base_color"),
+        ]);
+        assert_eq!(bindings[2].uses(), &[(String::from("wall_1"), "base_color")]);
+
+        // Pooling a file with itself adds nothing.
+        let paths = [paths[0], paths[0]];
+        let (_, materials, textures, _) = get_many(&paths, &Options::default()).unwrap();
+        assert_eq!(materials.len(), 2);
+        assert_eq!(textures.len(), 2);
+    }
 }
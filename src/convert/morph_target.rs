@@ -7,24 +7,159 @@ use cgmath::Vector3;
 use gltf::accessor::{Accessor, DataType, Dimensions};
 use gltf::accessor::sparse::{IndexType, Sparse as GltfSparse};
 use gltf::mesh::Primitive as GltfPrimitive;
-use gltf_importer::Buffers;
-use gltf_utils::{AccessorIter, Source};
+use gltf_utils::Source;
 
 use super::super::{Result, Error};
 use super::ConvertError;
+use super::buffer::Buffers;
+use super::options::Options;
+use super::util::{AccessorIter, PrimitiveIterators};
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct MorphTarget {
     // Currently all morph targets are `[f32; 3]`.
+    /// Deltas from the base positions, or, with
+    /// `Options::absolute_morph_targets`, full absolute positions.
     positions: Option<Data>,
     normals: Option<Data>,
     tangents: Option<Data>,
 }
 
+impl MorphTarget {
+    pub fn positions(&self) -> Option<&Data> {
+        self.positions.as_ref()
+    }
+
+    pub fn normals(&self) -> Option<&Data> {
+        self.normals.as_ref()
+    }
+
+    pub fn tangents(&self) -> Option<&Data> {
+        self.tangents.as_ref()
+    }
+
+    /// Reorders the data after the vertices of its primitive were
+    /// renumbered. `order` holds the old index of every new vertex.
+    pub fn permute(&mut self, order: &[u32]) {
+        for data in [&mut self.positions, &mut self.normals, &mut self.tangents].iter_mut() {
+            if let Some(ref mut data) = **data {
+                data.permute(order);
+            }
+        }
+    }
+
+    /// Copies the data of every vertex into the vertices split from it, as
+    /// by `Primitive::flat_normals`. `order` holds the old index of every
+    /// new vertex.
+    pub fn unweld(&mut self, order: &[u32]) {
+        for data in [&mut self.positions, &mut self.normals, &mut self.tangents].iter_mut() {
+            if let Some(ref mut data) = **data {
+                data.unweld(order);
+            }
+        }
+    }
+
+    /// Mirrors the data along the Z axis, as by `Primitive::mirror_z`.
+    pub fn mirror_z(&mut self) {
+        for data in [&mut self.positions, &mut self.normals, &mut self.tangents].iter_mut() {
+            if let Some(ref mut data) = **data {
+                data.mirror_z();
+            }
+        }
+    }
+
+    /// Extends the data to copies of the first `vertex_count` vertices
+    /// appended with flipped normals, as by `Primitive::duplicate_back_faces`.
+    pub fn duplicate(&mut self, vertex_count: u32) {
+        for data in [&mut self.positions, &mut self.tangents].iter_mut() {
+            if let Some(ref mut data) = **data {
+                data.duplicate(vertex_count, false);
+            }
+        }
+        if let Some(ref mut normals) = self.normals {
+            normals.duplicate(vertex_count, true);
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub enum Data {
     Full(Vec<Vector3<f32>>),
     Sparse(Vec<SparseDatum>),
 }
 
+impl Data {
+    fn mirror_z(&mut self) {
+        match *self {
+            Data::Full(ref mut values) => {
+                for value in values.iter_mut() { value.z = -value.z; }
+            },
+            Data::Sparse(ref mut data) => {
+                for datum in data.iter_mut() { datum.value.z = -datum.value.z; }
+            },
+        }
+    }
+
+    fn duplicate(&mut self, vertex_count: u32, negate: bool) {
+        let sign = if negate { -1.0 } else { 1.0 };
+        match *self {
+            Data::Full(ref mut values) => {
+                let copies = values.iter().map(|&value| value * sign).collect::<Vec<_>>();
+                values.extend(copies);
+            },
+            Data::Sparse(ref mut data) => {
+                let copies = data.iter().map(|datum| SparseDatum {
+                    index: datum.index + vertex_count,
+                    value: datum.value * sign,
+                }).collect::<Vec<_>>();
+                data.extend(copies);
+            },
+        }
+    }
+
+    fn unweld(&mut self, order: &[u32]) {
+        match *self {
+            Data::Full(ref mut values) => {
+                *values = order.iter().map(|&old| values[old as usize]).collect();
+            },
+            Data::Sparse(ref mut data) => {
+                let mut old_values = vec![None; order.len()];
+                for datum in data.iter() {
+                    if let Some(value) = old_values.get_mut(datum.index as usize) {
+                        *value = Some(datum.value);
+                    }
+                }
+                *data = order.iter().enumerate()
+                    .filter_map(|(new, &old)| {
+                        old_values.get(old as usize).and_then(|value| *value).map(|value| SparseDatum {
+                            index: new as u32,
+                            value: value,
+                        })
+                    })
+                    .collect();
+            },
+        }
+    }
+
+    fn permute(&mut self, order: &[u32]) {
+        match *self {
+            Data::Full(ref mut values) => {
+                *values = order.iter().map(|&old| values[old as usize]).collect();
+            },
+            Data::Sparse(ref mut data) => {
+                let mut new_indices = vec![0; order.len()];
+                for (new, &old) in order.iter().enumerate() {
+                    new_indices[old as usize] = new as u32;
+                }
+                for datum in data.iter_mut() {
+                    datum.index = new_indices[datum.index as usize];
+                }
+            },
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SparseDatum {
     pub index: u32,
     pub value: Vector3<f32>,
@@ -33,9 +168,15 @@ pub struct SparseDatum {
 pub fn get<'a>(
     primitive: &'a GltfPrimitive,
     buffers: &'a Buffers,
+    options: &'a Options,
 ) -> Result<Vec<MorphTarget>> {
     primitive.morph_targets().map(|morph_target| {
-        let positions = get_data(morph_target.positions(), buffers)?;
+        let mut positions = get_data(morph_target.positions(), buffers)?;
+        if options.absolute_morph_targets {
+            if let Some(deltas) = positions {
+                positions = Some(resolve_absolute(primitive, deltas, buffers)?);
+            }
+        }
         let normals = get_data(morph_target.normals(), buffers)?;
         let tangents = get_data(morph_target.tangents(), buffers)?;
 
@@ -70,17 +211,65 @@ fn get_data<'a>(
             let indices = get_sparse_indices(&sparse, buffers)?;
             let values = get_sparse_values(&sparse, buffers)?;
 
-            Ok(Some(Data::Sparse(indices.into_iter().zip(values.into_iter())
-                                 .map(|(index, value)| SparseDatum { index, value })
-                                 .collect())))
+            // A base of zeros is the same as no base, so the overrides
+            // alone describe the data. Otherwise they are merged into it.
+            let mut base = FullMorphs(AccessorIter::new(access2, buffers)?)
+                .map(|data| Vector3::from(data))
+                .collect::<Vec<_>>();
+            if base.iter().all(|value| *value == Vector3::new(0.0, 0.0, 0.0)) {
+                Ok(Some(Data::Sparse(indices.into_iter().zip(values.into_iter())
+                                     .map(|(index, value)| SparseDatum { index, value })
+                                     .collect())))
+            } else {
+                for (index, value) in indices.into_iter().zip(values.into_iter()) {
+                    let datum = base.get_mut(index as usize).ok_or(ConvertError::Other)?;
+                    *datum = value;
+                }
+
+                Ok(Some(Data::Full(base)))
+            }
         } else {
-            Ok(Some(Data::Full(FullMorphs(AccessorIter::new(access2, buffers))
+            Ok(Some(Data::Full(FullMorphs(AccessorIter::new(access2, buffers)?)
                     .map(|data| Vector3::from(data))
                     .collect())))
         }
     } else { Ok(None) }
 }
 
+/// Adds the base positions of `primitive` to morph target deltas.
+fn resolve_absolute<'a>(
+    primitive: &'a GltfPrimitive,
+    deltas: Data,
+    buffers: &'a Buffers,
+) -> Result<Data> {
+    let mut positions = primitive.positions(buffers)?.ok_or(ConvertError::MissingAttributes)?
+        .read_all()
+        .into_iter()
+        .map(Vector3::from)
+        .collect::<Vec<_>>();
+
+    match deltas {
+        Data::Full(deltas) => {
+            if deltas.len() != positions.len() {
+                return Err(Error::Convert(ConvertError::Other));
+            }
+
+            for (position, delta) in positions.iter_mut().zip(deltas.into_iter()) {
+                *position += delta;
+            }
+        },
+        Data::Sparse(deltas) => {
+            for datum in deltas {
+                let position = positions.get_mut(datum.index as usize)
+                    .ok_or(ConvertError::Other)?;
+                *position += datum.value;
+            }
+        },
+    }
+
+    Ok(Data::Full(positions))
+}
+
 fn get_sparse_indices<'a>(
     sparse: &'a GltfSparse,
     buffers: &'a Buffers,
@@ -99,8 +288,11 @@ fn get_sparse_indices<'a>(
     debug_assert!(stride >= index_size);
     
     let start = view.offset();
-    let end = start + stride * (count - 1) + index_size;
-    let data = &buffers.source_buffer(&view.buffer())[start .. end];
+    let end = stride.checked_mul(count.saturating_sub(1))
+        .and_then(|length| length.checked_add(index_size))
+        .and_then(|length| length.checked_add(start));
+    let data = end.and_then(|end| buffers.source_buffer(&view.buffer()).get(start .. end))
+        .ok_or(ConvertError::BufferViewOverrun { view: view.index() })?;
     let mut cursor = Cursor::new(data);
     
     let mut indices = Vec::<u32>::with_capacity(count);
@@ -135,8 +327,11 @@ fn get_sparse_values<'a>(
     debug_assert!(stride >= size_of::<[f32; 3]>());
     
     let start = view.offset();
-    let end = start + stride * (count - 1) + size_of::<[f32; 3]>();
-    let data = &buffers.source_buffer(&view.buffer())[start .. end];
+    let end = stride.checked_mul(count.saturating_sub(1))
+        .and_then(|length| length.checked_add(size_of::<[f32; 3]>()))
+        .and_then(|length| length.checked_add(start));
+    let data = end.and_then(|end| buffers.source_buffer(&view.buffer()).get(start .. end))
+        .ok_or(ConvertError::BufferViewOverrun { view: view.index() })?;
     let mut cursor = Cursor::new(data);
     
     let mut values = Vec::<Vector3<f32>>::with_capacity(count);
@@ -166,3 +361,93 @@ impl<'a> Iterator for FullMorphs<'a> {
         self.0.size_hint()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use gltf::Gltf;
+
+    use super::*;
+    use super::super::buffer::get as get_buffers;
+    use super::super::import;
+
+    fn get_positions(options: &Options) -> Vec<Vector3<f32>> {
+        get_target_positions("testmodels/gltf2/MorphTarget/MorphTarget.gltf", options)
+    }
+
+    fn get_target_positions(path: &str, options: &Options) -> Vec<Vector3<f32>> {
+        let path = Path::new(path);
+        let (gltf, _, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let mesh = gltf.meshes().next().unwrap();
+        let primitive = mesh.primitives().next().unwrap();
+        let mut targets = get(&primitive, &buffers, options).unwrap();
+
+        match targets.remove(0).positions {
+            Some(Data::Full(positions)) => positions,
+            Some(Data::Sparse(deltas)) => {
+                deltas.into_iter().map(|datum| datum.value).collect()
+            },
+            None => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_absolute_positions() {
+        let mut options = Options::default();
+
+        let deltas = get_positions(&options);
+        assert_eq!(deltas, vec![Vector3::new(1.0, 0.0, 0.0)]);
+
+        options.absolute_morph_targets = true;
+        let positions = get_positions(&options);
+        assert_eq!(positions.len(), 8);
+        assert_eq!(positions[5], Vector3::new(6.0, 2.0, 3.0));
+        assert_eq!(positions[4], Vector3::new(4.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn test_sparse_base() {
+        let positions = get_target_positions(
+            "testmodels/gltf2/SparseBase/SparseBase.gltf",
+            &Options::default(),
+        );
+
+        assert_eq!(positions.len(), 4);
+        assert_eq!(positions[2], Vector3::new(0.0, 3.0, 0.0));
+        for &index in &[0, 1, 3] {
+            assert_eq!(positions[index], Vector3::new(0.5, 0.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn test_sparse_overrun() {
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "buffers": [ { "uri": "data:application/octet-stream;base64,AAAAAAAAAAA=", "byteLength": 8 } ],
+            "bufferViews": [ { "buffer": 0, "byteLength": 8 } ],
+            "accessors": [ {
+                "bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3",
+                "sparse": {
+                    "count": 4, "extensions": {},
+                    "indices": { "bufferView": 0, "componentType": 5125, "extensions": {} },
+                    "values": { "bufferView": 0, "extensions": {} }
+                }
+            } ]
+        }"#;
+        let gltf = Gltf::from_str(json).unwrap().validate_minimally().unwrap();
+        let buffers = get_buffers(Path::new("."), gltf.buffers(), None).unwrap();
+        let accessor = gltf.accessors().next().unwrap();
+        let sparse = accessor.sparse().unwrap();
+
+        // Four indices or values do not fit in the eight byte buffer.
+        match get_sparse_indices(&sparse, &buffers) {
+            Err(Error::Convert(ConvertError::BufferViewOverrun { view })) => assert_eq!(view, 0),
+            _ => panic!("expected BufferViewOverrun"),
+        }
+        match get_sparse_values(&sparse, &buffers) {
+            Err(Error::Convert(ConvertError::BufferViewOverrun { view })) => assert_eq!(view, 0),
+            _ => panic!("expected BufferViewOverrun"),
+        }
+    }
+}
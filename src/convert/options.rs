@@ -0,0 +1,329 @@
+use std::path::PathBuf;
+
+/// Settings controlling how a glTF asset is converted.
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// Color space `COLOR_0` vertex colors are stored in.
+    /// glTF specifies vertex colors in linear space.
+    pub vertex_color_space: ColorSpace,
+    /// What to do with glTF objects that have no name.
+    pub name_policy: NamePolicy,
+    /// Whether node, mesh and material names are also given as hashed IDs.
+    pub name_ids: NameIds,
+    /// Whether the consumer generates mipmaps for textures. Samplers
+    /// without a min filter then default to `LinearMipmapLinear`.
+    pub generate_mipmaps: bool,
+    /// Anisotropic filtering level given to every texture. glTF samplers
+    /// have no anisotropy of their own.
+    pub default_anisotropy: Option<u8>,
+    /// Texture to substitute, with a warning, for images that cannot be
+    /// loaded, e.g. procedural textures. Without it, such textures fail the
+    /// conversion.
+    pub placeholder_texture: Option<PlaceholderTexture>,
+    /// Order of the channels in decoded RGB and RGBA texture contents.
+    pub channel_order: ChannelOrder,
+    /// Whether PNG and JPEG images are kept as encoded, with
+    /// `Format::Encoded`, instead of decoded. Encoded textures are never
+    /// resized, padded, reordered, baked or flipped.
+    pub keep_encoded_images: bool,
+    /// Whether morph target positions are resolved into absolute positions
+    /// by adding the base positions. glTF stores them as deltas.
+    pub absolute_morph_targets: bool,
+    /// Nodes whose names start with this prefix are skipped, as are nodes
+    /// hidden with `KHR_node_visibility`.
+    pub hidden_prefix: Option<String>,
+    /// Directory to record progress in. Each converted model is written
+    /// there as it completes, and models recorded by an earlier,
+    /// interrupted run are read back instead of converted again. A
+    /// checkpoint left by a run with another input file or other options
+    /// fails with `ConvertError::InvalidCheckpoint`.
+    pub checkpoint: Option<PathBuf>,
+    /// Whether non-joint nodes between joints of a skeleton are kept as
+    /// passthrough joints. Otherwise the joints below them lose their parent.
+    pub preserve_connector_nodes: bool,
+    /// Whether to warn about joints whose bind pose does not match their
+    /// inverse bind matrix.
+    pub validate_bind_poses: bool,
+    /// Whether textures sampling a texture coordinate set their primitive
+    /// lacks are rebound to set 0 with a warning, instead of failing.
+    pub downgrade_tex_coords: bool,
+    /// Whether vertices with a second set of joint influences keep the four
+    /// strongest of all eight, renormalized. Otherwise the second set is
+    /// ignored.
+    pub prune_influences: bool,
+    /// Whether animations are shifted to start at time zero.
+    pub rebase_time: bool,
+    /// Handedness of the output coordinate system. Left handed output is
+    /// mirrored along the Z axis: vertex data, node transforms, skins and
+    /// animations alike. Cameras and lights then face down their local +Z
+    /// axis instead of -Z.
+    pub handedness: Handedness,
+    /// Whether primitives without normals get flat ones computed from their
+    /// faces. Otherwise they fail with `ConvertError::MissingAttributes`.
+    pub flat_normals: bool,
+    /// Whether primitives without tangents whose material has a normal map
+    /// get tangents computed from their texture coordinates.
+    pub generate_tangents: bool,
+    /// What to do with normals that are not unit length.
+    pub unit_normals: UnitNormals,
+    /// What to do with attribute sets past the four glTF allows, like
+    /// `TEXCOORD_4` or `JOINTS_2`.
+    pub extra_attribute_sets: ExtraAttributeSets,
+    /// Whether the keyframes of linear and step channels are sorted by time
+    /// when an exporter wrote them out of order.
+    pub reorder_keyframes: bool,
+    /// Whether keyframe values are stored in fixed point instead of as
+    /// floats, as `Keyframes::Quantized`, for memory constrained runtimes.
+    pub quantize_keyframes: bool,
+    /// Whether texture coordinates are wrapped into `[0, 1]` according to
+    /// the sampler of the first texture using each set, for consumers
+    /// without hardware repeat or mirroring.
+    pub bake_uv_wrap: bool,
+    /// Whether the triangles of primitives with double sided materials are
+    /// duplicated with the opposite winding and flipped normals, for
+    /// consumers without two sided rendering.
+    pub duplicate_double_sided: bool,
+    /// Whether the primitives of each mesh are ordered by the alpha mode of
+    /// their material: opaque, mask, then blend.
+    pub sort_by_alpha_mode: bool,
+    /// Whether the blended primitives of a mesh that also has opaque or
+    /// masked ones are moved into a second model on the same node.
+    pub split_by_alpha_mode: bool,
+    /// Whether materials identical but for their name are merged into one.
+    pub dedup_materials: bool,
+    /// Whether triangle lists are reordered for the post-transform vertex
+    /// cache, with their vertices renumbered in order of first use.
+    pub optimize_vertex_cache: bool,
+    /// Whether the bind pose of every joint is also stored as a dual
+    /// quaternion, for dual quaternion skinning. Animated poses are not
+    /// converted.
+    pub dual_quaternion_skinning: bool,
+    /// Whether the joints of every skin are reordered so parents come
+    /// before their children. Vertex joint indices and animation channels
+    /// follow the new order.
+    pub sort_joints: bool,
+    /// Largest width or height of a texture. Larger uncompressed textures
+    /// are scaled down to fit, keeping their aspect ratio; block compressed
+    /// and encoded textures keep their size, with
+    /// `ConvertWarning::OversizedTexture`.
+    pub max_texture_size: Option<u32>,
+    /// How textures whose dimensions are not powers of two are enlarged to
+    /// the next power of two, if at all. Block compressed textures are left
+    /// alone.
+    pub force_power_of_two: Option<PowerOfTwo>,
+    /// Alignment in bytes each row of texture contents is padded to, for
+    /// GPU APIs with row pitch requirements.
+    pub row_alignment: Option<usize>,
+    /// Whether occlusion strengths are baked into the red channel of their
+    /// textures, so consumers can sample them directly.
+    pub bake_occlusion_strength: bool,
+    /// Convention normal map textures are stored in. glTF normal maps use
+    /// the OpenGL convention.
+    pub normal_map_convention: NormalMapConvention,
+    /// Name of the glTF default material, given to primitives without a
+    /// material. Without it, such primitives fail with
+    /// `ConvertError::NoMaterial`.
+    pub default_material_name: Option<String>,
+    /// Compression applied to serialized output after the header.
+    pub compression: Compression,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            vertex_color_space: ColorSpace::Linear,
+            name_policy: NamePolicy::Require,
+            name_ids: NameIds::Off,
+            generate_mipmaps: false,
+            default_anisotropy: None,
+            placeholder_texture: None,
+            channel_order: ChannelOrder::Rgba,
+            keep_encoded_images: false,
+            absolute_morph_targets: false,
+            hidden_prefix: None,
+            checkpoint: None,
+            preserve_connector_nodes: false,
+            validate_bind_poses: false,
+            downgrade_tex_coords: false,
+            prune_influences: false,
+            rebase_time: false,
+            handedness: Handedness::Right,
+            flat_normals: true,
+            generate_tangents: true,
+            unit_normals: UnitNormals::Keep,
+            extra_attribute_sets: ExtraAttributeSets::Ignore,
+            reorder_keyframes: false,
+            quantize_keyframes: false,
+            bake_uv_wrap: false,
+            duplicate_double_sided: false,
+            sort_by_alpha_mode: false,
+            split_by_alpha_mode: false,
+            dedup_materials: false,
+            optimize_vertex_cache: false,
+            dual_quaternion_skinning: false,
+            sort_joints: false,
+            max_texture_size: None,
+            force_power_of_two: None,
+            row_alignment: None,
+            bake_occlusion_strength: false,
+            normal_map_convention: NormalMapConvention::OpenGl,
+            default_material_name: None,
+            compression: Compression::None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NamePolicy {
+    /// Fail with `ConvertError::NoName`.
+    Require,
+    /// Derive a name from the object's source or index.
+    Generate,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NameIds {
+    /// Keep names as strings only.
+    Off,
+    /// Store the ID of each name alongside it.
+    Alongside,
+    /// Store the ID of each name instead of it. Material names are kept,
+    /// as primitives refer to their material by name.
+    Instead,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PowerOfTwo {
+    /// Fill the added texels with zeros. UVs must be scaled by
+    /// `Texture::uv_scale` to sample the original image.
+    Pad,
+    /// Stretch the image over the new dimensions.
+    Resize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compression {
+    /// Store the chunks as written, so they can be memory mapped.
+    None,
+    /// Compress the chunks with zlib wrapped deflate.
+    #[cfg(feature = "compression")]
+    Deflate,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlaceholderTexture {
+    /// RGBA color filling the whole texture.
+    Solid([u8; 4]),
+    /// Alternating texels of an RGBA color and opaque black.
+    Checkerboard([u8; 4]),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Handedness {
+    /// As in glTF.
+    Right,
+    Left,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnitNormals {
+    /// Leave normals as decoded.
+    Keep,
+    /// Rescale normals and tangent directions to unit length.
+    Normalize,
+    /// Fail with `ConvertError::NonUnitNormal`.
+    Reject,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExtraAttributeSets {
+    /// Leave them out, with `ConvertWarning::UnsupportedAttributeSet`.
+    Ignore,
+    /// Fail with `ConvertError::UnsupportedAttributeSet`.
+    Reject,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ChannelOrder {
+    Rgba,
+    /// Red and blue swapped. RGB textures become BGR.
+    Bgra,
+    /// Alpha first. RGB textures, having no alpha, are left alone.
+    Argb,
+}
+
+impl ChannelOrder {
+    /// Position of RGBA channel `channel` (0 for red to 3 for alpha) in a
+    /// texel of `channels` components. Gray texels are never reordered.
+    pub fn position(self, channel: usize, channels: usize) -> usize {
+        match (self, channels) {
+            (_, 1) | (_, 2) => channel,
+            (ChannelOrder::Rgba, _) => channel,
+            (ChannelOrder::Bgra, _) => [2, 1, 0, 3][channel],
+            (ChannelOrder::Argb, 4) => [1, 2, 3, 0][channel],
+            (ChannelOrder::Argb, _) => channel,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NormalMapConvention {
+    /// Green points up the texture (+Y), as in glTF.
+    OpenGl,
+    /// Green points down the texture (-Y).
+    DirectX,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorSpace {
+    Linear,
+    Srgb,
+}
+
+impl ColorSpace {
+    /// Converts an RGBA color from this color space into `to`.
+    /// Alpha is always linear and is left untouched.
+    pub fn convert(self, color: [f32; 4], to: ColorSpace) -> [f32; 4] {
+        let channel: fn(f32) -> f32 = match (self, to) {
+            (ColorSpace::Linear, ColorSpace::Srgb) => linear_to_srgb,
+            (ColorSpace::Srgb, ColorSpace::Linear) => srgb_to_linear,
+            _ => { return color; },
+        };
+
+        [channel(color[0]), channel(color[1]), channel(color[2]), color[3]]
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linear_to_srgb() {
+        let gray = ColorSpace::Linear.convert([0.5, 0.5, 0.5, 0.5], ColorSpace::Srgb);
+
+        for channel in gray[0..3].iter() {
+            assert!((channel - 0.735_357).abs() < 1e-4);
+        }
+        assert_eq!(gray[3], 0.5);
+
+        let back = ColorSpace::Srgb.convert(gray, ColorSpace::Linear);
+        assert!((back[0] - 0.5).abs() < 1e-4);
+    }
+}
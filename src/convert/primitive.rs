@@ -1,53 +1,1147 @@
-use cgmath::{Vector2, Vector3, Vector4};
-use gltf::mesh::{Primitive as GltfPrimitive, Primitives as GltfPrimitives};
-use gltf_importer::Buffers;
-use gltf_utils::PrimitiveIterators;
+use std::cmp::Ordering;
+use std::u32;
+
+use cgmath::{InnerSpace, Vector2, Vector3, Vector4};
+use gltf::mesh::{Attribute as GltfAttribute, Mode as GltfMode, Primitive as GltfPrimitive, Primitives as GltfPrimitives, Semantic as GltfSemantic};
 use itertools::multizip;
 
 use super::super::{Result, Error};
-use super::ConvertError;
-use super::material::Materials;
+use super::{ConvertError, ConvertWarning};
+use super::buffer::Buffers;
+use super::layout::{AttributeFormat, Semantic, VertexLayout, write_u16x4, write_vec2, write_vec3, write_vec4};
+use super::material::{Material, Materials};
 use super::morph_target::{MorphTarget, get as get_morph_targets};
-use super::texture::Texture;
+use super::options::{ColorSpace, ExtraAttributeSets, Handedness, Options, UnitNormals};
+use super::skin::remap_joint_indices;
+use super::texture::{Texture, WrappingMode};
+use super::util::PrimitiveIterators;
+use super::vertex_cache::{optimize as optimize_triangles, remap_vertices};
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Primitive {
     material: String,
+    mode: Mode,
     attributes: Attributes,
     indices: Vec<u32>,
+    morph_targets: Vec<MorphTarget>,
+}
+
+impl Primitive {
+    pub fn material(&self) -> &str {
+        &self.material
+    }
+
+    /// Switches the primitive to the material `name`.
+    pub fn rename_material(&mut self, name: &str) {
+        self.material = name.to_owned();
+    }
+
+    pub fn attributes(&self) -> &Attributes {
+        &self.attributes
+    }
+
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    pub fn morph_targets(&self) -> &[MorphTarget] {
+        &self.morph_targets
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        match self.attributes {
+            Attributes::NoTex1NoTangentNoBones(ref vertices) => vertices.len(),
+            Attributes::NoTex1NoTangentBones(ref vertices) => vertices.len(),
+            Attributes::NoTex1TangentNoBones(ref vertices) => vertices.len(),
+            Attributes::NoTex1TangentBones(ref vertices) => vertices.len(),
+            Attributes::Tex1NoTangentNoBones(ref vertices) => vertices.len(),
+            Attributes::Tex1NoTangentBones(ref vertices) => vertices.len(),
+            Attributes::Tex1TangentNoBones(ref vertices) => vertices.len(),
+            Attributes::Tex1TangentBones(ref vertices) => vertices.len(),
+            Attributes::NoTex1NoTangentColorNoBones(ref vertices) => vertices.len(),
+            Attributes::NoTex1NoTangentColorBones(ref vertices) => vertices.len(),
+            Attributes::NoTex1TangentColorNoBones(ref vertices) => vertices.len(),
+            Attributes::NoTex1TangentColorBones(ref vertices) => vertices.len(),
+            Attributes::Tex1NoTangentColorNoBones(ref vertices) => vertices.len(),
+            Attributes::Tex1NoTangentColorBones(ref vertices) => vertices.len(),
+            Attributes::Tex1TangentColorNoBones(ref vertices) => vertices.len(),
+            Attributes::Tex1TangentColorBones(ref vertices) => vertices.len(),
+            Attributes::Points { ref positions, .. } => positions.len(),
+        }
+    }
+
+    pub fn positions(&self) -> Vec<Vector3<f32>> {
+        match self.attributes {
+            Attributes::NoTex1NoTangentNoBones(ref vertices) => vertices.iter().map(|vertex| vertex.position).collect(),
+            Attributes::NoTex1NoTangentBones(ref vertices) => vertices.iter().map(|vertex| vertex.position).collect(),
+            Attributes::NoTex1TangentNoBones(ref vertices) => vertices.iter().map(|vertex| vertex.position).collect(),
+            Attributes::NoTex1TangentBones(ref vertices) => vertices.iter().map(|vertex| vertex.position).collect(),
+            Attributes::Tex1NoTangentNoBones(ref vertices) => vertices.iter().map(|vertex| vertex.position).collect(),
+            Attributes::Tex1NoTangentBones(ref vertices) => vertices.iter().map(|vertex| vertex.position).collect(),
+            Attributes::Tex1TangentNoBones(ref vertices) => vertices.iter().map(|vertex| vertex.position).collect(),
+            Attributes::Tex1TangentBones(ref vertices) => vertices.iter().map(|vertex| vertex.position).collect(),
+            Attributes::NoTex1NoTangentColorNoBones(ref vertices) => vertices.iter().map(|vertex| vertex.position).collect(),
+            Attributes::NoTex1NoTangentColorBones(ref vertices) => vertices.iter().map(|vertex| vertex.position).collect(),
+            Attributes::NoTex1TangentColorNoBones(ref vertices) => vertices.iter().map(|vertex| vertex.position).collect(),
+            Attributes::NoTex1TangentColorBones(ref vertices) => vertices.iter().map(|vertex| vertex.position).collect(),
+            Attributes::Tex1NoTangentColorNoBones(ref vertices) => vertices.iter().map(|vertex| vertex.position).collect(),
+            Attributes::Tex1NoTangentColorBones(ref vertices) => vertices.iter().map(|vertex| vertex.position).collect(),
+            Attributes::Tex1TangentColorNoBones(ref vertices) => vertices.iter().map(|vertex| vertex.position).collect(),
+            Attributes::Tex1TangentColorBones(ref vertices) => vertices.iter().map(|vertex| vertex.position).collect(),
+            Attributes::Points { ref positions, .. } => positions.clone(),
+        }
+    }
+
+    /// Visits the index triples of every triangle, unrolling strips and fans.
+    /// Primitives that are not made of triangles yield nothing.
+    pub fn triangles<'a>(&'a self) -> Triangles<'a> {
+        Triangles {
+            mode: self.mode,
+            indices: &self.indices,
+            index: 0,
+        }
+    }
+
+    /// Packs the vertex attributes into one interleaved buffer, ready for
+    /// uploading to the GPU.
+    pub fn interleaved_bytes(&self) -> (VertexLayout, Vec<u8>) {
+        use self::AttributeFormat::*;
+        use self::Semantic::*;
+
+        match self.attributes {
+            Attributes::NoTex1NoTangentNoBones(ref vertices) => {
+                let layout = VertexLayout::new(&[(Position, F32x3), (Normal, F32x3), (TexCoord0, F32x2)]);
+                let mut bytes = Vec::<u8>::with_capacity(layout.stride * vertices.len());
+                for vertex in vertices.iter() {
+                    write_vec3(&mut bytes, &vertex.position);
+                    write_vec3(&mut bytes, &vertex.normal);
+                    write_vec2(&mut bytes, &vertex.texcoord0);
+                }
+                (layout, bytes)
+            },
+            Attributes::NoTex1NoTangentBones(ref vertices) => {
+                let layout = VertexLayout::new(&[
+                    (Position, F32x3), (Normal, F32x3), (TexCoord0, F32x2), (Joints, U16x4), (Weights, F32x4),
+                ]);
+                let mut bytes = Vec::<u8>::with_capacity(layout.stride * vertices.len());
+                for vertex in vertices.iter() {
+                    write_vec3(&mut bytes, &vertex.position);
+                    write_vec3(&mut bytes, &vertex.normal);
+                    write_vec2(&mut bytes, &vertex.texcoord0);
+                    write_u16x4(&mut bytes, &vertex.joints);
+                    write_vec4(&mut bytes, &vertex.weights);
+                }
+                (layout, bytes)
+            },
+            Attributes::NoTex1TangentNoBones(ref vertices) => {
+                let layout = VertexLayout::new(&[
+                    (Position, F32x3), (Normal, F32x3), (TexCoord0, F32x2), (Tangent, F32x4),
+                ]);
+                let mut bytes = Vec::<u8>::with_capacity(layout.stride * vertices.len());
+                for vertex in vertices.iter() {
+                    write_vec3(&mut bytes, &vertex.position);
+                    write_vec3(&mut bytes, &vertex.normal);
+                    write_vec2(&mut bytes, &vertex.texcoord0);
+                    write_vec4(&mut bytes, &vertex.tangent);
+                }
+                (layout, bytes)
+            },
+            Attributes::NoTex1TangentBones(ref vertices) => {
+                let layout = VertexLayout::new(&[
+                    (Position, F32x3), (Normal, F32x3), (TexCoord0, F32x2), (Tangent, F32x4),
+                    (Joints, U16x4), (Weights, F32x4),
+                ]);
+                let mut bytes = Vec::<u8>::with_capacity(layout.stride * vertices.len());
+                for vertex in vertices.iter() {
+                    write_vec3(&mut bytes, &vertex.position);
+                    write_vec3(&mut bytes, &vertex.normal);
+                    write_vec2(&mut bytes, &vertex.texcoord0);
+                    write_vec4(&mut bytes, &vertex.tangent);
+                    write_u16x4(&mut bytes, &vertex.joints);
+                    write_vec4(&mut bytes, &vertex.weights);
+                }
+                (layout, bytes)
+            },
+            Attributes::Tex1NoTangentNoBones(ref vertices) => {
+                let layout = VertexLayout::new(&[
+                    (Position, F32x3), (Normal, F32x3), (TexCoord0, F32x2), (TexCoord1, F32x2),
+                ]);
+                let mut bytes = Vec::<u8>::with_capacity(layout.stride * vertices.len());
+                for vertex in vertices.iter() {
+                    write_vec3(&mut bytes, &vertex.position);
+                    write_vec3(&mut bytes, &vertex.normal);
+                    write_vec2(&mut bytes, &vertex.texcoord0);
+                    write_vec2(&mut bytes, &vertex.texcoord1);
+                }
+                (layout, bytes)
+            },
+            Attributes::Tex1NoTangentBones(ref vertices) => {
+                let layout = VertexLayout::new(&[
+                    (Position, F32x3), (Normal, F32x3), (TexCoord0, F32x2), (TexCoord1, F32x2),
+                    (Joints, U16x4), (Weights, F32x4),
+                ]);
+                let mut bytes = Vec::<u8>::with_capacity(layout.stride * vertices.len());
+                for vertex in vertices.iter() {
+                    write_vec3(&mut bytes, &vertex.position);
+                    write_vec3(&mut bytes, &vertex.normal);
+                    write_vec2(&mut bytes, &vertex.texcoord0);
+                    write_vec2(&mut bytes, &vertex.texcoord1);
+                    write_u16x4(&mut bytes, &vertex.joints);
+                    write_vec4(&mut bytes, &vertex.weights);
+                }
+                (layout, bytes)
+            },
+            Attributes::Tex1TangentNoBones(ref vertices) => {
+                let layout = VertexLayout::new(&[
+                    (Position, F32x3), (Normal, F32x3), (TexCoord0, F32x2), (TexCoord1, F32x2),
+                    (Tangent, F32x4),
+                ]);
+                let mut bytes = Vec::<u8>::with_capacity(layout.stride * vertices.len());
+                for vertex in vertices.iter() {
+                    write_vec3(&mut bytes, &vertex.position);
+                    write_vec3(&mut bytes, &vertex.normal);
+                    write_vec2(&mut bytes, &vertex.texcoord0);
+                    write_vec2(&mut bytes, &vertex.texcoord1);
+                    write_vec4(&mut bytes, &vertex.tangent);
+                }
+                (layout, bytes)
+            },
+            Attributes::Tex1TangentBones(ref vertices) => {
+                let layout = VertexLayout::new(&[
+                    (Position, F32x3), (Normal, F32x3), (TexCoord0, F32x2), (TexCoord1, F32x2),
+                    (Tangent, F32x4), (Joints, U16x4), (Weights, F32x4),
+                ]);
+                let mut bytes = Vec::<u8>::with_capacity(layout.stride * vertices.len());
+                for vertex in vertices.iter() {
+                    write_vec3(&mut bytes, &vertex.position);
+                    write_vec3(&mut bytes, &vertex.normal);
+                    write_vec2(&mut bytes, &vertex.texcoord0);
+                    write_vec2(&mut bytes, &vertex.texcoord1);
+                    write_vec4(&mut bytes, &vertex.tangent);
+                    write_u16x4(&mut bytes, &vertex.joints);
+                    write_vec4(&mut bytes, &vertex.weights);
+                }
+                (layout, bytes)
+            },
+            Attributes::NoTex1NoTangentColorNoBones(ref vertices) => {
+                let layout = VertexLayout::new(&[
+                    (Position, F32x3), (Normal, F32x3), (TexCoord0, F32x2), (Color, F32x4),
+                ]);
+                let mut bytes = Vec::<u8>::with_capacity(layout.stride * vertices.len());
+                for vertex in vertices.iter() {
+                    write_vec3(&mut bytes, &vertex.position);
+                    write_vec3(&mut bytes, &vertex.normal);
+                    write_vec2(&mut bytes, &vertex.texcoord0);
+                    write_vec4(&mut bytes, &vertex.color);
+                }
+                (layout, bytes)
+            },
+            Attributes::NoTex1NoTangentColorBones(ref vertices) => {
+                let layout = VertexLayout::new(&[
+                    (Position, F32x3), (Normal, F32x3), (TexCoord0, F32x2), (Color, F32x4),
+                    (Joints, U16x4), (Weights, F32x4),
+                ]);
+                let mut bytes = Vec::<u8>::with_capacity(layout.stride * vertices.len());
+                for vertex in vertices.iter() {
+                    write_vec3(&mut bytes, &vertex.position);
+                    write_vec3(&mut bytes, &vertex.normal);
+                    write_vec2(&mut bytes, &vertex.texcoord0);
+                    write_vec4(&mut bytes, &vertex.color);
+                    write_u16x4(&mut bytes, &vertex.joints);
+                    write_vec4(&mut bytes, &vertex.weights);
+                }
+                (layout, bytes)
+            },
+            Attributes::NoTex1TangentColorNoBones(ref vertices) => {
+                let layout = VertexLayout::new(&[
+                    (Position, F32x3), (Normal, F32x3), (TexCoord0, F32x2), (Tangent, F32x4), (Color, F32x4),
+                ]);
+                let mut bytes = Vec::<u8>::with_capacity(layout.stride * vertices.len());
+                for vertex in vertices.iter() {
+                    write_vec3(&mut bytes, &vertex.position);
+                    write_vec3(&mut bytes, &vertex.normal);
+                    write_vec2(&mut bytes, &vertex.texcoord0);
+                    write_vec4(&mut bytes, &vertex.tangent);
+                    write_vec4(&mut bytes, &vertex.color);
+                }
+                (layout, bytes)
+            },
+            Attributes::NoTex1TangentColorBones(ref vertices) => {
+                let layout = VertexLayout::new(&[
+                    (Position, F32x3), (Normal, F32x3), (TexCoord0, F32x2), (Tangent, F32x4),
+                    (Color, F32x4), (Joints, U16x4), (Weights, F32x4),
+                ]);
+                let mut bytes = Vec::<u8>::with_capacity(layout.stride * vertices.len());
+                for vertex in vertices.iter() {
+                    write_vec3(&mut bytes, &vertex.position);
+                    write_vec3(&mut bytes, &vertex.normal);
+                    write_vec2(&mut bytes, &vertex.texcoord0);
+                    write_vec4(&mut bytes, &vertex.tangent);
+                    write_vec4(&mut bytes, &vertex.color);
+                    write_u16x4(&mut bytes, &vertex.joints);
+                    write_vec4(&mut bytes, &vertex.weights);
+                }
+                (layout, bytes)
+            },
+            Attributes::Tex1NoTangentColorNoBones(ref vertices) => {
+                let layout = VertexLayout::new(&[
+                    (Position, F32x3), (Normal, F32x3), (TexCoord0, F32x2), (TexCoord1, F32x2), (Color, F32x4),
+                ]);
+                let mut bytes = Vec::<u8>::with_capacity(layout.stride * vertices.len());
+                for vertex in vertices.iter() {
+                    write_vec3(&mut bytes, &vertex.position);
+                    write_vec3(&mut bytes, &vertex.normal);
+                    write_vec2(&mut bytes, &vertex.texcoord0);
+                    write_vec2(&mut bytes, &vertex.texcoord1);
+                    write_vec4(&mut bytes, &vertex.color);
+                }
+                (layout, bytes)
+            },
+            Attributes::Tex1NoTangentColorBones(ref vertices) => {
+                let layout = VertexLayout::new(&[
+                    (Position, F32x3), (Normal, F32x3), (TexCoord0, F32x2), (TexCoord1, F32x2),
+                    (Color, F32x4), (Joints, U16x4), (Weights, F32x4),
+                ]);
+                let mut bytes = Vec::<u8>::with_capacity(layout.stride * vertices.len());
+                for vertex in vertices.iter() {
+                    write_vec3(&mut bytes, &vertex.position);
+                    write_vec3(&mut bytes, &vertex.normal);
+                    write_vec2(&mut bytes, &vertex.texcoord0);
+                    write_vec2(&mut bytes, &vertex.texcoord1);
+                    write_vec4(&mut bytes, &vertex.color);
+                    write_u16x4(&mut bytes, &vertex.joints);
+                    write_vec4(&mut bytes, &vertex.weights);
+                }
+                (layout, bytes)
+            },
+            Attributes::Tex1TangentColorNoBones(ref vertices) => {
+                let layout = VertexLayout::new(&[
+                    (Position, F32x3), (Normal, F32x3), (TexCoord0, F32x2), (TexCoord1, F32x2),
+                    (Tangent, F32x4), (Color, F32x4),
+                ]);
+                let mut bytes = Vec::<u8>::with_capacity(layout.stride * vertices.len());
+                for vertex in vertices.iter() {
+                    write_vec3(&mut bytes, &vertex.position);
+                    write_vec3(&mut bytes, &vertex.normal);
+                    write_vec2(&mut bytes, &vertex.texcoord0);
+                    write_vec2(&mut bytes, &vertex.texcoord1);
+                    write_vec4(&mut bytes, &vertex.tangent);
+                    write_vec4(&mut bytes, &vertex.color);
+                }
+                (layout, bytes)
+            },
+            Attributes::Tex1TangentColorBones(ref vertices) => {
+                let layout = VertexLayout::new(&[
+                    (Position, F32x3), (Normal, F32x3), (TexCoord0, F32x2), (TexCoord1, F32x2),
+                    (Tangent, F32x4), (Color, F32x4), (Joints, U16x4), (Weights, F32x4),
+                ]);
+                let mut bytes = Vec::<u8>::with_capacity(layout.stride * vertices.len());
+                for vertex in vertices.iter() {
+                    write_vec3(&mut bytes, &vertex.position);
+                    write_vec3(&mut bytes, &vertex.normal);
+                    write_vec2(&mut bytes, &vertex.texcoord0);
+                    write_vec2(&mut bytes, &vertex.texcoord1);
+                    write_vec4(&mut bytes, &vertex.tangent);
+                    write_vec4(&mut bytes, &vertex.color);
+                    write_u16x4(&mut bytes, &vertex.joints);
+                    write_vec4(&mut bytes, &vertex.weights);
+                }
+                (layout, bytes)
+            },
+            Attributes::Points { ref positions, ref colors } => {
+                let layout = match *colors {
+                    Some(_) => VertexLayout::new(&[(Position, F32x3), (Color, F32x4)]),
+                    None => VertexLayout::new(&[(Position, F32x3)]),
+                };
+                let mut bytes = Vec::<u8>::with_capacity(layout.stride * positions.len());
+                for (index, position) in positions.iter().enumerate() {
+                    write_vec3(&mut bytes, position);
+                    if let Some(ref colors) = *colors {
+                        write_vec4(&mut bytes, &colors[index]);
+                    }
+                }
+                (layout, bytes)
+            },
+        }
+    }
+
+    /// Splits the vertices into a position stream, for depth and shadow
+    /// passes, and the other attributes interleaved as in
+    /// `interleaved_bytes` without the position.
+    pub fn split_streams(&self) -> (Vec<Vector3<f32>>, Vec<u8>) {
+        let (layout, bytes) = self.interleaved_bytes();
+        let position_size = AttributeFormat::F32x3.size();
+        let attributes = bytes.chunks(layout.stride)
+            .flat_map(|vertex| vertex[position_size..].iter().cloned())
+            .collect::<Vec<_>>();
+
+        (self.positions(), attributes)
+    }
+
+    /// Reorders the triangles of a triangle list for the post-transform
+    /// vertex cache, and renumbers the vertices in order of first use.
+    pub fn optimize_vertex_cache(&mut self) {
+        if self.mode != Mode::Triangles || self.indices.is_empty() {
+            return;
+        }
+
+        let vertex_count = self.vertex_count();
+        self.indices = optimize_triangles(&self.indices, vertex_count);
+        let order = remap_vertices(&mut self.indices, vertex_count);
+
+        match self.attributes {
+            Attributes::NoTex1NoTangentNoBones(ref mut vertices) => permute(vertices, &order),
+            Attributes::NoTex1NoTangentBones(ref mut vertices) => permute(vertices, &order),
+            Attributes::NoTex1TangentNoBones(ref mut vertices) => permute(vertices, &order),
+            Attributes::NoTex1TangentBones(ref mut vertices) => permute(vertices, &order),
+            Attributes::Tex1NoTangentNoBones(ref mut vertices) => permute(vertices, &order),
+            Attributes::Tex1NoTangentBones(ref mut vertices) => permute(vertices, &order),
+            Attributes::Tex1TangentNoBones(ref mut vertices) => permute(vertices, &order),
+            Attributes::Tex1TangentBones(ref mut vertices) => permute(vertices, &order),
+            Attributes::NoTex1NoTangentColorNoBones(ref mut vertices) => permute(vertices, &order),
+            Attributes::NoTex1NoTangentColorBones(ref mut vertices) => permute(vertices, &order),
+            Attributes::NoTex1TangentColorNoBones(ref mut vertices) => permute(vertices, &order),
+            Attributes::NoTex1TangentColorBones(ref mut vertices) => permute(vertices, &order),
+            Attributes::Tex1NoTangentColorNoBones(ref mut vertices) => permute(vertices, &order),
+            Attributes::Tex1NoTangentColorBones(ref mut vertices) => permute(vertices, &order),
+            Attributes::Tex1TangentColorNoBones(ref mut vertices) => permute(vertices, &order),
+            Attributes::Tex1TangentColorBones(ref mut vertices) => permute(vertices, &order),
+            Attributes::Points { ref mut positions, ref mut colors } => {
+                permute(positions, &order);
+                if let Some(ref mut colors) = *colors {
+                    permute(colors, &order);
+                }
+            },
+        }
+        for morph_target in self.morph_targets.iter_mut() {
+            morph_target.permute(&order);
+        }
+    }
+
+    /// Bakes wrapping modes into texture coordinate set `set`.
+    pub fn bake_wrap(&mut self, set: u32, wrap_s: WrappingMode, wrap_t: WrappingMode) {
+        for tex_coord in self.tex_coords_mut(set) {
+            tex_coord.x = wrap_s.bake(tex_coord.x);
+            tex_coord.y = wrap_t.bake(tex_coord.y);
+        }
+    }
+
+    /// Normalizes the normals and tangent directions, or checks the normals
+    /// are unit length, according to `mode`. Zero normals are left alone.
+    pub fn fix_normals(&mut self, mode: UnitNormals) -> Result<()> {
+        match mode {
+            UnitNormals::Keep => {},
+            UnitNormals::Normalize => {
+                for normal in self.normals_mut() {
+                    if normal.magnitude2() > 0.0 {
+                        *normal = normal.normalize();
+                    }
+                }
+                for tangent in self.tangents_mut() {
+                    let direction = tangent.truncate();
+                    if direction.magnitude2() > 0.0 {
+                        *tangent = direction.normalize().extend(tangent.w);
+                    }
+                }
+            },
+            UnitNormals::Reject => {
+                for (vertex, normal) in self.normals_mut().into_iter().enumerate() {
+                    if (normal.magnitude() - 1.0).abs() > UNIT_NORMAL_EPSILON {
+                        return Err(Error::Convert(ConvertError::NonUnitNormal { vertex: vertex }));
+                    }
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Gives every triangle corner a vertex of its own, with the normal of
+    /// its face: the normalized cross product of the triangle's edges.
+    /// Degenerate triangles get zero normals. Strips and fans become a
+    /// triangle list.
+    pub fn flat_normals(&mut self) -> Result<()> {
+        let triangles = self.triangles().collect::<Vec<_>>();
+        if triangles.is_empty() {
+            return Ok(());
+        }
+        let order = triangles.iter().flat_map(|triangle| triangle.iter().cloned()).collect::<Vec<_>>();
+        check_vertex_count(order.len() as u64)?;
+
+        match self.attributes {
+            Attributes::NoTex1NoTangentNoBones(ref mut vertices) => unweld(vertices, &order),
+            Attributes::NoTex1NoTangentBones(ref mut vertices) => unweld(vertices, &order),
+            Attributes::NoTex1TangentNoBones(ref mut vertices) => unweld(vertices, &order),
+            Attributes::NoTex1TangentBones(ref mut vertices) => unweld(vertices, &order),
+            Attributes::Tex1NoTangentNoBones(ref mut vertices) => unweld(vertices, &order),
+            Attributes::Tex1NoTangentBones(ref mut vertices) => unweld(vertices, &order),
+            Attributes::Tex1TangentNoBones(ref mut vertices) => unweld(vertices, &order),
+            Attributes::Tex1TangentBones(ref mut vertices) => unweld(vertices, &order),
+            Attributes::NoTex1NoTangentColorNoBones(ref mut vertices) => unweld(vertices, &order),
+            Attributes::NoTex1NoTangentColorBones(ref mut vertices) => unweld(vertices, &order),
+            Attributes::NoTex1TangentColorNoBones(ref mut vertices) => unweld(vertices, &order),
+            Attributes::NoTex1TangentColorBones(ref mut vertices) => unweld(vertices, &order),
+            Attributes::Tex1NoTangentColorNoBones(ref mut vertices) => unweld(vertices, &order),
+            Attributes::Tex1NoTangentColorBones(ref mut vertices) => unweld(vertices, &order),
+            Attributes::Tex1TangentColorNoBones(ref mut vertices) => unweld(vertices, &order),
+            Attributes::Tex1TangentColorBones(ref mut vertices) => unweld(vertices, &order),
+            Attributes::Points { .. } => { return Ok(()); },
+        }
+        for morph_target in self.morph_targets.iter_mut() {
+            morph_target.unweld(&order);
+        }
+
+        let faces = self.positions().chunks(3).map(|corners| {
+            let normal = (corners[1] - corners[0]).cross(corners[2] - corners[0]);
+            if normal.magnitude2() > 0.0 { normal.normalize() } else { normal }
+        }).collect::<Vec<_>>();
+        for (vertex, normal) in self.normals_mut().into_iter().enumerate() {
+            *normal = faces[vertex / 3];
+        }
+        self.indices = (0..order.len() as u32).collect();
+        self.mode = Mode::Triangles;
+
+        Ok(())
+    }
+
+    /// Computes tangents from the positions, normals and first texture
+    /// coordinates with Lengyel's method. The per triangle texture space
+    /// directions are summed at each vertex, then the tangent is made
+    /// orthogonal to the normal, with W the sign of the bitangent. glTF
+    /// texture coordinates run down the image while normal maps point green
+    /// up, so the bitangent follows decreasing V.
+    pub fn generate_tangents(&mut self) {
+        let vertex_count = self.vertex_count();
+        let positions = self.positions();
+        let normals = self.normals_mut().into_iter().map(|normal| *normal).collect::<Vec<_>>();
+        let tex_coords = self.tex_coords_mut(0).into_iter().map(|tex_coord| *tex_coord).collect::<Vec<_>>();
+        if normals.len() != vertex_count || tex_coords.len() != vertex_count {
+            return;
+        }
+
+        let mut tangents = vec![Vector3::new(0.0, 0.0, 0.0); vertex_count];
+        let mut bitangents = vec![Vector3::new(0.0, 0.0, 0.0); vertex_count];
+        for [a, b, c] in self.triangles() {
+            let (a, b, c) = (a as usize, b as usize, c as usize);
+            let (edge1, edge2) = (positions[b] - positions[a], positions[c] - positions[a]);
+            let (uv1, uv2) = (tex_coords[b] - tex_coords[a], tex_coords[c] - tex_coords[a]);
+            let determinant = uv1.x * uv2.y - uv2.x * uv1.y;
+            if determinant == 0.0 {
+                continue;
+            }
+
+            let tangent = (edge1 * uv2.y - edge2 * uv1.y) / determinant;
+            let bitangent = (edge2 * uv1.x - edge1 * uv2.x) / determinant;
+            for &vertex in [a, b, c].iter() {
+                tangents[vertex] += tangent;
+                bitangents[vertex] += bitangent;
+            }
+        }
+
+        for (vertex, tangent) in self.tangents_mut().into_iter().enumerate() {
+            let normal = normals[vertex];
+            let direction = tangents[vertex] - normal * normal.dot(tangents[vertex]);
+            let direction = if direction.magnitude2() > 0.0 { direction.normalize() } else { direction };
+            let w = if normal.cross(direction).dot(bitangents[vertex]) > 0.0 { -1.0 } else { 1.0 };
+            *tangent = direction.extend(w);
+        }
+    }
+
+    /// Mirrors the primitive along the Z axis, for a left handed coordinate
+    /// system. Positions, normals and tangent directions have Z negated.
+    /// Mirroring flips the bitangent `cross(normal, tangent)`, so tangent W
+    /// is negated to keep it, and triangles are rewound to stay front
+    /// facing, turning strips and fans into a triangle list.
+    pub fn mirror_z(&mut self) {
+        for position in self.positions_mut() {
+            position.z = -position.z;
+        }
+        for normal in self.normals_mut() {
+            normal.z = -normal.z;
+        }
+        for tangent in self.tangents_mut() {
+            tangent.z = -tangent.z;
+            tangent.w = -tangent.w;
+        }
+        for morph_target in self.morph_targets.iter_mut() {
+            morph_target.mirror_z();
+        }
+
+        let triangles = self.triangles().collect::<Vec<_>>();
+        if !triangles.is_empty() {
+            self.indices = triangles.iter().flat_map(|&[a, b, c]| vec![a, c, b]).collect();
+            self.mode = Mode::Triangles;
+        }
+    }
+
+    /// Appends a copy of every triangle with the opposite winding, using
+    /// copies of its vertices with flipped normals, and turns the primitive
+    /// into a triangle list. Primitives without triangles are left alone.
+    pub fn duplicate_back_faces(&mut self) -> Result<()> {
+        let front = self.triangles().collect::<Vec<_>>();
+        if front.is_empty() {
+            return Ok(());
+        }
+        let vertex_count = self.vertex_count();
+        check_vertex_count(vertex_count as u64 * 2)?;
+
+        match self.attributes {
+            Attributes::NoTex1NoTangentNoBones(ref mut vertices) => duplicate(vertices),
+            Attributes::NoTex1NoTangentBones(ref mut vertices) => duplicate(vertices),
+            Attributes::NoTex1TangentNoBones(ref mut vertices) => duplicate(vertices),
+            Attributes::NoTex1TangentBones(ref mut vertices) => duplicate(vertices),
+            Attributes::Tex1NoTangentNoBones(ref mut vertices) => duplicate(vertices),
+            Attributes::Tex1NoTangentBones(ref mut vertices) => duplicate(vertices),
+            Attributes::Tex1TangentNoBones(ref mut vertices) => duplicate(vertices),
+            Attributes::Tex1TangentBones(ref mut vertices) => duplicate(vertices),
+            Attributes::NoTex1NoTangentColorNoBones(ref mut vertices) => duplicate(vertices),
+            Attributes::NoTex1NoTangentColorBones(ref mut vertices) => duplicate(vertices),
+            Attributes::NoTex1TangentColorNoBones(ref mut vertices) => duplicate(vertices),
+            Attributes::NoTex1TangentColorBones(ref mut vertices) => duplicate(vertices),
+            Attributes::Tex1NoTangentColorNoBones(ref mut vertices) => duplicate(vertices),
+            Attributes::Tex1NoTangentColorBones(ref mut vertices) => duplicate(vertices),
+            Attributes::Tex1TangentColorNoBones(ref mut vertices) => duplicate(vertices),
+            Attributes::Tex1TangentColorBones(ref mut vertices) => duplicate(vertices),
+            Attributes::Points { .. } => { return Ok(()); },
+        }
+        for normal in self.normals_mut().into_iter().skip(vertex_count) {
+            *normal = -*normal;
+        }
+        // Keep the bitangent pointing the same way.
+        for tangent in self.tangents_mut().into_iter().skip(vertex_count) {
+            tangent.w = -tangent.w;
+        }
+        for morph_target in self.morph_targets.iter_mut() {
+            morph_target.duplicate(vertex_count as u32);
+        }
+
+        let offset = vertex_count as u32;
+        let back = front.iter().map(|&[a, b, c]| [a + offset, c + offset, b + offset]).collect::<Vec<_>>();
+        self.indices = front.iter().chain(back.iter())
+            .flat_map(|triangle| triangle.iter().cloned())
+            .collect();
+        self.mode = Mode::Triangles;
+
+        Ok(())
+    }
+
+    fn positions_mut(&mut self) -> Vec<&mut Vector3<f32>> {
+        match self.attributes {
+            Attributes::NoTex1NoTangentNoBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.position).collect()
+            },
+            Attributes::NoTex1NoTangentBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.position).collect()
+            },
+            Attributes::NoTex1TangentNoBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.position).collect()
+            },
+            Attributes::NoTex1TangentBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.position).collect()
+            },
+            Attributes::Tex1NoTangentNoBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.position).collect()
+            },
+            Attributes::Tex1NoTangentBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.position).collect()
+            },
+            Attributes::Tex1TangentNoBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.position).collect()
+            },
+            Attributes::Tex1TangentBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.position).collect()
+            },
+            Attributes::NoTex1NoTangentColorNoBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.position).collect()
+            },
+            Attributes::NoTex1NoTangentColorBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.position).collect()
+            },
+            Attributes::NoTex1TangentColorNoBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.position).collect()
+            },
+            Attributes::NoTex1TangentColorBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.position).collect()
+            },
+            Attributes::Tex1NoTangentColorNoBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.position).collect()
+            },
+            Attributes::Tex1NoTangentColorBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.position).collect()
+            },
+            Attributes::Tex1TangentColorNoBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.position).collect()
+            },
+            Attributes::Tex1TangentColorBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.position).collect()
+            },
+            Attributes::Points { ref mut positions, .. } => positions.iter_mut().collect(),
+        }
+    }
+
+    fn normals_mut(&mut self) -> Vec<&mut Vector3<f32>> {
+        match self.attributes {
+            Attributes::NoTex1NoTangentNoBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.normal).collect()
+            },
+            Attributes::NoTex1NoTangentBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.normal).collect()
+            },
+            Attributes::NoTex1TangentNoBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.normal).collect()
+            },
+            Attributes::NoTex1TangentBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.normal).collect()
+            },
+            Attributes::Tex1NoTangentNoBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.normal).collect()
+            },
+            Attributes::Tex1NoTangentBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.normal).collect()
+            },
+            Attributes::Tex1TangentNoBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.normal).collect()
+            },
+            Attributes::Tex1TangentBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.normal).collect()
+            },
+            Attributes::NoTex1NoTangentColorNoBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.normal).collect()
+            },
+            Attributes::NoTex1NoTangentColorBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.normal).collect()
+            },
+            Attributes::NoTex1TangentColorNoBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.normal).collect()
+            },
+            Attributes::NoTex1TangentColorBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.normal).collect()
+            },
+            Attributes::Tex1NoTangentColorNoBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.normal).collect()
+            },
+            Attributes::Tex1NoTangentColorBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.normal).collect()
+            },
+            Attributes::Tex1TangentColorNoBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.normal).collect()
+            },
+            Attributes::Tex1TangentColorBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.normal).collect()
+            },
+            Attributes::Points { .. } => Vec::new(),
+        }
+    }
+
+    fn tangents_mut(&mut self) -> Vec<&mut Vector4<f32>> {
+        match self.attributes {
+            Attributes::NoTex1TangentNoBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.tangent).collect()
+            },
+            Attributes::NoTex1TangentBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.tangent).collect()
+            },
+            Attributes::Tex1TangentNoBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.tangent).collect()
+            },
+            Attributes::Tex1TangentBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.tangent).collect()
+            },
+            Attributes::NoTex1TangentColorNoBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.tangent).collect()
+            },
+            Attributes::NoTex1TangentColorBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.tangent).collect()
+            },
+            Attributes::Tex1TangentColorNoBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.tangent).collect()
+            },
+            Attributes::Tex1TangentColorBones(ref mut vertices) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.tangent).collect()
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    fn tex_coords_mut(&mut self, set: u32) -> Vec<&mut Vector2<f32>> {
+        match (&mut self.attributes, set) {
+            (&mut Attributes::NoTex1NoTangentNoBones(ref mut vertices), 0) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.texcoord0).collect()
+            },
+            (&mut Attributes::NoTex1NoTangentBones(ref mut vertices), 0) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.texcoord0).collect()
+            },
+            (&mut Attributes::NoTex1TangentNoBones(ref mut vertices), 0) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.texcoord0).collect()
+            },
+            (&mut Attributes::NoTex1TangentBones(ref mut vertices), 0) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.texcoord0).collect()
+            },
+            (&mut Attributes::Tex1NoTangentNoBones(ref mut vertices), 0) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.texcoord0).collect()
+            },
+            (&mut Attributes::Tex1NoTangentBones(ref mut vertices), 0) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.texcoord0).collect()
+            },
+            (&mut Attributes::Tex1TangentNoBones(ref mut vertices), 0) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.texcoord0).collect()
+            },
+            (&mut Attributes::Tex1TangentBones(ref mut vertices), 0) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.texcoord0).collect()
+            },
+            (&mut Attributes::NoTex1NoTangentColorNoBones(ref mut vertices), 0) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.texcoord0).collect()
+            },
+            (&mut Attributes::NoTex1NoTangentColorBones(ref mut vertices), 0) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.texcoord0).collect()
+            },
+            (&mut Attributes::NoTex1TangentColorNoBones(ref mut vertices), 0) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.texcoord0).collect()
+            },
+            (&mut Attributes::NoTex1TangentColorBones(ref mut vertices), 0) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.texcoord0).collect()
+            },
+            (&mut Attributes::Tex1NoTangentColorNoBones(ref mut vertices), 0) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.texcoord0).collect()
+            },
+            (&mut Attributes::Tex1NoTangentColorBones(ref mut vertices), 0) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.texcoord0).collect()
+            },
+            (&mut Attributes::Tex1TangentColorNoBones(ref mut vertices), 0) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.texcoord0).collect()
+            },
+            (&mut Attributes::Tex1TangentColorBones(ref mut vertices), 0) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.texcoord0).collect()
+            },
+            (&mut Attributes::Tex1NoTangentNoBones(ref mut vertices), 1) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.texcoord1).collect()
+            },
+            (&mut Attributes::Tex1NoTangentBones(ref mut vertices), 1) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.texcoord1).collect()
+            },
+            (&mut Attributes::Tex1TangentNoBones(ref mut vertices), 1) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.texcoord1).collect()
+            },
+            (&mut Attributes::Tex1TangentBones(ref mut vertices), 1) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.texcoord1).collect()
+            },
+            (&mut Attributes::Tex1NoTangentColorNoBones(ref mut vertices), 1) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.texcoord1).collect()
+            },
+            (&mut Attributes::Tex1NoTangentColorBones(ref mut vertices), 1) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.texcoord1).collect()
+            },
+            (&mut Attributes::Tex1TangentColorNoBones(ref mut vertices), 1) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.texcoord1).collect()
+            },
+            (&mut Attributes::Tex1TangentColorBones(ref mut vertices), 1) => {
+                vertices.iter_mut().map(|vertex| &mut vertex.texcoord1).collect()
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    /// Rewrites vertex joint indices after `Skin::sort_topologically`.
+    pub fn remap_joints(&mut self, mapping: &[u16]) {
+        match self.attributes {
+            Attributes::NoTex1NoTangentBones(ref mut vertices) => {
+                for vertex in vertices.iter_mut() {
+                    remap_joint_indices(mapping, &mut vertex.joints);
+                }
+            },
+            Attributes::NoTex1TangentBones(ref mut vertices) => {
+                for vertex in vertices.iter_mut() {
+                    remap_joint_indices(mapping, &mut vertex.joints);
+                }
+            },
+            Attributes::Tex1NoTangentBones(ref mut vertices) => {
+                for vertex in vertices.iter_mut() {
+                    remap_joint_indices(mapping, &mut vertex.joints);
+                }
+            },
+            Attributes::Tex1TangentBones(ref mut vertices) => {
+                for vertex in vertices.iter_mut() {
+                    remap_joint_indices(mapping, &mut vertex.joints);
+                }
+            },
+            Attributes::NoTex1NoTangentColorBones(ref mut vertices) => {
+                for vertex in vertices.iter_mut() {
+                    remap_joint_indices(mapping, &mut vertex.joints);
+                }
+            },
+            Attributes::NoTex1TangentColorBones(ref mut vertices) => {
+                for vertex in vertices.iter_mut() {
+                    remap_joint_indices(mapping, &mut vertex.joints);
+                }
+            },
+            Attributes::Tex1NoTangentColorBones(ref mut vertices) => {
+                for vertex in vertices.iter_mut() {
+                    remap_joint_indices(mapping, &mut vertex.joints);
+                }
+            },
+            Attributes::Tex1TangentColorBones(ref mut vertices) => {
+                for vertex in vertices.iter_mut() {
+                    remap_joint_indices(mapping, &mut vertex.joints);
+                }
+            },
+            _ => {},
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Mode {
+    Points,
+    Lines,
+    LineLoop,
+    LineStrip,
+    Triangles,
+    TriangleStrip,
+    TriangleFan,
+}
+
+/// Iterator over the triangles of a primitive.
+pub struct Triangles<'a> {
+    mode: Mode,
+    indices: &'a [u32],
+    index: usize,
+}
+
+impl<'a> Iterator for Triangles<'a> {
+    type Item = [u32; 3];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let i = self.index;
+        let triangle = match self.mode {
+            Mode::Triangles if i * 3 + 2 < self.indices.len() => {
+                let j = i * 3;
+                [self.indices[j], self.indices[j + 1], self.indices[j + 2]]
+            },
+            // Flip every other triangle of a strip to keep the winding order.
+            Mode::TriangleStrip if i + 2 < self.indices.len() => {
+                if i % 2 == 0 {
+                    [self.indices[i], self.indices[i + 1], self.indices[i + 2]]
+                } else {
+                    [self.indices[i + 1], self.indices[i], self.indices[i + 2]]
+                }
+            },
+            Mode::TriangleFan if i + 2 < self.indices.len() => {
+                [self.indices[0], self.indices[i + 1], self.indices[i + 2]]
+            },
+            _ => { return None; },
+        };
+
+        self.index += 1;
+        Some(triangle)
+    }
 }
 
 pub fn get<'a>(
     primitives: GltfPrimitives,
+    node_name: &'a str,
     weights: Option<&'a [f32]>,
-    has_joints: bool,
+    has_skin: bool,
     buffers: &'a Buffers,
     materials: &'a Materials,
+    options: &'a Options,
+    warnings: &'a mut Vec<ConvertWarning>,
 ) -> Result<Vec<Primitive>> {
     primitives.map(|primitive| {
-        // let morph_targets = get_morph_targets(primitive, buffers)?;
+        check_attribute_sets(&primitive, node_name, options, warnings)?;
+        let morph_targets = get_morph_targets(&primitive, buffers, options)?;
 
-        // The default material is not supported.
-        let material_index = match primitive.material().index() {
-            Some(index) => index,
-            None => { return Err(Error::Convert(ConvertError::NoMaterial)); },
+        // The default material is only supported under a name from the options.
+        let material = match (primitive.material().index(), options.default_material_name.as_ref()) {
+            (Some(index), _) => materials.get(index).ok_or(ConvertError::Other)?,
+            (None, Some(name)) => name.as_str(),
+            (None, None) => { return Err(Error::Convert(ConvertError::NoMaterial)); },
+        };
+        let mode = match primitive.mode() {
+            GltfMode::Points => Mode::Points,
+            GltfMode::Lines => Mode::Lines,
+            GltfMode::LineLoop => Mode::LineLoop,
+            GltfMode::LineStrip => Mode::LineStrip,
+            GltfMode::Triangles => Mode::Triangles,
+            GltfMode::TriangleStrip => Mode::TriangleStrip,
+            GltfMode::TriangleFan => Mode::TriangleFan,
         };
-        let material = materials.get(material_index)
-            .ok_or(ConvertError::Other)?;
-        let attributes = get_attributes(
-            &primitive,
-            has_joints,
-            buffers,
-        )?;
-        let indices = get_indices(&primitive, buffers)?;
-
-        Ok(Primitive {
+        // Vertex indices are stored as `u32`, so larger primitives cannot
+        // be addressed.
+        let vertex_count = primitive.get(&GltfSemantic::Positions)
+            .map(|accessor| accessor.count())
+            .unwrap_or(0);
+        check_vertex_count(vertex_count as u64)?;
+
+        let has_normals = primitive.get(&GltfSemantic::Normals).is_some();
+        let has_tangents = primitive.get(&GltfSemantic::Tangents).is_some();
+        let (attributes, indices) = match mode {
+            // Point clouds usually come without normals or indices.
+            Mode::Points => {
+                let attributes = get_points(&primitive, buffers, options)?;
+                let indices = match primitive.indices_u32(buffers)? {
+                    Some(iter) => iter.collect::<Vec<_>>(),
+                    None => Vec::new(),
+                };
+                check_indices(&indices, vertex_count)?;
+
+                (attributes, indices)
+            },
+            _ => {
+                // Only primitives with joints and weights of their own are
+                // skinned, whatever the node says.
+                let has_influences = primitive.joints_u16(0, buffers)?.is_some() &&
+                    primitive.weights_f32(0, buffers)?.is_some();
+                if has_influences != has_skin {
+                    warnings.push(ConvertWarning::SkinMismatch {
+                        node: node_name.to_owned(),
+                        has_skin: has_skin,
+                    });
+                }
+                let has_joints = has_skin && has_influences;
+
+                let attributes = get_attributes(
+                    &primitive,
+                    has_joints,
+                    buffers,
+                    options,
+                )?;
+                let indices = get_indices(&primitive, vertex_count, buffers)?;
+
+                (attributes, indices)
+            },
+        };
+
+        let mut my_primitive = Primitive {
             material: material.to_owned(),
+            mode: mode,
             attributes: attributes,
             indices: indices,
-        })
+            morph_targets: morph_targets,
+        };
+
+        if !has_normals && mode != Mode::Points {
+            my_primitive.flat_normals()?;
+        }
+        if !has_tangents && mode != Mode::Points && needs_tangents(&primitive, options) {
+            my_primitive.generate_tangents();
+        }
+        my_primitive.fix_normals(options.unit_normals)?;
+        if options.handedness == Handedness::Left {
+            my_primitive.mirror_z();
+        }
+        if options.duplicate_double_sided && materials.by_name(material).map_or(false, Material::double_sided) {
+            my_primitive.duplicate_back_faces()?;
+        }
+        if options.bake_uv_wrap {
+            for set in 0..2 {
+                if let Some((wrap_s, wrap_t)) = get_wrapping_modes(&primitive, set) {
+                    my_primitive.bake_wrap(set, wrap_s, wrap_t);
+                }
+            }
+        }
+        if options.optimize_vertex_cache {
+            my_primitive.optimize_vertex_cache();
+        }
+
+        Ok(my_primitive)
     }).collect()
 }
 
+/// Number of sets glTF allows for each of the numbered attributes.
+const MAX_ATTRIBUTE_SETS: u32 = 4;
+
+/// Finds attribute sets past `MAX_ATTRIBUTE_SETS`. Those are never read, so
+/// ignoring them only takes a warning.
+fn check_attribute_sets<'a>(
+    primitive: &'a GltfPrimitive,
+    node_name: &'a str,
+    options: &'a Options,
+    warnings: &'a mut Vec<ConvertWarning>,
+) -> Result<()> {
+    for attribute in primitive.attributes() {
+        let (name, index) = match attribute {
+            GltfAttribute::Colors(index, _) => ("COLOR", index),
+            GltfAttribute::TexCoords(index, _) => ("TEXCOORD", index),
+            GltfAttribute::Joints(index, _) => ("JOINTS", index),
+            GltfAttribute::Weights(index, _) => ("WEIGHTS", index),
+            _ => { continue; },
+        };
+        if index < MAX_ATTRIBUTE_SETS {
+            continue;
+        }
+
+        match options.extra_attribute_sets {
+            ExtraAttributeSets::Ignore => {
+                warnings.push(ConvertWarning::UnsupportedAttributeSet {
+                    node: node_name.to_owned(),
+                    name: name,
+                    index: index,
+                });
+            },
+            ExtraAttributeSets::Reject => {
+                return Err(Error::Convert(ConvertError::UnsupportedAttributeSet {
+                    name: name,
+                    index: index,
+                }));
+            },
+        }
+    }
+
+    Ok(())
+}
+
+/// Largest difference from unit length tolerated by `UnitNormals::Reject`.
+const UNIT_NORMAL_EPSILON: f32 = 1e-3;
+
+/// Whether tangents should be generated for `primitive` when it has none.
+fn needs_tangents<'a>(primitive: &'a GltfPrimitive, options: &'a Options) -> bool {
+    options.generate_tangents && primitive.material().normal_texture().is_some()
+}
+
+/// Replaces `items` so the new item `i` is a copy of the old item
+/// `order[i]`.
+fn unweld<T: Clone>(items: &mut Vec<T>, order: &[u32]) {
+    *items = order.iter().map(|&old| items[old as usize].clone()).collect();
+}
+
+/// Appends a copy of every item.
+fn duplicate<T: Clone>(items: &mut Vec<T>) {
+    let copies = items.clone();
+    items.extend(copies);
+}
+
+/// Reorders `items` so the new item `i` is the old item `order[i]`.
+fn permute<T>(items: &mut Vec<T>, order: &[u32]) {
+    let mut old_items = items.drain(..).map(Some).collect::<Vec<_>>();
+    items.extend(order.iter().map(|&old| old_items[old as usize].take().unwrap()));
+}
+
+/// Wrapping modes of the first texture of the primitive's material that
+/// samples texture coordinate set `set`.
+fn get_wrapping_modes<'a>(
+    primitive: &'a GltfPrimitive,
+    set: u32,
+) -> Option<(WrappingMode, WrappingMode)> {
+    let material = primitive.material();
+    let pbr = material.pbr_metallic_roughness();
+    let textures = vec![
+        pbr.base_color_texture().map(|info| (info.tex_coord(), info.texture())),
+        pbr.metallic_roughness_texture().map(|info| (info.tex_coord(), info.texture())),
+        material.normal_texture().map(|info| (info.tex_coord(), info.texture())),
+        material.occlusion_texture().map(|info| (info.tex_coord(), info.texture())),
+        material.emissive_texture().map(|info| (info.tex_coord(), info.texture())),
+    ];
+
+    textures.into_iter()
+        .filter_map(|texture| texture)
+        .find(|&(tex_coord, _)| tex_coord == set)
+        .map(|(_, texture)| {
+            let sampler = texture.sampler();
+            (WrappingMode::from(sampler.wrap_s()), WrappingMode::from(sampler.wrap_t()))
+        })
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub enum Attributes {
     NoTex1NoTangentNoBones(Vec<VertexNoTex1NoTangentNoBones>),
     NoTex1NoTangentBones(Vec<VertexNoTex1NoTangentBones>),
@@ -57,33 +1151,316 @@ pub enum Attributes {
     Tex1NoTangentBones(Vec<VertexTex1NoTangentBones>),
     Tex1TangentNoBones(Vec<VertexTex1TangentNoBones>),
     Tex1TangentBones(Vec<VertexTex1TangentBones>),
+    NoTex1NoTangentColorNoBones(Vec<VertexNoTex1NoTangentColorNoBones>),
+    NoTex1NoTangentColorBones(Vec<VertexNoTex1NoTangentColorBones>),
+    NoTex1TangentColorNoBones(Vec<VertexNoTex1TangentColorNoBones>),
+    NoTex1TangentColorBones(Vec<VertexNoTex1TangentColorBones>),
+    Tex1NoTangentColorNoBones(Vec<VertexTex1NoTangentColorNoBones>),
+    Tex1NoTangentColorBones(Vec<VertexTex1NoTangentColorBones>),
+    Tex1TangentColorNoBones(Vec<VertexTex1TangentColorNoBones>),
+    Tex1TangentColorBones(Vec<VertexTex1TangentColorBones>),
+    Points {
+        positions: Vec<Vector3<f32>>,
+        colors: Option<Vec<Vector4<f32>>>,
+    },
+}
+
+fn get_points<'a>(
+    primitive: &'a GltfPrimitive,
+    buffers: &'a Buffers,
+    options: &'a Options,
+) -> Result<Attributes> {
+    let positions = primitive.positions(buffers)?.ok_or(ConvertError::MissingAttributes)?
+        .read_all()
+        .into_iter()
+        .map(Vector3::<f32>::from)
+        .collect::<Vec<_>>();
+    let colors = match get_colors(primitive, buffers, options)? {
+        Some(colors) => {
+            let colors = colors.into_iter().map(Vector4::<f32>::from).collect::<Vec<_>>();
+
+            // Test all vertex attributes have the same number of elements.
+            if colors.len() != positions.len() {
+                return Err(Error::Convert(ConvertError::Other));
+            }
+
+            Some(colors)
+        },
+        None => None,
+    };
+
+    Ok(Attributes::Points {
+        positions: positions,
+        colors: colors,
+    })
+}
+
+/// Reads the `COLOR_0` vertex colors as RGBA, in the color space from the
+/// options. Integer colors are normalized to [0, 1] and RGB colors get an
+/// opaque alpha.
+fn get_colors<'a>(
+    primitive: &'a GltfPrimitive,
+    buffers: &'a Buffers,
+    options: &'a Options,
+) -> Result<Option<Vec<[f32; 4]>>> {
+    Ok(primitive.colors_rgba_f32(0, buffers)?.map(|iter| {
+        iter.map(|color| ColorSpace::Linear.convert(color, options.vertex_color_space))
+            .collect::<Vec<_>>()
+    }))
 }
 
 fn get_attributes<'a>(
     primitive: &'a GltfPrimitive,
     has_joints: bool,
     buffers: &'a Buffers,
+    options: &'a Options,
 ) -> Result<Attributes> {
     // Common iterators and their number of elements
-    let pos_num = primitive.positions(buffers).ok_or(ConvertError::MissingAttributes)?.count();
-    let pos_it = primitive.positions(buffers).ok_or(ConvertError::MissingAttributes)?;
-    let nor_num = primitive.normals(buffers).ok_or(ConvertError::MissingAttributes)?.count();
-    let nor_it = primitive.normals(buffers).ok_or(ConvertError::MissingAttributes)?;
-    let tx0_num = primitive.tex_coords_f32(0, buffers).ok_or(ConvertError::MissingAttributes)?.count();
-    let tx0_it = primitive.tex_coords_f32(0, buffers).ok_or(ConvertError::Other)?;
-
-    let has_tangents = primitive.tangents(buffers).is_some();
-    let has_texcoords_1 = primitive.tex_coords_f32(1, buffers).is_some();
-
-    if has_texcoords_1 && has_tangents && has_joints {
-        let tx1_num = primitive.tex_coords_f32(0, buffers).ok_or(ConvertError::Other)?.count();
-        let tx1_it = primitive.tex_coords_f32(0, buffers).ok_or(ConvertError::Other)?;
-        let tan_num = primitive.tangents(buffers).ok_or(ConvertError::Other)?.count();
-        let tan_it = primitive.tangents(buffers).ok_or(ConvertError::Other)?;
-        let id0_num = primitive.joints_u16(0, buffers).ok_or(ConvertError::Other)?.count();
-        let id0_it = primitive.joints_u16(0, buffers).ok_or(ConvertError::Other)?;
-        let wt0_num = primitive.weights_f32(0, buffers).ok_or(ConvertError::Other)?.count();
-        let wt0_it = primitive.weights_f32(0, buffers).ok_or(ConvertError::Other)?;
+    let positions = primitive.positions(buffers)?.ok_or(ConvertError::MissingAttributes)?.read_all();
+    let pos_num = positions.len();
+    let pos_it = positions.into_iter();
+    // Missing normals are filled in by `Primitive::flat_normals`.
+    let normals = match (primitive.normals(buffers)?, options.flat_normals) {
+        (Some(normals), _) => normals.read_all(),
+        (None, true) => vec![[0.0; 3]; pos_num],
+        (None, false) => { return Err(Error::Convert(ConvertError::MissingAttributes)); },
+    };
+    let nor_num = normals.len();
+    let nor_it = normals.into_iter();
+    let tex_coords_0 = primitive.tex_coords_f32(0, buffers)?.ok_or(ConvertError::MissingAttributes)?.read_all();
+    let tx0_num = tex_coords_0.len();
+    let tx0_it = tex_coords_0.into_iter();
+
+    // Missing tangents are filled in by `Primitive::generate_tangents`.
+    let tangents = match primitive.tangents(buffers)? {
+        Some(tangents) => Some(tangents.read_all()),
+        None if needs_tangents(primitive, options) => Some(vec![[0.0; 4]; pos_num]),
+        None => None,
+    };
+    let has_tangents = tangents.is_some();
+    let has_texcoords_1 = primitive.tex_coords_f32(1, buffers)?.is_some();
+    let colors = get_colors(primitive, buffers, options)?;
+    let has_colors = colors.is_some();
+
+    if has_colors && has_texcoords_1 && has_tangents && has_joints {
+        let tx1_num = primitive.tex_coords_f32(1, buffers)?.ok_or(ConvertError::Other)?.count();
+        let tx1_it = primitive.tex_coords_f32(1, buffers)?.ok_or(ConvertError::Other)?;
+        let tangents = tangents.ok_or(ConvertError::Other)?;
+        let tan_num = tangents.len();
+        let tan_it = tangents.into_iter();
+        let colors = colors.ok_or(ConvertError::Other)?;
+        let col_num = colors.len();
+        let col_it = colors.into_iter();
+        let (joints, weights) = get_influences(primitive, buffers, options)?;
+        let id0_num = joints.len();
+        let id0_it = joints.into_iter();
+        let wt0_num = weights.len();
+        let wt0_it = weights.into_iter();
+
+        // Test all vertex attributes have the same number of elements.
+        if pos_num == nor_num && nor_num == tx0_num && tx0_num == tx1_num && tx1_num == tan_num && tan_num == col_num && col_num == id0_num && id0_num == wt0_num {
+            Ok(Attributes::Tex1TangentColorBones(multizip((pos_it, nor_it, tx0_it, tx1_it, tan_it, col_it, id0_it, wt0_it))
+               .map(|(pos, norm, tx0, tx1, tang, col, ids, wts)| {
+                   VertexTex1TangentColorBones {
+                       position: Vector3::<f32>::from(pos),
+                       normal: Vector3::<f32>::from(norm),
+                       texcoord0: Vector2::<f32>::from(tx0),
+                       texcoord1: Vector2::<f32>::from(tx1),
+                       tangent: Vector4::<f32>::from(tang),
+                       color: Vector4::<f32>::from(col),
+                       joints: Vector4::<u16>::from(ids),
+                       weights: Vector4::<f32>::from(wts),
+                   }
+               }).collect()))
+        } else {
+            Err(Error::Convert(ConvertError::Other))
+        }
+    } else if has_colors && has_texcoords_1 && has_tangents && !has_joints {
+        let tx1_num = primitive.tex_coords_f32(1, buffers)?.ok_or(ConvertError::Other)?.count();
+        let tx1_it = primitive.tex_coords_f32(1, buffers)?.ok_or(ConvertError::Other)?;
+        let tangents = tangents.ok_or(ConvertError::Other)?;
+        let tan_num = tangents.len();
+        let tan_it = tangents.into_iter();
+        let colors = colors.ok_or(ConvertError::Other)?;
+        let col_num = colors.len();
+        let col_it = colors.into_iter();
+
+        // Test all vertex attributes have the same number of elements.
+        if pos_num == nor_num && nor_num == tx0_num && tx0_num == tx1_num && tx1_num == tan_num && tan_num == col_num {
+            Ok(Attributes::Tex1TangentColorNoBones(multizip((pos_it, nor_it, tx0_it, tx1_it, tan_it, col_it))
+               .map(|(pos, norm, tx0, tx1, tang, col)| {
+                   VertexTex1TangentColorNoBones {
+                       position: Vector3::<f32>::from(pos),
+                       normal: Vector3::<f32>::from(norm),
+                       texcoord0: Vector2::<f32>::from(tx0),
+                       texcoord1: Vector2::<f32>::from(tx1),
+                       tangent: Vector4::<f32>::from(tang),
+                       color: Vector4::<f32>::from(col),
+                   }
+               }).collect()))
+        } else {
+            Err(Error::Convert(ConvertError::Other))
+        }
+    } else if has_colors && has_texcoords_1 && !has_tangents && has_joints {
+        let tx1_num = primitive.tex_coords_f32(1, buffers)?.ok_or(ConvertError::Other)?.count();
+        let tx1_it = primitive.tex_coords_f32(1, buffers)?.ok_or(ConvertError::Other)?;
+        let colors = colors.ok_or(ConvertError::Other)?;
+        let col_num = colors.len();
+        let col_it = colors.into_iter();
+        let (joints, weights) = get_influences(primitive, buffers, options)?;
+        let id0_num = joints.len();
+        let id0_it = joints.into_iter();
+        let wt0_num = weights.len();
+        let wt0_it = weights.into_iter();
+
+        // Test all vertex attributes have the same number of elements.
+        if pos_num == nor_num && nor_num == tx0_num && tx0_num == tx1_num && tx1_num == col_num && col_num == id0_num && id0_num == wt0_num {
+            Ok(Attributes::Tex1NoTangentColorBones(multizip((pos_it, nor_it, tx0_it, tx1_it, col_it, id0_it, wt0_it))
+               .map(|(pos, norm, tx0, tx1, col, ids, wts)| {
+                   VertexTex1NoTangentColorBones {
+                       position: Vector3::<f32>::from(pos),
+                       normal: Vector3::<f32>::from(norm),
+                       texcoord0: Vector2::<f32>::from(tx0),
+                       texcoord1: Vector2::<f32>::from(tx1),
+                       color: Vector4::<f32>::from(col),
+                       joints: Vector4::<u16>::from(ids),
+                       weights: Vector4::<f32>::from(wts),
+                   }
+               }).collect()))
+        } else {
+            Err(Error::Convert(ConvertError::Other))
+        }
+    } else if has_colors && has_texcoords_1 && !has_tangents && !has_joints {
+        let tx1_num = primitive.tex_coords_f32(1, buffers)?.ok_or(ConvertError::Other)?.count();
+        let tx1_it = primitive.tex_coords_f32(1, buffers)?.ok_or(ConvertError::Other)?;
+        let colors = colors.ok_or(ConvertError::Other)?;
+        let col_num = colors.len();
+        let col_it = colors.into_iter();
+
+        // Test all vertex attributes have the same number of elements.
+        if pos_num == nor_num && nor_num == tx0_num && tx0_num == tx1_num && tx1_num == col_num {
+            Ok(Attributes::Tex1NoTangentColorNoBones(multizip((pos_it, nor_it, tx0_it, tx1_it, col_it))
+               .map(|(pos, norm, tx0, tx1, col)| {
+                   VertexTex1NoTangentColorNoBones {
+                       position: Vector3::<f32>::from(pos),
+                       normal: Vector3::<f32>::from(norm),
+                       texcoord0: Vector2::<f32>::from(tx0),
+                       texcoord1: Vector2::<f32>::from(tx1),
+                       color: Vector4::<f32>::from(col),
+                   }
+               }).collect()))
+        } else {
+            Err(Error::Convert(ConvertError::Other))
+        }
+    } else if has_colors && !has_texcoords_1 && has_tangents && has_joints {
+        let tangents = tangents.ok_or(ConvertError::Other)?;
+        let tan_num = tangents.len();
+        let tan_it = tangents.into_iter();
+        let colors = colors.ok_or(ConvertError::Other)?;
+        let col_num = colors.len();
+        let col_it = colors.into_iter();
+        let (joints, weights) = get_influences(primitive, buffers, options)?;
+        let id0_num = joints.len();
+        let id0_it = joints.into_iter();
+        let wt0_num = weights.len();
+        let wt0_it = weights.into_iter();
+
+        // Test all vertex attributes have the same number of elements.
+        if pos_num == nor_num && nor_num == tx0_num && tx0_num == tan_num && tan_num == col_num && col_num == id0_num && id0_num == wt0_num {
+            Ok(Attributes::NoTex1TangentColorBones(multizip((pos_it, nor_it, tx0_it, tan_it, col_it, id0_it, wt0_it))
+               .map(|(pos, norm, tx0, tang, col, ids, wts)| {
+                   VertexNoTex1TangentColorBones {
+                       position: Vector3::<f32>::from(pos),
+                       normal: Vector3::<f32>::from(norm),
+                       texcoord0: Vector2::<f32>::from(tx0),
+                       tangent: Vector4::<f32>::from(tang),
+                       color: Vector4::<f32>::from(col),
+                       joints: Vector4::<u16>::from(ids),
+                       weights: Vector4::<f32>::from(wts),
+                   }
+               }).collect()))
+        } else {
+            Err(Error::Convert(ConvertError::Other))
+        }
+    } else if has_colors && !has_texcoords_1 && has_tangents && !has_joints {
+        let tangents = tangents.ok_or(ConvertError::Other)?;
+        let tan_num = tangents.len();
+        let tan_it = tangents.into_iter();
+        let colors = colors.ok_or(ConvertError::Other)?;
+        let col_num = colors.len();
+        let col_it = colors.into_iter();
+
+        // Test all vertex attributes have the same number of elements.
+        if pos_num == nor_num && nor_num == tx0_num && tx0_num == tan_num && tan_num == col_num {
+            Ok(Attributes::NoTex1TangentColorNoBones(multizip((pos_it, nor_it, tx0_it, tan_it, col_it))
+               .map(|(pos, norm, tx0, tang, col)| {
+                   VertexNoTex1TangentColorNoBones {
+                       position: Vector3::<f32>::from(pos),
+                       normal: Vector3::<f32>::from(norm),
+                       texcoord0: Vector2::<f32>::from(tx0),
+                       tangent: Vector4::<f32>::from(tang),
+                       color: Vector4::<f32>::from(col),
+                   }
+               }).collect()))
+        } else {
+            Err(Error::Convert(ConvertError::Other))
+        }
+    } else if has_colors && !has_texcoords_1 && !has_tangents && has_joints {
+        let colors = colors.ok_or(ConvertError::Other)?;
+        let col_num = colors.len();
+        let col_it = colors.into_iter();
+        let (joints, weights) = get_influences(primitive, buffers, options)?;
+        let id0_num = joints.len();
+        let id0_it = joints.into_iter();
+        let wt0_num = weights.len();
+        let wt0_it = weights.into_iter();
+
+        // Test all vertex attributes have the same number of elements.
+        if pos_num == nor_num && nor_num == tx0_num && tx0_num == col_num && col_num == id0_num && id0_num == wt0_num {
+            Ok(Attributes::NoTex1NoTangentColorBones(multizip((pos_it, nor_it, tx0_it, col_it, id0_it, wt0_it))
+               .map(|(pos, norm, tx0, col, ids, wts)| {
+                   VertexNoTex1NoTangentColorBones {
+                       position: Vector3::<f32>::from(pos),
+                       normal: Vector3::<f32>::from(norm),
+                       texcoord0: Vector2::<f32>::from(tx0),
+                       color: Vector4::<f32>::from(col),
+                       joints: Vector4::<u16>::from(ids),
+                       weights: Vector4::<f32>::from(wts),
+                   }
+               }).collect()))
+        } else {
+            Err(Error::Convert(ConvertError::Other))
+        }
+    } else if has_colors && !has_texcoords_1 && !has_tangents && !has_joints {
+        let colors = colors.ok_or(ConvertError::Other)?;
+        let col_num = colors.len();
+        let col_it = colors.into_iter();
+
+        // Test all vertex attributes have the same number of elements.
+        if pos_num == nor_num && nor_num == tx0_num && tx0_num == col_num {
+            Ok(Attributes::NoTex1NoTangentColorNoBones(multizip((pos_it, nor_it, tx0_it, col_it))
+               .map(|(pos, norm, tx0, col)| {
+                   VertexNoTex1NoTangentColorNoBones {
+                       position: Vector3::<f32>::from(pos),
+                       normal: Vector3::<f32>::from(norm),
+                       texcoord0: Vector2::<f32>::from(tx0),
+                       color: Vector4::<f32>::from(col),
+                   }
+               }).collect()))
+        } else {
+            Err(Error::Convert(ConvertError::Other))
+        }
+    } else if has_texcoords_1 && has_tangents && has_joints {
+        let tx1_num = primitive.tex_coords_f32(1, buffers)?.ok_or(ConvertError::Other)?.count();
+        let tx1_it = primitive.tex_coords_f32(1, buffers)?.ok_or(ConvertError::Other)?;
+        let tangents = tangents.ok_or(ConvertError::Other)?;
+        let tan_num = tangents.len();
+        let tan_it = tangents.into_iter();
+        let (joints, weights) = get_influences(primitive, buffers, options)?;
+        let id0_num = joints.len();
+        let id0_it = joints.into_iter();
+        let wt0_num = weights.len();
+        let wt0_it = weights.into_iter();
 
         // Test all vertex attributes have the same number of elements.
         if pos_num == nor_num && nor_num == tx0_num && tx0_num == tx1_num && tx1_num == tan_num && tan_num == id0_num && id0_num == wt0_num {
@@ -104,10 +1481,11 @@ fn get_attributes<'a>(
             Err(Error::Convert(ConvertError::Other))
         }
     } else if has_texcoords_1 && has_tangents && !has_joints {
-        let tx1_num = primitive.tex_coords_f32(0, buffers).ok_or(ConvertError::Other)?.count();
-        let tx1_it = primitive.tex_coords_f32(0, buffers).ok_or(ConvertError::Other)?;
-        let tan_num = primitive.tangents(buffers).ok_or(ConvertError::Other)?.count();
-        let tan_it = primitive.tangents(buffers).ok_or(ConvertError::Other)?;
+        let tx1_num = primitive.tex_coords_f32(1, buffers)?.ok_or(ConvertError::Other)?.count();
+        let tx1_it = primitive.tex_coords_f32(1, buffers)?.ok_or(ConvertError::Other)?;
+        let tangents = tangents.ok_or(ConvertError::Other)?;
+        let tan_num = tangents.len();
+        let tan_it = tangents.into_iter();
 
         // Test all vertex attributes have the same number of elements.
         if pos_num == nor_num && nor_num == tx0_num && tx0_num == tx1_num && tx1_num == tan_num {
@@ -126,12 +1504,13 @@ fn get_attributes<'a>(
             Err(Error::Convert(ConvertError::Other))
         }
     } else if has_texcoords_1 && !has_tangents && has_joints {
-        let tx1_num = primitive.tex_coords_f32(0, buffers).ok_or(ConvertError::Other)?.count();
-        let tx1_it = primitive.tex_coords_f32(0, buffers).ok_or(ConvertError::Other)?;
-        let id0_num = primitive.joints_u16(0, buffers).ok_or(ConvertError::Other)?.count();
-        let id0_it = primitive.joints_u16(0, buffers).ok_or(ConvertError::Other)?;
-        let wt0_num = primitive.weights_f32(0, buffers).ok_or(ConvertError::Other)?.count();
-        let wt0_it = primitive.weights_f32(0, buffers).ok_or(ConvertError::Other)?;
+        let tx1_num = primitive.tex_coords_f32(1, buffers)?.ok_or(ConvertError::Other)?.count();
+        let tx1_it = primitive.tex_coords_f32(1, buffers)?.ok_or(ConvertError::Other)?;
+        let (joints, weights) = get_influences(primitive, buffers, options)?;
+        let id0_num = joints.len();
+        let id0_it = joints.into_iter();
+        let wt0_num = weights.len();
+        let wt0_it = weights.into_iter();
         
         // Ensure all vertex attributes have the same number of elements.
         if pos_num == nor_num && nor_num == tx0_num && tx0_num == tx1_num && tx1_num == id0_num && id0_num == wt0_num {
@@ -151,8 +1530,8 @@ fn get_attributes<'a>(
             Err(Error::Convert(ConvertError::Other))
         }
     } else if has_texcoords_1 && !has_tangents && !has_joints {
-        let tx1_num = primitive.tex_coords_f32(0, buffers).ok_or(ConvertError::Other)?.count();
-        let tx1_it = primitive.tex_coords_f32(0, buffers).ok_or(ConvertError::Other)?;
+        let tx1_num = primitive.tex_coords_f32(1, buffers)?.ok_or(ConvertError::Other)?.count();
+        let tx1_it = primitive.tex_coords_f32(1, buffers)?.ok_or(ConvertError::Other)?;
         
         // Ensure all vertex attributes have the same number of elements.
         if pos_num == nor_num && nor_num == tx0_num && tx0_num == tx1_num {
@@ -171,12 +1550,14 @@ fn get_attributes<'a>(
         }
 
     } else if !has_texcoords_1 && has_tangents && has_joints {
-        let tan_num = primitive.tangents(buffers).ok_or(ConvertError::Other)?.count();
-        let tan_it = primitive.tangents(buffers).ok_or(ConvertError::Other)?;
-        let id0_num = primitive.joints_u16(0, buffers).ok_or(ConvertError::Other)?.count();
-        let id0_it = primitive.joints_u16(0, buffers).ok_or(ConvertError::Other)?;
-        let wt0_num = primitive.weights_f32(0, buffers).ok_or(ConvertError::Other)?.count();
-        let wt0_it = primitive.weights_f32(0, buffers).ok_or(ConvertError::Other)?;
+        let tangents = tangents.ok_or(ConvertError::Other)?;
+        let tan_num = tangents.len();
+        let tan_it = tangents.into_iter();
+        let (joints, weights) = get_influences(primitive, buffers, options)?;
+        let id0_num = joints.len();
+        let id0_it = joints.into_iter();
+        let wt0_num = weights.len();
+        let wt0_it = weights.into_iter();
         
         // Ensure all vertex attributes have the same number of elements.
         if pos_num == nor_num && nor_num == tx0_num && tx0_num == tan_num && tan_num == id0_num && id0_num == wt0_num {
@@ -197,8 +1578,9 @@ fn get_attributes<'a>(
         }
 
     } else if !has_texcoords_1 && has_tangents && !has_joints {
-        let tan_num = primitive.tangents(buffers).ok_or(ConvertError::Other)?.count();
-        let tan_it = primitive.tangents(buffers).ok_or(ConvertError::Other)?;
+        let tangents = tangents.ok_or(ConvertError::Other)?;
+        let tan_num = tangents.len();
+        let tan_it = tangents.into_iter();
         
         // Ensure all vertex attributes have the same number of elements.
         if pos_num == nor_num && nor_num == tx0_num && tx0_num == tan_num {
@@ -217,10 +1599,11 @@ fn get_attributes<'a>(
         }
 
     } else if !has_texcoords_1 && !has_tangents && has_joints {
-        let id0_num = primitive.joints_u16(0, buffers).ok_or(ConvertError::Other)?.count();
-        let id0_it = primitive.joints_u16(0, buffers).ok_or(ConvertError::Other)?;
-        let wt0_num = primitive.weights_f32(0, buffers).ok_or(ConvertError::Other)?.count();
-        let wt0_it = primitive.weights_f32(0, buffers).ok_or(ConvertError::Other)?;
+        let (joints, weights) = get_influences(primitive, buffers, options)?;
+        let id0_num = joints.len();
+        let id0_it = joints.into_iter();
+        let wt0_num = weights.len();
+        let wt0_it = weights.into_iter();
         
         // Ensure all vertex attributes have the same number of elements.
         if pos_num == nor_num && nor_num == tx0_num && tx0_num == id0_num && id0_num == wt0_num {
@@ -256,12 +1639,66 @@ fn get_attributes<'a>(
     }
 }
 
+/// Reads the joint indices and weights of every vertex. A second set of
+/// influences is only used with `Options::prune_influences`.
+fn get_influences<'a>(
+    primitive: &'a GltfPrimitive,
+    buffers: &'a Buffers,
+    options: &'a Options,
+) -> Result<(Vec<[u16; 4]>, Vec<[f32; 4]>)> {
+    let joints_0 = primitive.joints_u16(0, buffers)?.ok_or(ConvertError::Other)?.collect::<Vec<_>>();
+    let weights_0 = primitive.weights_f32(0, buffers)?.ok_or(ConvertError::Other)?.collect::<Vec<_>>();
+
+    if !options.prune_influences {
+        return Ok((joints_0, weights_0));
+    }
+
+    let (joints_1, weights_1) = match (primitive.joints_u16(1, buffers)?, primitive.weights_f32(1, buffers)?) {
+        (Some(joints), Some(weights)) => (joints.collect::<Vec<_>>(), weights.collect::<Vec<_>>()),
+        _ => { return Ok((joints_0, weights_0)); },
+    };
+
+    // Test all vertex attributes have the same number of elements.
+    if joints_0.len() != weights_0.len() || joints_0.len() != joints_1.len() || joints_1.len() != weights_1.len() {
+        return Err(Error::Convert(ConvertError::Other));
+    }
+
+    Ok(multizip((joints_0, weights_0, joints_1, weights_1))
+       .map(|(joints_0, weights_0, joints_1, weights_1)| {
+           prune_influences(
+               [joints_0[0], joints_0[1], joints_0[2], joints_0[3],
+                joints_1[0], joints_1[1], joints_1[2], joints_1[3]],
+               [weights_0[0], weights_0[1], weights_0[2], weights_0[3],
+                weights_1[0], weights_1[1], weights_1[2], weights_1[3]],
+           )
+       }).unzip())
+}
+
+/// Keeps the four strongest of eight influences and renormalizes their
+/// weights to sum to one.
+fn prune_influences(joints: [u16; 8], weights: [f32; 8]) -> ([u16; 4], [f32; 4]) {
+    let mut influences = joints.iter().cloned().zip(weights.iter().cloned()).collect::<Vec<_>>();
+    influences.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+
+    let total = influences[..4].iter().fold(0.0, |sum, &(_, weight)| sum + weight);
+    let mut my_joints = [0_u16; 4];
+    let mut my_weights = [0.0_f32; 4];
+    for (i, &(joint, weight)) in influences[..4].iter().enumerate() {
+        my_joints[i] = joint;
+        my_weights[i] = if total > 0.0 { weight / total } else { weight };
+    }
+
+    (my_joints, my_weights)
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct VertexNoTex1NoTangentNoBones {
     position: Vector3<f32>,
     normal: Vector3<f32>,
     texcoord0: Vector2<f32>,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct VertexNoTex1NoTangentBones {
     position: Vector3<f32>,
     normal: Vector3<f32>,
@@ -270,6 +1707,7 @@ pub struct VertexNoTex1NoTangentBones {
     weights: Vector4<f32>,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct VertexNoTex1TangentNoBones {
     position: Vector3<f32>,
     normal: Vector3<f32>,
@@ -277,6 +1715,7 @@ pub struct VertexNoTex1TangentNoBones {
     tangent: Vector4<f32>,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct VertexNoTex1TangentBones {
     position: Vector3<f32>,
     normal: Vector3<f32>,
@@ -286,6 +1725,7 @@ pub struct VertexNoTex1TangentBones {
     weights: Vector4<f32>,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct VertexTex1NoTangentNoBones {
     position: Vector3<f32>,
     normal: Vector3<f32>,
@@ -293,6 +1733,7 @@ pub struct VertexTex1NoTangentNoBones {
     texcoord1: Vector2<f32>,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct VertexTex1NoTangentBones {
     position: Vector3<f32>,
     normal: Vector3<f32>,
@@ -302,6 +1743,7 @@ pub struct VertexTex1NoTangentBones {
     weights: Vector4<f32>,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct VertexTex1TangentNoBones {
     position: Vector3<f32>,
     normal: Vector3<f32>,
@@ -310,6 +1752,7 @@ pub struct VertexTex1TangentNoBones {
     tangent: Vector4<f32>,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct VertexTex1TangentBones {
     position: Vector3<f32>,
     normal: Vector3<f32>,
@@ -320,11 +1763,605 @@ pub struct VertexTex1TangentBones {
     weights: Vector4<f32>,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
+pub struct VertexNoTex1NoTangentColorNoBones {
+    position: Vector3<f32>,
+    normal: Vector3<f32>,
+    texcoord0: Vector2<f32>,
+    color: Vector4<f32>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct VertexNoTex1NoTangentColorBones {
+    position: Vector3<f32>,
+    normal: Vector3<f32>,
+    texcoord0: Vector2<f32>,
+    color: Vector4<f32>,
+    joints: Vector4<u16>,
+    weights: Vector4<f32>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct VertexNoTex1TangentColorNoBones {
+    position: Vector3<f32>,
+    normal: Vector3<f32>,
+    texcoord0: Vector2<f32>,
+    tangent: Vector4<f32>,
+    color: Vector4<f32>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct VertexNoTex1TangentColorBones {
+    position: Vector3<f32>,
+    normal: Vector3<f32>,
+    texcoord0: Vector2<f32>,
+    tangent: Vector4<f32>,
+    color: Vector4<f32>,
+    joints: Vector4<u16>,
+    weights: Vector4<f32>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct VertexTex1NoTangentColorNoBones {
+    position: Vector3<f32>,
+    normal: Vector3<f32>,
+    texcoord0: Vector2<f32>,
+    texcoord1: Vector2<f32>,
+    color: Vector4<f32>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct VertexTex1NoTangentColorBones {
+    position: Vector3<f32>,
+    normal: Vector3<f32>,
+    texcoord0: Vector2<f32>,
+    texcoord1: Vector2<f32>,
+    color: Vector4<f32>,
+    joints: Vector4<u16>,
+    weights: Vector4<f32>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct VertexTex1TangentColorNoBones {
+    position: Vector3<f32>,
+    normal: Vector3<f32>,
+    texcoord0: Vector2<f32>,
+    texcoord1: Vector2<f32>,
+    tangent: Vector4<f32>,
+    color: Vector4<f32>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct VertexTex1TangentColorBones {
+    position: Vector3<f32>,
+    normal: Vector3<f32>,
+    texcoord0: Vector2<f32>,
+    texcoord1: Vector2<f32>,
+    tangent: Vector4<f32>,
+    color: Vector4<f32>,
+    joints: Vector4<u16>,
+    weights: Vector4<f32>,
+}
+
+/// Fails if `count` vertices cannot be addressed by `u32` indices.
+fn check_vertex_count(count: u64) -> Result<()> {
+    if count > u32::MAX as u64 {
+        Err(Error::Convert(ConvertError::VertexCountOverflow { count: count }))
+    } else {
+        Ok(())
+    }
+}
+
+/// Fails if any of `indices` names a vertex past `vertex_count`. The passes
+/// that run after reading index the vertices directly, so this has to hold
+/// before any of them do.
+fn check_indices(indices: &[u32], vertex_count: usize) -> Result<()> {
+    match indices.iter().find(|&&index| index as usize >= vertex_count) {
+        Some(&index) => {
+            Err(Error::Convert(ConvertError::IndexOutOfRange {
+                index: index,
+                vertex_count: vertex_count,
+            }))
+        },
+        None => Ok(()),
+    }
+}
+
+/// Reads the indices of `primitive`. Primitives without any draw their
+/// vertices in order, so those get `0..vertex_count`.
 fn get_indices<'a>(
     primitive: &'a GltfPrimitive,
+    vertex_count: usize,
     buffers: &'a Buffers,
 ) -> Result<Vec<u32>> {
-    let iter = primitive.indices_u32(buffers).ok_or(ConvertError::MissingAttributes)?;
+    match primitive.indices_u32(buffers)? {
+        Some(iter) => {
+            let indices = iter.collect::<Vec<_>>();
+            check_indices(&indices, vertex_count)?;
+
+            Ok(indices)
+        },
+        None => Ok((0..vertex_count as u32).collect::<Vec<_>>()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use super::super::import;
+    use super::super::material::get as get_materials;
+    use super::super::texture::{Textures, get as get_textures};
+
+    #[test]
+    fn test_extra_attribute_sets() {
+        let path = Path::new("testmodels/gltf2/TexCoord5/TexCoord5.gltf");
+        let (gltf, extensions, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let materials = get_materials(gltf.materials(), &Textures::default(), &extensions).unwrap();
+        let mesh = gltf.meshes().next().unwrap();
+        let mut options = Options::default();
+        let mut warnings = Vec::new();
+
+        let primitives = get(mesh.primitives(), "quad", None, false, &buffers, &materials, &options, &mut warnings);
+        assert_eq!(primitives.unwrap()[0].vertex_count(), 4);
+        assert_eq!(warnings, vec![ConvertWarning::UnsupportedAttributeSet {
+            node: String::from("quad"),
+            name: "TEXCOORD",
+            index: 5,
+        }]);
+
+        options.extra_attribute_sets = ExtraAttributeSets::Reject;
+        match get(mesh.primitives(), "quad", None, false, &buffers, &materials, &options, &mut Vec::new()) {
+            Err(Error::Convert(ConvertError::UnsupportedAttributeSet { name: "TEXCOORD", index: 5 })) => {},
+            _ => panic!("Expected an unsupported attribute set"),
+        }
+    }
+
+    #[test]
+    fn test_left_handed() {
+        let path = Path::new("testmodels/gltf2/TangentQuad/TangentQuad.gltf");
+        let (gltf, extensions, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let materials = get_materials(gltf.materials(), &Textures::default(), &extensions).unwrap();
+        let mesh = gltf.meshes().next().unwrap();
+        let mut options = Options::default();
+        options.handedness = Handedness::Left;
+        let primitives = get(mesh.primitives(), "quad", None, false, &buffers, &materials, &options, &mut Vec::new()).unwrap();
+
+        match *primitives[0].attributes() {
+            Attributes::NoTex1TangentNoBones(ref vertices) => {
+                assert_eq!(vertices[0].normal, Vector3::new(0.0, 0.0, -1.0));
+                assert_eq!(vertices[0].tangent, Vector4::new(1.0, 0.0, 0.0, -1.0));
+            },
+            _ => panic!("Expected tangents"),
+        }
+        assert_eq!(&primitives[0].indices()[..3], &[0, 2, 1]);
+    }
+
+    #[test]
+    fn test_unindexed() {
+        let path = Path::new("testmodels/gltf2/Unindexed/Unindexed.gltf");
+        let (gltf, extensions, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let materials = get_materials(gltf.materials(), &Textures::default(), &extensions).unwrap();
+        let mesh = gltf.meshes().next().unwrap();
+        let primitives = get(mesh.primitives(), "quad", None, false, &buffers, &materials, &Options::default(), &mut Vec::new()).unwrap();
+
+        assert_eq!(primitives[0].vertex_count(), 6);
+        assert_eq!(primitives[0].indices(), &[0, 1, 2, 3, 4, 5]);
+        assert_eq!(primitives[0].triangles().count(), 2);
+    }
+
+    #[test]
+    fn test_index_out_of_range() {
+        let path = Path::new("testmodels/gltf2/BadIndex/BadIndex.gltf");
+        let (gltf, extensions, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let materials = get_materials(gltf.materials(), &Textures::default(), &extensions).unwrap();
+
+        // Without normals the triangle would be unwelded for flat normals.
+        let mesh = gltf.meshes().next().unwrap();
+        match get(mesh.primitives(), "unlit", None, false, &buffers, &materials, &Options::default(), &mut Vec::new()) {
+            Err(Error::Convert(ConvertError::IndexOutOfRange { index, vertex_count })) => {
+                assert_eq!((index, vertex_count), (5, 3));
+            },
+            _ => panic!("expected IndexOutOfRange"),
+        }
+
+        // With normals nothing is unwelded, but the vertex cache optimizer
+        // would still index past the vertices.
+        let mut options = Options::default();
+        options.optimize_vertex_cache = true;
+        let mesh = gltf.meshes().nth(1).unwrap();
+        match get(mesh.primitives(), "lit", None, false, &buffers, &materials, &options, &mut Vec::new()) {
+            Err(Error::Convert(ConvertError::IndexOutOfRange { index, vertex_count })) => {
+                assert_eq!((index, vertex_count), (5, 3));
+            },
+            _ => panic!("expected IndexOutOfRange"),
+        }
+    }
+
+    #[test]
+    fn test_flat_normals() {
+        let path = Path::new("testmodels/gltf2/FlatCube/FlatCube.gltf");
+        let (gltf, extensions, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let materials = get_materials(gltf.materials(), &Textures::default(), &extensions).unwrap();
+        let mesh = gltf.meshes().next().unwrap();
+        let mut options = Options::default();
+        let primitives = get(mesh.primitives(), "cube", None, false, &buffers, &materials, &options, &mut Vec::new()).unwrap();
+
+        // Each of the 12 triangles gets its own corners.
+        let primitive = &primitives[0];
+        assert_eq!(primitive.vertex_count(), 36);
+        assert_eq!(primitive.indices(), &(0..36).collect::<Vec<u32>>()[..]);
+        let normals = match *primitive.attributes() {
+            Attributes::NoTex1NoTangentNoBones(ref vertices) => {
+                vertices.iter().map(|vertex| vertex.normal).collect::<Vec<_>>()
+            },
+            _ => panic!("Expected plain vertices"),
+        };
+        assert_eq!(normals[0], Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(normals[6], Vector3::new(0.0, 0.0, -1.0));
+        assert_eq!(normals[12], Vector3::new(0.0, -1.0, 0.0));
+        assert!(normals.chunks(3).all(|face| face[0] == face[1] && face[1] == face[2]));
+
+        options.flat_normals = false;
+        match get(mesh.primitives(), "cube", None, false, &buffers, &materials, &options, &mut Vec::new()) {
+            Err(Error::Convert(ConvertError::MissingAttributes)) => {},
+            _ => panic!("Expected missing attributes"),
+        }
+    }
+
+    #[test]
+    fn test_generate_tangents() {
+        let path = Path::new("testmodels/gltf2/NormalMapQuad/NormalMapQuad.gltf");
+        let (gltf, extensions, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let textures = get_textures(path.parent().unwrap(), gltf.textures(), &buffers, &Options::default(), &mut Vec::new()).unwrap();
+        let materials = get_materials(gltf.materials(), &textures, &extensions).unwrap();
+        let mesh = gltf.meshes().next().unwrap();
+        let mut options = Options::default();
+        let primitives = get(mesh.primitives(), "quad", None, false, &buffers, &materials, &options, &mut Vec::new()).unwrap();
+
+        // U follows X and V runs down Y, as in the tangent quad.
+        match *primitives[0].attributes() {
+            Attributes::NoTex1TangentNoBones(ref vertices) => {
+                for vertex in vertices {
+                    assert_eq!(vertex.tangent, Vector4::new(1.0, 0.0, 0.0, 1.0));
+                }
+            },
+            _ => panic!("Expected tangents"),
+        }
+
+        // Mirrored texture coordinates flip the bitangent.
+        let mut primitive = primitives[0].clone();
+        for tex_coord in primitive.tex_coords_mut(0) {
+            tex_coord.y = 1.0 - tex_coord.y;
+        }
+        primitive.generate_tangents();
+        assert!(primitive.tangents_mut().into_iter().all(|tangent| *tangent == Vector4::new(1.0, 0.0, 0.0, -1.0)));
+
+        options.generate_tangents = false;
+        let primitives = get(mesh.primitives(), "quad", None, false, &buffers, &materials, &options, &mut Vec::new()).unwrap();
+        match *primitives[0].attributes() {
+            Attributes::NoTex1NoTangentNoBones(_) => {},
+            _ => panic!("Expected no tangents"),
+        }
+    }
+
+    #[test]
+    fn test_morph_targets() {
+        let path = Path::new("testmodels/gltf2/MorphQuad/MorphQuad.gltf");
+        let (gltf, extensions, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let materials = get_materials(gltf.materials(), &Textures::default(), &extensions).unwrap();
+        let mesh = gltf.meshes().next().unwrap();
+        let primitives = get(
+            mesh.primitives(),
+            "bulge",
+            None,
+            false,
+            &buffers,
+            &materials,
+            &Options::default(),
+            &mut Vec::new(),
+        ).unwrap();
+
+        let morph_targets = primitives[0].morph_targets();
+        assert_eq!(morph_targets.len(), 1);
+        assert!(morph_targets[0].positions().is_some());
+        assert!(morph_targets[0].normals().is_some());
+        assert!(morph_targets[0].tangents().is_none());
+    }
+
+    #[test]
+    fn test_tex_coords_1() {
+        let path = Path::new("testmodels/gltf2/TwoUvSets/TwoUvSets.gltf");
+        let (gltf, extensions, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let materials = get_materials(gltf.materials(), &Textures::default(), &extensions).unwrap();
+        let mesh = gltf.meshes().next().unwrap();
+        let primitives = get(
+            mesh.primitives(),
+            "panel",
+            None,
+            false,
+            &buffers,
+            &materials,
+            &Options::default(),
+            &mut Vec::new(),
+        ).unwrap();
+
+        match primitives[0].attributes {
+            Attributes::Tex1NoTangentNoBones(ref vertices) => {
+                assert!(vertices.iter().any(|vertex| vertex.texcoord1 != vertex.texcoord0));
+                assert_eq!(vertices[2].texcoord1, Vector2::new(0.75, 0.75));
+            },
+            _ => panic!("Expected a second texture coordinate set"),
+        }
+    }
 
-    Ok(iter.collect::<Vec<_>>())
+    #[test]
+    fn test_vertex_colors() {
+        let path = Path::new("testmodels/gltf2/VertexColors/VertexColors.gltf");
+        let (gltf, extensions, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let materials = get_materials(gltf.materials(), &Textures::default(), &extensions).unwrap();
+        let mesh = gltf.meshes().next().unwrap();
+        let primitives = get(mesh.primitives(), "painted", None, false, &buffers, &materials, &Options::default(), &mut Vec::new()).unwrap();
+
+        // The colors are normalized unsigned shorts, as Blender exports them.
+        match primitives[0].attributes {
+            Attributes::NoTex1NoTangentColorNoBones(ref vertices) => {
+                assert_eq!(vertices[0].color, Vector4::new(1.0, 0.0, 0.0, 1.0));
+                assert_eq!(vertices[1].color, Vector4::new(0.0, 1.0, 0.0, 1.0));
+                assert_eq!(vertices[2].color, Vector4::new(0.0, 0.0, 1.0, 1.0));
+                assert_eq!(vertices[3].color, Vector4::new(1.0, 1.0, 0.0, 0.0));
+            },
+            _ => panic!("Expected vertex colors"),
+        }
+
+        let (layout, bytes) = primitives[0].interleaved_bytes();
+        assert_eq!(layout.stride, 48);
+        assert_eq!(bytes.len(), 4 * 48);
+
+        // The second primitive is mid gray in normalized unsigned bytes, the
+        // second vertex half transparent.
+        let mut options = Options::default();
+        options.vertex_color_space = ColorSpace::Srgb;
+        for &(ref options, gray) in [(Options::default(), 128.0 / 255.0), (options, 0.736_647)].iter() {
+            let primitives = get(mesh.primitives(), "painted", None, false, &buffers, &materials, options, &mut Vec::new()).unwrap();
+            match primitives[1].attributes {
+                Attributes::NoTex1NoTangentColorNoBones(ref vertices) => {
+                    for (vertex, alpha) in vertices.iter().zip(&[1.0, 128.0 / 255.0, 1.0, 1.0]) {
+                        assert!((vertex.color - Vector4::new(gray, gray, gray, *alpha)).magnitude() < 1e-5);
+                    }
+                },
+                _ => panic!("Expected vertex colors"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_points() {
+        let path = Path::new("testmodels/gltf2/PointCloud/PointCloud.gltf");
+        let (gltf, extensions, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let materials = get_materials(gltf.materials(), &Textures::default(), &extensions).unwrap();
+        let mesh = gltf.meshes().next().unwrap();
+        let primitives = get(
+            mesh.primitives(),
+            "points",
+            None,
+            false,
+            &buffers,
+            &materials,
+            &Options::default(),
+            &mut Vec::new(),
+        ).unwrap();
+
+        assert!(primitives[0].indices.is_empty());
+        match primitives[0].attributes {
+            Attributes::Points { ref positions, ref colors } => {
+                assert_eq!(positions.len(), 3);
+                assert_eq!(colors.as_ref().map(Vec::len), Some(3));
+                assert_eq!(positions[1], Vector3::new(1.0, 0.0, 0.0));
+            },
+            _ => panic!("expected point attributes"),
+        }
+        assert_eq!(primitives[0].triangles().count(), 0);
+    }
+
+    fn get_primitive(mode: Mode, indices: Vec<u32>) -> Primitive {
+        Primitive {
+            material: String::from("test"),
+            mode: mode,
+            attributes: Attributes::NoTex1NoTangentNoBones(Vec::new()),
+            indices: indices,
+            morph_targets: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_prune_influences() {
+        let (joints, weights) = prune_influences(
+            [1, 2, 3, 4, 5, 6, 7, 8],
+            [0.05, 0.3, 0.05, 0.1, 0.2, 0.05, 0.15, 0.1],
+        );
+
+        assert_eq!(joints, [2, 5, 7, 4]);
+        assert!((weights.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+        assert!((weights[0] - 0.3 / 0.75).abs() < 1e-6);
+        assert!((weights[3] - 0.1 / 0.75).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_bake_wrap() {
+        let mut primitive = get_primitive(Mode::Triangles, Vec::new());
+        let vertex = |u: f32, v: f32| VertexNoTex1NoTangentNoBones {
+            position: Vector3::new(0.0, 0.0, 0.0),
+            normal: Vector3::new(0.0, 0.0, 1.0),
+            texcoord0: Vector2::new(u, v),
+        };
+        primitive.attributes = Attributes::NoTex1NoTangentNoBones(vec![
+            vertex(1.5, 1.25),
+            vertex(-0.25, 2.75),
+        ]);
+
+        primitive.bake_wrap(0, WrappingMode::Repeat, WrappingMode::MirroredRepeat);
+        match primitive.attributes {
+            Attributes::NoTex1NoTangentNoBones(ref vertices) => {
+                assert_eq!(vertices[0].texcoord0, Vector2::new(0.5, 0.75));
+                assert_eq!(vertices[1].texcoord0, Vector2::new(0.75, 0.75));
+            },
+            _ => panic!("unexpected attributes"),
+        }
+
+        assert_eq!(WrappingMode::Repeat.bake(1.5), 0.5);
+        assert_eq!(WrappingMode::MirroredRepeat.bake(1.5), 0.5);
+        assert_eq!(WrappingMode::MirroredRepeat.bake(1.75), 0.25);
+        assert_eq!(WrappingMode::MirroredRepeat.bake(-0.25), 0.25);
+        assert_eq!(WrappingMode::ClampToEdge.bake(1.5), 1.5);
+    }
+
+    #[test]
+    fn test_fix_normals() {
+        let mut primitive = get_primitive(Mode::Triangles, Vec::new());
+        primitive.attributes = Attributes::NoTex1TangentNoBones(vec![VertexNoTex1TangentNoBones {
+            position: Vector3::new(0.0, 0.0, 0.0),
+            normal: Vector3::new(0.0, 0.0, 2.0),
+            texcoord0: Vector2::new(0.0, 0.0),
+            tangent: Vector4::new(3.0, 0.0, 0.0, -1.0),
+        }]);
+
+        assert!(primitive.fix_normals(UnitNormals::Keep).is_ok());
+        match primitive.fix_normals(UnitNormals::Reject) {
+            Err(Error::Convert(ConvertError::NonUnitNormal { vertex })) => assert_eq!(vertex, 0),
+            _ => panic!("expected NonUnitNormal"),
+        }
+
+        primitive.fix_normals(UnitNormals::Normalize).unwrap();
+        match primitive.attributes {
+            Attributes::NoTex1TangentNoBones(ref vertices) => {
+                assert_eq!(vertices[0].normal, Vector3::new(0.0, 0.0, 1.0));
+                assert_eq!(vertices[0].tangent, Vector4::new(1.0, 0.0, 0.0, -1.0));
+            },
+            _ => panic!("unexpected attributes"),
+        }
+        assert!(primitive.fix_normals(UnitNormals::Reject).is_ok());
+    }
+
+    #[test]
+    fn test_duplicate_back_faces() {
+        let path = Path::new("testmodels/gltf2/DoubleSided/DoubleSided.gltf");
+        let (gltf, extensions, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let materials = get_materials(gltf.materials(), &Textures::default(), &extensions).unwrap();
+        let mesh = gltf.meshes().next().unwrap();
+        let mut options = Options::default();
+        options.duplicate_double_sided = true;
+        let primitives = get(
+            mesh.primitives(),
+            "quads",
+            None,
+            false,
+            &buffers,
+            &materials,
+            &options,
+            &mut Vec::new(),
+        ).unwrap();
+
+        // Only the double sided quad is duplicated.
+        assert_eq!(primitives[0].triangles().count(), 4);
+        assert_eq!(primitives[0].vertex_count(), 8);
+        assert_eq!(primitives[1].triangles().count(), 2);
+
+        let positions = primitives[0].positions();
+        let facing = primitives[0].triangles().map(|[a, b, c]| {
+            let (a, b, c) = (positions[a as usize], positions[b as usize], positions[c as usize]);
+            (b - a).cross(c - a).z.signum()
+        }).collect::<Vec<_>>();
+        assert_eq!(facing, vec![1.0, 1.0, -1.0, -1.0]);
+
+        match primitives[0].attributes {
+            Attributes::NoTex1NoTangentNoBones(ref vertices) => {
+                assert_eq!(vertices[0].normal, Vector3::new(0.0, 0.0, 1.0));
+                assert_eq!(vertices[4].normal, Vector3::new(0.0, 0.0, -1.0));
+            },
+            _ => panic!("unexpected attributes"),
+        }
+    }
+
+    #[test]
+    fn test_vertex_count_overflow() {
+        assert!(check_vertex_count(u32::MAX as u64).is_ok());
+        match check_vertex_count(u32::MAX as u64 + 1) {
+            Err(Error::Convert(ConvertError::VertexCountOverflow { count })) => {
+                assert_eq!(count, u32::MAX as u64 + 1);
+            },
+            _ => panic!("expected VertexCountOverflow"),
+        }
+    }
+
+    #[test]
+    fn test_interleaved_bytes() {
+        use byteorder::{LE, ByteOrder};
+
+        let vertex = |x: f32| VertexTex1TangentNoBones {
+            position: Vector3::new(x, 2.0, 3.0),
+            normal: Vector3::new(0.0, 0.0, 1.0),
+            texcoord0: Vector2::new(0.5, 0.5),
+            texcoord1: Vector2::new(0.25, 0.75),
+            tangent: Vector4::new(1.0, 0.0, 0.0, 1.0),
+        };
+        let mut primitive = get_primitive(Mode::Triangles, vec![0, 1, 2]);
+        primitive.attributes = Attributes::Tex1TangentNoBones(vec![vertex(1.0), vertex(4.0), vertex(7.0)]);
+
+        let (layout, bytes) = primitive.interleaved_bytes();
+        assert_eq!(layout.stride, 12 + 12 + 8 + 8 + 16);
+        assert_eq!(bytes.len(), 3 * layout.stride);
+        assert_eq!(layout.attributes[4].semantic, Semantic::Tangent);
+        assert_eq!(layout.attributes[4].offset, 40);
+
+        let position = [LE::read_f32(&bytes[0..]), LE::read_f32(&bytes[4..]), LE::read_f32(&bytes[8..])];
+        assert_eq!(position, [1.0, 2.0, 3.0]);
+        assert_eq!(LE::read_f32(&bytes[layout.stride..]), 4.0);
+    }
+
+    #[test]
+    fn test_split_streams() {
+        let vertex = |x: f32| VertexNoTex1NoTangentNoBones {
+            position: Vector3::new(x, 2.0, 3.0),
+            normal: Vector3::new(0.0, 0.0, 1.0),
+            texcoord0: Vector2::new(0.5, 0.5),
+        };
+        let mut primitive = get_primitive(Mode::Triangles, vec![0, 1, 2]);
+        primitive.attributes = Attributes::NoTex1NoTangentNoBones(vec![vertex(1.0), vertex(4.0), vertex(7.0)]);
+
+        let (positions, attributes) = primitive.split_streams();
+        let (layout, bytes) = primitive.interleaved_bytes();
+        let stride = layout.stride - AttributeFormat::F32x3.size();
+        assert_eq!(positions.len(), primitive.vertex_count());
+        assert_eq!(positions[1], Vector3::new(4.0, 2.0, 3.0));
+        assert_eq!(stride, 12 + 8);
+        assert_eq!(attributes.len(), primitive.vertex_count() * stride);
+        assert_eq!(&attributes[stride..2 * stride], &bytes[layout.stride + 12..2 * layout.stride]);
+    }
+
+    #[test]
+    fn test_triangles() {
+        let cube = vec![
+            0, 1, 2, 0, 2, 3, // front
+            4, 6, 5, 4, 7, 6, // back
+            0, 4, 5, 0, 5, 1, // bottom
+            3, 2, 6, 3, 6, 7, // top
+            0, 3, 7, 0, 7, 4, // left
+            1, 5, 6, 1, 6, 2, // right
+        ];
+        let triangles = get_primitive(Mode::Triangles, cube).triangles().collect::<Vec<_>>();
+        assert_eq!(triangles.len(), 12);
+        assert_eq!(triangles[0], [0, 1, 2]);
+        assert_eq!(triangles[3], [4, 7, 6]);
+        assert_eq!(triangles[11], [1, 6, 2]);
+
+        let strip = get_primitive(Mode::TriangleStrip, vec![0, 1, 2, 3]).triangles().collect::<Vec<_>>();
+        assert_eq!(strip, vec![[0, 1, 2], [2, 1, 3]]);
+
+        let fan = get_primitive(Mode::TriangleFan, vec![0, 1, 2, 3]).triangles().collect::<Vec<_>>();
+        assert_eq!(fan, vec![[0, 1, 2], [0, 2, 3]]);
+
+        assert_eq!(get_primitive(Mode::Lines, vec![0, 1]).triangles().count(), 0);
+    }
 }
@@ -0,0 +1,67 @@
+use std::f32::consts::SQRT_2;
+use std::i16;
+
+use cgmath::{InnerSpace, Quaternion, Vector3};
+
+/// Maps `value` from `min..max` onto the whole `i16` range.
+pub fn quantize(value: f32, min: f32, max: f32) -> i16 {
+    if max <= min {
+        return 0;
+    }
+    let unit = ((value - min) / (max - min)).max(0.0).min(1.0);
+
+    ((unit * 65535.0).round() - 32768.0) as i16
+}
+
+/// Inverse of `quantize`.
+pub fn dequantize(value: i16, min: f32, max: f32) -> f32 {
+    min + (value as f32 + 32768.0) / 65535.0 * (max - min)
+}
+
+/// A unit quaternion stored as its three smallest components. The largest
+/// one is rebuilt from the unit length, with the sign of the quaternion
+/// flipped so it is positive.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SmallestThree {
+    /// Position of the dropped component, in `x, y, z, w` order.
+    largest: u8,
+    /// The other components in order, each within `±1/√2`.
+    values: [i16; 3],
+}
+
+impl SmallestThree {
+    pub fn from_quaternion(quaternion: Quaternion<f32>) -> SmallestThree {
+        let quaternion = quaternion.normalize();
+        let components = [quaternion.v.x, quaternion.v.y, quaternion.v.z, quaternion.s];
+        let largest = (0..4).fold(0, |largest, index| {
+            if components[index].abs() > components[largest].abs() { index } else { largest }
+        });
+        let sign = if components[largest] < 0.0 { -1.0 } else { 1.0 };
+
+        let mut values = [0; 3];
+        for (value, index) in values.iter_mut().zip((0..4).filter(|&index| index != largest)) {
+            let scaled = components[index] * sign * SQRT_2 * i16::MAX as f32;
+            *value = scaled.round().max(-(i16::MAX as f32)).min(i16::MAX as f32) as i16;
+        }
+
+        SmallestThree {
+            largest: largest as u8,
+            values: values,
+        }
+    }
+
+    pub fn to_quaternion(&self) -> Quaternion<f32> {
+        let smallest = self.values.iter()
+            .map(|&value| value as f32 / (SQRT_2 * i16::MAX as f32))
+            .collect::<Vec<_>>();
+        let largest = (1.0 - smallest.iter().map(|value| value * value).sum::<f32>()).max(0.0).sqrt();
+
+        let mut components = [0.0; 4];
+        let mut rest = smallest.into_iter();
+        for (index, component) in components.iter_mut().enumerate() {
+            *component = if index == self.largest as usize { largest } else { rest.next().unwrap() };
+        }
+
+        Quaternion::from_sv(components[3], Vector3::new(components[0], components[1], components[2]))
+    }
+}
@@ -0,0 +1,240 @@
+use cgmath::{Matrix4, SquareMatrix, Vector3, Vector4};
+use gltf::{Node, Scene};
+
+use super::Model;
+use super::extension::Extensions;
+use super::{is_hidden, local_transform};
+use super::options::{NameIds, Options};
+use super::util::name_id;
+
+/// An axis aligned bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>,
+}
+
+impl Aabb {
+    /// Returns the smallest box holding every point, if there are any.
+    pub fn from_points<I: IntoIterator<Item = Vector3<f32>>>(points: I) -> Option<Aabb> {
+        points.into_iter().fold(None, |aabb: Option<Aabb>, point| {
+            let point_aabb = Aabb { min: point, max: point };
+            Some(aabb.map_or(point_aabb, |aabb| aabb.union(&point_aabb)))
+        })
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: Vector3::new(
+                self.min.x.min(other.min.x),
+                self.min.y.min(other.min.y),
+                self.min.z.min(other.min.z),
+            ),
+            max: Vector3::new(
+                self.max.x.max(other.max.x),
+                self.max.y.max(other.max.y),
+                self.max.z.max(other.max.z),
+            ),
+        }
+    }
+
+    /// Whether `other` lies entirely inside, allowing for rounding.
+    pub fn contains(&self, other: &Aabb) -> bool {
+        let epsilon = 1e-5;
+
+        self.min.x - epsilon <= other.min.x && other.max.x <= self.max.x + epsilon &&
+        self.min.y - epsilon <= other.min.y && other.max.y <= self.max.y + epsilon &&
+        self.min.z - epsilon <= other.min.z && other.max.z <= self.max.z + epsilon
+    }
+
+    /// Returns the box around the transformed corners.
+    pub fn transform(&self, matrix: &Matrix4<f32>) -> Aabb {
+        let corners = (0..8).map(|corner| {
+            let point = Vector4::new(
+                if corner & 1 == 0 { self.min.x } else { self.max.x },
+                if corner & 2 == 0 { self.min.y } else { self.max.y },
+                if corner & 4 == 0 { self.min.z } else { self.max.z },
+                1.0,
+            );
+            (matrix * point).truncate()
+        });
+
+        Aabb::from_points(corners).unwrap()
+    }
+}
+
+/// A visible node of the scene, with the bounds of everything below it.
+pub struct SceneNode {
+    name: Option<String>,
+    name_id: Option<u64>,
+    node_index: usize,
+    model_indices: Vec<usize>,
+    world_transform: Matrix4<f32>,
+    world_bounds: Option<Aabb>,
+    children: Vec<SceneNode>,
+}
+
+impl SceneNode {
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_ref().map(String::as_str)
+    }
+
+    /// Hashed ID of the name, with `Options::name_ids`.
+    pub fn name_id(&self) -> Option<u64> {
+        self.name_id
+    }
+
+    /// Index of the node in the glTF nodes.
+    pub fn node_index(&self) -> usize {
+        self.node_index
+    }
+
+    /// Indices of the node's models in `Conversion::models`. Nodes have
+    /// two with `Options::split_by_alpha_mode` if their mesh is split.
+    pub fn model_indices(&self) -> &[usize] {
+        &self.model_indices
+    }
+
+    pub fn world_transform(&self) -> &Matrix4<f32> {
+        &self.world_transform
+    }
+
+    /// World space bounds of the node's models and all of its descendants'.
+    /// `None` when none of them have a model.
+    pub fn world_bounds(&self) -> Option<Aabb> {
+        self.world_bounds
+    }
+
+    pub fn children(&self) -> &[SceneNode] {
+        &self.children
+    }
+}
+
+/// Builds the hierarchy of the visible nodes in `scene`.
+pub fn get<'a>(
+    scene: &'a Scene,
+    models: &'a [Model],
+    extensions: &'a Extensions,
+    options: &'a Options,
+) -> Vec<SceneNode> {
+    scene.nodes().filter_map(|node| {
+        get_helper(&node, &Matrix4::identity(), models, extensions, options)
+    }).collect()
+}
+
+fn get_helper<'a>(
+    node: &'a Node,
+    parent_transform: &'a Matrix4<f32>,
+    models: &'a [Model],
+    extensions: &'a Extensions,
+    options: &'a Options,
+) -> Option<SceneNode> {
+    if is_hidden(node, extensions, options) {
+        return None;
+    }
+
+    let world_transform = parent_transform * local_transform(node, options);
+    let my_model_indices = models.iter().enumerate()
+        .filter(|&(_, model)| model.node_index() == Some(node.index()))
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+
+    let children = node.children().filter_map(|child| {
+        get_helper(&child, &world_transform, models, extensions, options)
+    }).collect::<Vec<_>>();
+
+    // Combine the bounds bottom up.
+    let model_bounds = Aabb::from_points(my_model_indices.iter()
+        .flat_map(|&index| models[index].mesh().primitives().iter())
+        .flat_map(|primitive| primitive.positions()))
+        .map(|aabb| aabb.transform(&world_transform));
+    let world_bounds = children.iter()
+        .filter_map(SceneNode::world_bounds)
+        .chain(model_bounds)
+        .fold(None, |bounds: Option<Aabb>, aabb| Some(bounds.map_or(aabb, |bounds| bounds.union(&aabb))));
+
+    Some(SceneNode {
+        name: match options.name_ids {
+            NameIds::Instead => None,
+            _ => node.name().map(String::from),
+        },
+        name_id: match options.name_ids {
+            NameIds::Off => None,
+            _ => node.name().map(name_id),
+        },
+        node_index: node.index(),
+        model_indices: my_model_indices,
+        world_transform: world_transform,
+        world_bounds: world_bounds,
+        children: children,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use super::super::{Conversion, get_with_stats};
+
+    fn model_bounds(conversion: &Conversion, node: &SceneNode) -> Aabb {
+        let model = &conversion.models()[node.model_indices()[0]];
+        Aabb::from_points(model.mesh().primitives()[0].positions()).unwrap()
+            .transform(node.world_transform())
+    }
+
+    #[test]
+    fn test_world_bounds() {
+        let path = Path::new("testmodels/gltf2/Hierarchy/Hierarchy.gltf");
+        let (conversion, _) = get_with_stats(path, &Options::default()).unwrap();
+        let root = &conversion.scene()[0];
+        let root_bounds = root.world_bounds().unwrap();
+
+        assert!(root.model_indices().is_empty());
+        assert_eq!(root.children().len(), 2);
+        for child in root.children() {
+            assert!(root_bounds.contains(&model_bounds(&conversion, child)));
+        }
+
+        // The left quad is moved by -2, the right one is doubled and raised by 5.
+        assert_eq!(root_bounds, Aabb {
+            min: Vector3::new(8.0, 0.0, 0.0),
+            max: Vector3::new(12.0, 7.0, 0.0),
+        });
+    }
+
+    #[test]
+    fn test_split_bounds() {
+        let path = Path::new("testmodels/gltf2/MixedAlpha/MixedAlpha.gltf");
+        let mut options = Options::default();
+        options.split_by_alpha_mode = true;
+        let (conversion, _) = get_with_stats(path, &options).unwrap();
+        let node = &conversion.scene()[0];
+
+        // The raised glass is in the blended model, after the opaque one.
+        assert_eq!(node.model_indices(), &[0, 1]);
+        assert_eq!(node.world_bounds(), Some(Aabb {
+            min: Vector3::new(0.0, 0.0, 0.0),
+            max: Vector3::new(1.0, 3.0, 0.0),
+        }));
+    }
+
+    #[test]
+    fn test_name_ids() {
+        let path = Path::new("testmodels/gltf2/Hierarchy/Hierarchy.gltf");
+        let mut options = Options::default();
+
+        options.name_ids = NameIds::Alongside;
+        let (conversion, _) = get_with_stats(path, &options).unwrap();
+        let root = &conversion.scene()[0];
+        assert_eq!(root.name_id(), root.name().map(name_id));
+        assert!(root.name_id().is_some());
+
+        options.name_ids = NameIds::Instead;
+        let (other, _) = get_with_stats(path, &options).unwrap();
+        assert_eq!(other.scene()[0].name(), None);
+        assert_eq!(other.scene()[0].name_id(), root.name_id());
+        assert_eq!(other.models()[0].mesh().name(), None);
+        assert_eq!(other.models()[0].mesh().name_id(), conversion.models()[0].mesh().name_id());
+    }
+}
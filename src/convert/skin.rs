@@ -1,39 +1,114 @@
 use std::u16;
 use std::usize;
 
-use cgmath::{Matrix4, SquareMatrix};
+use std::collections::{HashMap, VecDeque};
+
+use cgmath::{Matrix4, SquareMatrix, Vector4};
+use gltf::Node;
 use gltf::gltf::Skins as GltfSkins;
 use gltf::skin::Skin as GltfSkin;
-use gltf_importer::Buffers;
 use itertools::multizip;
 
 use super::super::{Result, Error};
-use super::ConvertError;
+use super::{ConvertError, ConvertWarning, mirror_z};
+use super::buffer::Buffers;
+use super::dual_quaternion::DualQuaternion;
+use super::extension::Extensions;
+use super::humanoid::{HumanoidMap, get as get_humanoid};
+use super::options::{Handedness, Options};
 use super::util::SkinIterators;
 
 pub struct Skins {
     skins: Vec<Skin>,
+    /// Skin and joint index of every joint made from each node.
+    node_joints: HashMap<usize, Vec<(usize, u16)>>,
+    /// New index of every glTF joint of each skin sorted by
+    /// `Options::sort_joints`.
+    joint_mappings: Vec<Option<Vec<u16>>>,
 }
 
 impl Skins {
+    /// Joint index in the first skin using the node.
     pub fn get_joint_index(&self, node_index: usize) -> Option<u16> {
-        for skin in self.skins.iter() {
-            if let Some(index) = skin.get_joint_index(node_index) {
-                return Some(index);
-            }
-        }
+        self.get_joints(node_index).first().map(|&(_, joint_index)| joint_index)
+    }
 
-        None
+    /// Joint name in the first skin using the node.
+    pub fn get_joint_name(&self, node_index: usize) -> Option<&str> {
+        self.get_joints(node_index).first()
+            .and_then(|&(skin_index, _)| self.skins[skin_index].get_joint_name(node_index))
+    }
+
+    /// Returns the skin and joint index of every joint made from the node.
+    pub fn get_joints(&self, node_index: usize) -> &[(usize, u16)] {
+        self.node_joints.get(&node_index).map_or(&[], |joints| &joints[..])
+    }
+
+    /// Returns the skin and joint index of the node, preferring `skin_index`
+    /// when the node is shared between skins.
+    pub fn resolve_joint(&self, node_index: usize, skin_index: Option<usize>) -> Option<(usize, u16)> {
+        let joints = self.get_joints(node_index);
+
+        skin_index.and_then(|skin_index| joints.iter().find(|joint| joint.0 == skin_index))
+            .or_else(|| joints.first())
+            .cloned()
+    }
+
+    /// Returns the first skin with a joint for every node, if any.
+    pub fn find_skin(&self, node_indices: &[usize]) -> Option<usize> {
+        (0..self.skins.len()).find(|&skin_index| {
+            node_indices.iter().all(|&node_index| {
+                self.get_joints(node_index).iter().any(|joint| joint.0 == skin_index)
+            })
+        })
+    }
+
+    /// Returns the new index of every glTF joint of the skin, if its joints
+    /// were reordered.
+    pub fn joint_mapping(&self, skin_index: usize) -> Option<&[u16]> {
+        self.joint_mappings.get(skin_index)
+            .and_then(|mapping| mapping.as_ref())
+            .map(|mapping| &mapping[..])
+    }
+
+    pub fn skins(&self) -> &[Skin] {
+        &self.skins
+    }
+
+    pub fn into_skins(self) -> Vec<Skin> {
+        self.skins
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Skin {
     name: String,
     root_index: u16,
     joints: Vec<Joint>,
+    humanoid: HumanoidMap,
+    bind_pose_dual_quats: Option<Vec<DualQuaternion>>,
+    /// Kept up to date with the joints by `update_global_transforms`.
+    global_transforms: Vec<Matrix4<f32>>,
 }
 
 impl Skin {
+    /// Model space bind pose of every joint, the inverse of its inverse bind
+    /// matrix, with `Options::dual_quaternion_skinning`. Only the bind pose
+    /// is converted; animations keep their translation and rotation
+    /// channels.
+    pub fn bind_pose_dual_quats(&self) -> Option<&[DualQuaternion]> {
+        self.bind_pose_dual_quats.as_ref().map(Vec::as_slice)
+    }
+
+    /// Standard humanoid bones of the skin, if it is a VRM avatar.
+    pub fn humanoid(&self) -> &HumanoidMap {
+        &self.humanoid
+    }
+
+    pub fn joints(&self) -> &[Joint] {
+        &self.joints
+    }
+
     pub fn get_joint_index(&self, node_index: usize) -> Option<u16> {
         let mut index = 0_u16;
         
@@ -47,8 +122,123 @@ impl Skin {
 
         None
     }
+
+    pub fn get_joint_name(&self, node_index: usize) -> Option<&str> {
+        self.joints.iter()
+            .find(|joint| joint.old_index == node_index)
+            .map(|joint| joint.name.as_ref())
+    }
+
+    /// Returns joint indices ordered so every parent precedes its children,
+    /// visiting the skeleton breadth first.
+    pub fn topological_order(&self) -> Vec<u16> {
+        let mut order = Vec::<u16>::with_capacity(self.joints.len());
+        let mut queue = self.joints.iter().enumerate()
+            .filter(|&(_, joint)| joint.parent == u16::MAX)
+            .map(|(index, _)| index as u16)
+            .collect::<VecDeque<_>>();
+
+        while let Some(index) = queue.pop_front() {
+            order.push(index);
+            for (child, joint) in self.joints.iter().enumerate() {
+                if joint.parent == index {
+                    queue.push_back(child as u16);
+                }
+            }
+        }
+
+        order
+    }
+
+    /// Reorders the joints topologically. Returns the new index of every old
+    /// joint index, for use with `remap_joint_indices`.
+    pub fn sort_topologically(&mut self) -> Vec<u16> {
+        let order = self.topological_order();
+        let mut mapping = vec![u16::MAX; self.joints.len()];
+        for (new_index, &old_index) in order.iter().enumerate() {
+            mapping[old_index as usize] = new_index as u16;
+        }
+
+        let mut joints = self.joints.drain(..).map(Some).collect::<Vec<_>>();
+        self.joints = order.iter()
+            .filter_map(|&old_index| joints[old_index as usize].take())
+            .map(|mut joint| {
+                if joint.parent != u16::MAX {
+                    joint.parent = mapping[joint.parent as usize];
+                }
+                joint
+            })
+            .collect();
+        if let Some(ref mut dual_quats) = self.bind_pose_dual_quats {
+            *dual_quats = order.iter().map(|&old_index| dual_quats[old_index as usize]).collect();
+        }
+        self.update_global_transforms();
+        self.root_index = mapping[self.root_index as usize];
+        for joint_index in self.humanoid.values_mut() {
+            *joint_index = mapping[*joint_index as usize];
+        }
+
+        mapping
+    }
+
+    /// Model space transform of every joint, composing the local transforms
+    /// from the roots down.
+    pub fn global_transforms(&self) -> &[Matrix4<f32>] {
+        &self.global_transforms
+    }
+
+    fn update_global_transforms(&mut self) {
+        let mut globals = self.joints.iter()
+            .map(|joint| joint.local_transform)
+            .collect::<Vec<_>>();
+        for index in self.topological_order() {
+            let joint = &self.joints[index as usize];
+            if joint.parent != u16::MAX {
+                globals[index as usize] = globals[joint.parent as usize] * joint.local_transform;
+            }
+        }
+        self.global_transforms = globals;
+    }
+}
+
+/// Rewrites vertex joint indices using a mapping from `Skin::sort_topologically`.
+pub fn remap_joint_indices<'a>(
+    mapping: &'a [u16],
+    joints: &'a mut Vector4<u16>,
+) {
+    joints.x = mapping[joints.x as usize];
+    joints.y = mapping[joints.y as usize];
+    joints.z = mapping[joints.z as usize];
+    joints.w = mapping[joints.w as usize];
+}
+
+/// Largest difference from the identity tolerated by `check_bind_poses`.
+const BIND_POSE_EPSILON: f32 = 1e-3;
+
+/// Warns about joints whose bind pose transform, relative to the skeleton
+/// root, is not undone by their inverse bind matrix.
+pub fn check_bind_poses<'a>(
+    skins: &'a Skins,
+    warnings: &'a mut Vec<ConvertWarning>,
+) {
+    for skin in skins.skins.iter() {
+        let globals = skin.global_transforms();
+        for (index, joint) in skin.joints.iter().enumerate() {
+            let product = globals[index] * joint.inverse_bind_matrix;
+            let identity = Matrix4::<f32>::identity();
+            let product: &[f32; 16] = product.as_ref();
+            let identity: &[f32; 16] = identity.as_ref();
+
+            if product.iter().zip(identity.iter()).any(|(a, b)| (a - b).abs() > BIND_POSE_EPSILON) {
+                warnings.push(ConvertWarning::BindPoseMismatch {
+                    joint: joint.name.clone(),
+                });
+            }
+        }
+    }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Joint {
     name: String,
     local_transform: Matrix4<f32>,
@@ -61,24 +251,76 @@ pub struct Joint {
 pub fn get<'a>(
     skins: GltfSkins,
     buffers: &'a Buffers,
+    extensions: &'a Extensions,
+    options: &'a Options,
 ) -> Result<Skins> {
     let skins = skins.map(|skin| {
         let name = skin.name().ok_or(ConvertError::NoName)?;
         let root_index = get_root_index(&skin)?;
-        let joints = get_joints(&skin, buffers)?;
+        let mut joints = get_joints(&skin, buffers)?;
+        if options.preserve_connector_nodes {
+            let root = skin.skeleton().ok_or(ConvertError::NoSkeleton)?;
+            add_connector_joints(&root, None, &mut joints)?;
+        }
+        if options.handedness == Handedness::Left {
+            for joint in joints.iter_mut() {
+                joint.local_transform = mirror_z(joint.local_transform);
+                joint.inverse_bind_matrix = mirror_z(joint.inverse_bind_matrix);
+            }
+        }
 
-        Ok(Skin {
+        let mut my_skin = Skin {
             name: String::from(name),
             root_index: root_index,
             joints: joints,
-        })
+            humanoid: HumanoidMap::new(),
+            bind_pose_dual_quats: None,
+            global_transforms: Vec::new(),
+        };
+        my_skin.update_global_transforms();
+        my_skin.humanoid = get_humanoid(extensions, &my_skin);
+        if options.dual_quaternion_skinning {
+            my_skin.bind_pose_dual_quats = Some(get_bind_pose_dual_quats(&my_skin));
+        }
+
+        let mapping = match options.sort_joints {
+            true => Some(my_skin.sort_topologically()),
+            false => None,
+        };
+
+        Ok((my_skin, mapping))
     }).collect::<Result<Vec<_>>>()?;
+    let (skins, joint_mappings): (Vec<_>, Vec<_>) = skins.into_iter().unzip();
+
+    let mut node_joints = HashMap::<usize, Vec<(usize, u16)>>::new();
+    for (skin_index, skin) in skins.iter().enumerate() {
+        for (joint_index, joint) in skin.joints.iter().enumerate() {
+            node_joints.entry(joint.old_index)
+                .or_insert_with(Vec::new)
+                .push((skin_index, joint_index as u16));
+        }
+    }
 
     Ok(Skins {
         skins: skins,
+        node_joints: node_joints,
+        joint_mappings: joint_mappings,
     })
 }
 
+/// Undoes the inverse bind matrix of every joint. Joints whose matrix
+/// cannot be inverted use their rest pose instead.
+fn get_bind_pose_dual_quats<'a>(
+    skin: &'a Skin,
+) -> Vec<DualQuaternion> {
+    let globals = skin.global_transforms();
+    skin.joints.iter().enumerate().map(|(index, joint)| {
+        let bind_pose = joint.inverse_bind_matrix.invert()
+            .unwrap_or_else(|| globals[index]);
+        DualQuaternion::from_matrix(bind_pose)
+    }).collect()
+}
+
 fn get_root_index<'a>(
     skin: &'a GltfSkin,
 ) -> Result<u16> {
@@ -110,8 +352,8 @@ fn get_joints<'a>(
     let transforms = skin.joints().map(|joint| {
         Matrix4::<f32>::from(joint.transform().matrix())
     }).collect::<Vec<_>>();
-    let inverse_bind_matrices = get_inverse_bind_matrices(&skin, buffers);
     let parent_indices = get_parent_indices(skin)?;
+    let inverse_bind_matrices = get_inverse_bind_matrices(&skin, buffers, &transforms, &parent_indices)?;
     let old_indices = skin.joints().map(|joint| joint.index()).collect::<Vec<_>>();
 
     Ok(multizip((names, transforms, inverse_bind_matrices, parent_indices, old_indices))
@@ -126,6 +368,65 @@ fn get_joints<'a>(
         }).collect())
 }
 
+/// Adds the non-joint nodes between a joint and its descendant joints as
+/// passthrough joints, which no vertex is weighted to, so the descendants
+/// keep their whole transform chain. The passthrough joints are appended so
+/// existing joint indices stay valid.
+fn add_connector_joints<'a>(
+    node: &'a Node,
+    parent: Option<u16>,
+    joints: &'a mut Vec<Joint>,
+) -> Result<()> {
+    let joint_index = match joints.iter().position(|joint| joint.old_index == node.index()) {
+        Some(index) => {
+            if let Some(parent) = parent {
+                joints[index].parent = parent;
+            }
+            Some(index as u16)
+        },
+        None => match parent {
+            Some(parent) if has_joint_descendant(node, joints) => {
+                if joints.len() >= u16::MAX as usize {
+                    return Err(Error::Convert(ConvertError::TooManyJoints));
+                }
+
+                // Bind the passthrough where its parent's bind pose puts it.
+                let local_transform = Matrix4::<f32>::from(node.transform().matrix());
+                let parent_bind_pose = joints[parent as usize].inverse_bind_matrix.invert()
+                    .unwrap_or_else(Matrix4::identity);
+                let inverse_bind_matrix = (parent_bind_pose * local_transform).invert()
+                    .unwrap_or_else(Matrix4::identity);
+
+                joints.push(Joint {
+                    name: String::from(node.name().ok_or(ConvertError::NoName)?),
+                    local_transform: local_transform,
+                    inverse_bind_matrix: inverse_bind_matrix,
+                    parent: parent,
+                    old_index: node.index(),
+                });
+                Some((joints.len() - 1) as u16)
+            },
+            _ => { return Ok(()); },
+        },
+    };
+
+    for child in node.children() {
+        add_connector_joints(&child, joint_index, joints)?;
+    }
+
+    Ok(())
+}
+
+fn has_joint_descendant<'a>(
+    node: &'a Node,
+    joints: &'a [Joint],
+) -> bool {
+    node.children().any(|child| {
+        joints.iter().any(|joint| joint.old_index == child.index()) ||
+            has_joint_descendant(&child, joints)
+    })
+}
+
 fn get_joint_names<'a>(
     skin: &'a GltfSkin,
 ) -> Result<Vec<String>> {
@@ -144,10 +445,11 @@ fn get_parent_indices<'a>(
         .zip((0..len).into_iter())
         .collect::<Vec<_>>();
 
-    // Find `joints` indices for all child joints.
+    // Find `joints` indices for all child joints. Joints may also have
+    // children that are not joints (e.g. attached meshes); skip those.
     let mapping2 = skin.joints().map(|joint| {
-        joint.children().map(|child| {
-            mapping1.iter().find(|&&(nidx, _)| nidx == child.index()).unwrap().1
+        joint.children().filter_map(|child| {
+            mapping1.iter().find(|&&(nidx, _)| nidx == child.index()).map(|m| m.1)
         }).collect::<Vec<_>>()
     }).zip((0..len).into_iter()).collect::<Vec<_>>();
 
@@ -176,17 +478,322 @@ fn get_parent_indices<'a>(
 fn get_inverse_bind_matrices<'a>(
     skin: &'a GltfSkin,
     buffers: &'a Buffers,
-) -> Vec<Matrix4<f32>> {
-    match skin.ibms(buffers) {
+    transforms: &'a [Matrix4<f32>],
+    parent_indices: &'a [u16],
+) -> Result<Vec<Matrix4<f32>>> {
+    match skin.ibms(buffers)? {
         Some(inverse_bind_matrices) => {
-            inverse_bind_matrices
+            Ok(inverse_bind_matrices
                 .map(|matrix| {
                     Matrix4::from(matrix)
-                }).collect()
+                }).collect())
         },
+        // Without inverse bind matrices the joints are bound in their
+        // current pose, so invert each joint's transform relative to the
+        // skeleton root.
         None => {
-            skin.joints().map(|_| Matrix4::identity())
-                .collect()
+            Ok((0..transforms.len()).map(|index| {
+                let mut world = transforms[index];
+                let mut parent = parent_indices[index];
+                while parent != u16::MAX {
+                    world = transforms[parent as usize] * world;
+                    parent = parent_indices[parent as usize];
+                }
+
+                world.invert().unwrap_or_else(Matrix4::identity)
+            }).collect())
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_1_SQRT_2;
+    use std::path::Path;
+
+    use cgmath::{InnerSpace, Vector3, Vector4};
+    use gltf::Gltf;
+    use gltf::json::Value;
+
+    use super::*;
+    use super::super::buffer::get as get_buffers;
+    use super::super::humanoid::HumanBone;
+    use super::super::import;
+
+    #[test]
+    fn test_non_joint_child() {
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "nodes": [
+                { "name": "hips", "children": [ 1, 2 ] },
+                { "name": "spine" },
+                { "name": "holster" }
+            ],
+            "skins": [ { "name": "rig", "skeleton": 0, "joints": [ 0, 1 ] } ]
+        }"#;
+        let gltf = Gltf::from_str(json).unwrap().validate_minimally().unwrap();
+        let buffers = get_buffers(Path::new("."), gltf.buffers(), None).unwrap();
+        let skins = get(gltf.skins(), &buffers, &Extensions::new(Value::Null), &Options::default()).unwrap();
+        let joints = &skins.skins[0].joints;
+
+        assert_eq!(joints.len(), 2);
+        assert_eq!(joints[0].parent, u16::MAX);
+        assert_eq!(joints[1].parent, 0);
+        assert_eq!(skins.get_joint_index(2), None);
+    }
+
+    #[test]
+    fn test_connector_nodes() {
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "nodes": [
+                { "name": "hips", "translation": [ 0.0, 1.0, 0.0 ], "children": [ 1, 3 ] },
+                { "name": "connector", "translation": [ 0.0, 2.0, 0.0 ], "rotation": [ 0.0, 0.7071068, 0.0, 0.7071068 ], "children": [ 2 ] },
+                { "name": "spine", "translation": [ 1.0, 0.5, 0.0 ] },
+                { "name": "holster" }
+            ],
+            "skins": [ { "name": "rig", "skeleton": 0, "joints": [ 0, 2 ] } ]
+        }"#;
+        let gltf = Gltf::from_str(json).unwrap().validate_minimally().unwrap();
+        let buffers = get_buffers(Path::new("."), gltf.buffers(), None).unwrap();
+        let node_transform = |index: usize| {
+            Matrix4::<f32>::from(gltf.nodes().nth(index).unwrap().transform().matrix())
+        };
+
+        // By default the spine loses its connection to the hips.
+        let skins = get(gltf.skins(), &buffers, &Extensions::new(Value::Null), &Options::default()).unwrap();
+        assert_eq!(skins.skins[0].joints[1].parent, u16::MAX);
+
+        let mut options = Options::default();
+        options.preserve_connector_nodes = true;
+        let skins = get(gltf.skins(), &buffers, &Extensions::new(Value::Null), &options).unwrap();
+        let skin = &skins.skins[0];
+
+        // Only the connector is added, after the original joints.
+        assert_eq!(skin.joints.len(), 3);
+        assert_eq!(skin.joints[2].name, "connector");
+        assert_eq!(skin.joints[2].parent, 0);
+        assert_eq!(skin.joints[1].parent, 2);
+        assert_eq!(skins.get_joint_index(1), Some(2));
+
+        let expected = node_transform(0) * node_transform(1) * node_transform(2);
+        let spine = skin.global_transforms()[1];
+        for column in 0..4 {
+            assert!((spine[column] - expected[column]).magnitude() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_missing_inverse_bind_matrices() {
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "nodes": [
+                { "name": "hips", "translation": [ 0.0, 1.0, 0.0 ], "children": [ 1 ] },
+                { "name": "spine", "translation": [ 0.0, 2.0, 0.0 ] }
+            ],
+            "skins": [ { "name": "rig", "skeleton": 0, "joints": [ 0, 1 ] } ]
+        }"#;
+        let gltf = Gltf::from_str(json).unwrap().validate_minimally().unwrap();
+        let buffers = get_buffers(Path::new("."), gltf.buffers(), None).unwrap();
+        let skins = get(gltf.skins(), &buffers, &Extensions::new(Value::Null), &Options::default()).unwrap();
+        let joints = skins.skins[0].joints();
+
+        assert_eq!(joints[0].inverse_bind_matrix, Matrix4::from_translation(Vector3::new(0.0, -1.0, 0.0)));
+        assert_eq!(joints[1].inverse_bind_matrix, Matrix4::from_translation(Vector3::new(0.0, -3.0, 0.0)));
+
+        let mut warnings = Vec::new();
+        check_bind_poses(&skins, &mut warnings);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_bind_pose_dual_quats() {
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "nodes": [
+                { "name": "hips", "rotation": [ 0.0, 0.7071068, 0.0, 0.7071068 ], "children": [ 1 ] },
+                { "name": "spine", "translation": [ 0.0, 2.0, 0.0 ] }
+            ],
+            "skins": [ { "name": "rig", "skeleton": 0, "joints": [ 0, 1 ] } ]
+        }"#;
+        let gltf = Gltf::from_str(json).unwrap().validate_minimally().unwrap();
+        let buffers = get_buffers(Path::new("."), gltf.buffers(), None).unwrap();
+        let extensions = Extensions::new(Value::Null);
+
+        let skins = get(gltf.skins(), &buffers, &extensions, &Options::default()).unwrap();
+        assert!(skins.skins[0].bind_pose_dual_quats().is_none());
+
+        let mut options = Options::default();
+        options.dual_quaternion_skinning = true;
+        let skins = get(gltf.skins(), &buffers, &extensions, &options).unwrap();
+        let dual_quats = skins.skins[0].bind_pose_dual_quats().unwrap();
+
+        // The hips only rotate.
+        let hips = dual_quats[0];
+        assert!((hips.real.magnitude() - 1.0).abs() < 1e-5);
+        assert!((hips.real.v.y - FRAC_1_SQRT_2).abs() < 1e-5);
+        assert!(hips.dual.magnitude() < 1e-5);
+
+        // The spine inherits the rotation, which leaves its translation alone.
+        let spine = dual_quats[1];
+        assert!((spine.real.magnitude() - 1.0).abs() < 1e-5);
+        assert!((spine.translation() - Vector3::new(0.0, 2.0, 0.0)).magnitude() < 1e-5);
+    }
+
+    #[test]
+    fn test_check_bind_poses() {
+        let joint = |name: &str, parent: u16, inverse_bind_matrix: Matrix4<f32>| Joint {
+            name: String::from(name),
+            local_transform: Matrix4::from_translation(Vector3::new(0.0, 1.0, 0.0)),
+            inverse_bind_matrix: inverse_bind_matrix,
+            parent: parent,
+            old_index: 0,
+        };
+        let mut skin = Skin {
+            name: String::from("rig"),
+            root_index: 0,
+            joints: vec![
+                joint("hips", u16::MAX, Matrix4::from_translation(Vector3::new(0.0, -1.0, 0.0))),
+                joint("spine", 0, Matrix4::from_translation(Vector3::new(0.0, -2.0, 0.0))),
+                joint("head", 1, Matrix4::from_translation(Vector3::new(0.0, -2.0, 0.0))),
+            ],
+            humanoid: HumanoidMap::new(),
+            bind_pose_dual_quats: None,
+            global_transforms: Vec::new(),
+        };
+        skin.update_global_transforms();
+        let skins = Skins {
+            skins: vec![skin],
+            node_joints: HashMap::new(),
+            joint_mappings: vec![None],
+        };
+        let mut warnings = Vec::new();
+
+        check_bind_poses(&skins, &mut warnings);
+        assert_eq!(warnings, vec![ConvertWarning::BindPoseMismatch {
+            joint: String::from("head"),
+        }]);
+    }
+
+    #[test]
+    fn test_topological_order() {
+        let joint = |name: &str, parent: u16| Joint {
+            name: String::from(name),
+            local_transform: Matrix4::identity(),
+            inverse_bind_matrix: Matrix4::identity(),
+            parent: parent,
+            old_index: 0,
+        };
+        let mut skin = Skin {
+            name: String::from("rig"),
+            root_index: 2,
+            joints: vec![
+                joint("head", 3),
+                joint("hand", 4),
+                joint("hips", u16::MAX),
+                joint("spine", 2),
+                joint("arm", 3),
+            ],
+            humanoid: HumanoidMap::new(),
+            bind_pose_dual_quats: None,
+            global_transforms: Vec::new(),
+        };
+
+        let order = skin.topological_order();
+        for (position, &index) in order.iter().enumerate() {
+            let parent = skin.joints[index as usize].parent;
+            if parent != u16::MAX {
+                assert!(order[..position].contains(&parent));
+            }
+        }
+
+        let mut vertex_joints = Vector4::new(0, 1, 2, 4);
+        let names = |skin: &Skin, joints: Vector4<u16>| {
+            [joints.x, joints.y, joints.z, joints.w].iter()
+                .map(|&index| skin.joints[index as usize].name.clone())
+                .collect::<Vec<_>>()
+        };
+        let before = names(&skin, vertex_joints);
+
+        let mapping = skin.sort_topologically();
+        remap_joint_indices(&mapping, &mut vertex_joints);
+        assert_eq!(names(&skin, vertex_joints), before);
+        assert_eq!(skin.root_index, 0);
+        for (index, joint) in skin.joints.iter().enumerate() {
+            assert!(joint.parent == u16::MAX || (joint.parent as usize) < index);
+        }
+    }
+
+    #[test]
+    fn test_global_transforms() {
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "nodes": [
+                { "name": "hips", "translation": [ 0.0, 1.0, 0.0 ], "rotation": [ 0.0, 0.7071068, 0.0, 0.7071068 ], "children": [ 1 ] },
+                { "name": "spine", "translation": [ 0.0, 2.0, 0.0 ], "children": [ 2 ] },
+                { "name": "head", "translation": [ 1.0, 0.5, 0.0 ] }
+            ],
+            "skins": [ { "name": "rig", "skeleton": 0, "joints": [ 2, 0, 1 ] } ]
+        }"#;
+        let gltf = Gltf::from_str(json).unwrap().validate_minimally().unwrap();
+        let buffers = get_buffers(Path::new("."), gltf.buffers(), None).unwrap();
+        let skins = get(gltf.skins(), &buffers, &Extensions::new(Value::Null), &Options::default()).unwrap();
+        let skin = &skins.skins[0];
+        let globals = skin.global_transforms();
+
+        // The head is listed before its parents.
+        assert_eq!(globals[1], skin.joints[1].local_transform);
+        for (index, joint) in skin.joints.iter().enumerate() {
+            if joint.parent != u16::MAX {
+                assert_eq!(globals[index], globals[joint.parent as usize] * joint.local_transform);
+            }
+        }
+
+        // Sorting keeps the transforms in step with the joints.
+        let mut sorted = skin.clone();
+        let mapping = sorted.sort_topologically();
+        for (old_index, &new_index) in mapping.iter().enumerate() {
+            assert_eq!(sorted.global_transforms()[new_index as usize], globals[old_index]);
+        }
+    }
+
+    #[test]
+    fn test_humanoid() {
+        let path = Path::new("testmodels/gltf2/Humanoid/Humanoid.gltf");
+        let (gltf, extensions, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let skins = get(gltf.skins(), &buffers, &extensions, &Options::default()).unwrap();
+        let humanoid = skins.skins[0].humanoid();
+
+        assert_eq!(humanoid.get(&HumanBone::Hips), Some(&1));
+        assert_eq!(humanoid.get(&HumanBone::Head), Some(&2));
+        // Bones whose nodes are not joints of the skin are left out.
+        assert_eq!(humanoid.get(&HumanBone::Spine), None);
+        assert_eq!(humanoid.get(&HumanBone::LeftHand), None);
+    }
+
+    #[test]
+    fn test_left_handed() {
+        let path = Path::new("testmodels/gltf2/LeftHanded/LeftHanded.gltf");
+        let (gltf, extensions, buffers) = import(path, path.parent().unwrap()).unwrap();
+        let right = get(gltf.skins(), &buffers, &extensions, &Options::default()).unwrap();
+        let mut options = Options::default();
+        options.handedness = Handedness::Left;
+        let left = get(gltf.skins(), &buffers, &extensions, &options).unwrap();
+        let (right, left) = (&right.skins[0], &left.skins[0]);
+
+        // Bind and posed transforms are both mirrored, so they still agree.
+        for index in 0..2 {
+            let pairs = [
+                (right.joints[index].inverse_bind_matrix, left.joints[index].inverse_bind_matrix),
+                (right.global_transforms()[index], left.global_transforms()[index]),
+            ];
+            for &(right, left) in pairs.iter() {
+                let expected = mirror_z(right);
+                for column in 0..4 {
+                    assert!((left[column] - expected[column]).magnitude() < 1e-5);
+                }
+            }
+        }
+        assert_eq!(left.joints[0].inverse_bind_matrix[3], Vector4::new(0.0, -1.0, 2.0, 1.0));
+    }
+}
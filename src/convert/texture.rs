@@ -1,14 +1,28 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{Cursor, Read};
 use std::path::Path;
 
-use gltf::image::Data as GltfData;
+use byteorder::{LE, ByteOrder};
+use gltf::image::{Data as GltfData, Image as GltfImage};
 use gltf::gltf::Textures as GltfTextures;
-use gltf::texture::{MinFilter as GltfMinFilter, MagFilter as GltfMagFilter, WrappingMode as GltfWrappingMode};
-use gltf_importer::Buffers;
-use image::{GenericImage, DynamicImage, load_from_memory as load_image_from_memory, open as open_image};
+use gltf::texture::{Texture as GltfTexture, MinFilter as GltfMinFilter, MagFilter as GltfMagFilter, WrappingMode as GltfWrappingMode};
+use image::{GenericImage, DynamicImage, ImageBuffer, ImageDecoder, ImageError, ImageFormat, FilterType, Luma, LumaA, Pixel, Rgb, Rgba, guess_format, load_from_memory as load_image_from_memory, open as open_image};
+use image::imageops::resize;
+use image::jpeg::JPEGDecoder;
+use image::png::PNGDecoder;
+use png::{BitDepth, ColorType as PngColorType, Decoder as PngDecoder, HasParameters, Transformations};
 
-use super::super::Result;
-use super::ConvertError;
+use super::super::{Result, Error};
+use super::{ConvertError, ConvertWarning};
+use super::buffer::Buffers;
+use super::ktx2::{get as get_ktx2, is_ktx2};
+use super::options::{ChannelOrder, NamePolicy, Options, PlaceholderTexture, PowerOfTwo};
+use super::util::{decode_data_uri, resolve_uri};
 
+#[derive(Default)]
 pub struct Textures {
     textures: Vec<Texture>,
 }
@@ -20,28 +34,228 @@ impl Textures {
             None => None,
         }
     }
+
+    pub fn textures(&self) -> &[Texture] {
+        &self.textures
+    }
+
+    pub fn into_textures(self) -> Vec<Texture> {
+        self.textures
+    }
+
+    pub fn by_name_mut(&mut self, name: &str) -> Option<&mut Texture> {
+        self.textures.iter_mut().find(|texture| texture.name == name)
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Texture {
     name: String,
     mag_filter: MagFilter,
     min_filter: MinFilter,
     wrap_s_mode: WrappingMode,
     wrap_t_mode: WrappingMode,
+    /// Maximum anisotropic filtering level, from `Options::default_anisotropy`.
+    anisotropy: Option<u8>,
     width: u32,
     height: u32,
+    /// Dimensions of the source image, before `Options::max_texture_size`
+    /// and `Options::force_power_of_two`.
+    original_dimensions: (u32, u32),
+    /// Factor to scale UVs by so they still cover the original image.
+    uv_scale: [f32; 2],
+    channel_order: ChannelOrder,
     format: Format,
+    /// Bytes from the start of one row of pixels, or of blocks, to the next.
+    row_stride: usize,
+    contents: Vec<u8>,
+    mip_levels: Vec<MipLevel>,
+}
+
+impl Texture {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn anisotropy(&self) -> Option<u8> {
+        self.anisotropy
+    }
+
+    pub fn original_dimensions(&self) -> (u32, u32) {
+        self.original_dimensions
+    }
+
+    pub fn uv_scale(&self) -> [f32; 2] {
+        self.uv_scale
+    }
+
+    /// Order of the channels in `contents`, for RGB and RGBA formats.
+    pub fn channel_order(&self) -> ChannelOrder {
+        self.channel_order
+    }
+
+    /// Inverts the green channel, converting a normal map between the OpenGL
+    /// and DirectX conventions. Block compressed, encoded and gray textures
+    /// are left alone. Returns whether the channel was inverted.
+    pub fn flip_green(&mut self) -> bool {
+        match self.format {
+            Format::RgbImage | Format::RgbaImage | Format::Rgb16Image | Format::Rgba16Image => {},
+            _ => { return false; },
+        }
+
+        let size = self.format.channel_size();
+        let green = self.channel_order.position(1, self.format.block_size() / size) * size;
+        for texel in self.texels_mut() {
+            // Inverting every bit subtracts the channel from its maximum.
+            for byte in texel[green..green + size].iter_mut() {
+                *byte = !*byte;
+            }
+        }
+
+        true
+    }
+
+    /// Applies an occlusion strength to the red channel, so the texture can
+    /// be sampled with a strength of 1. Block compressed and encoded
+    /// textures are left alone. Returns whether the strength was applied.
+    pub fn bake_occlusion_strength(&mut self, strength: f32) -> bool {
+        if self.format.block_dimensions() != (1, 1) {
+            return false;
+        }
+
+        let size = self.format.channel_size();
+        let red = self.channel_order.position(0, self.format.block_size() / size) * size;
+        for texel in self.texels_mut() {
+            match size {
+                2 => {
+                    let sampled = LE::read_u16(&texel[red..]) as f32 / 65535.0;
+                    LE::write_u16(&mut texel[red..], (apply_occlusion_strength(sampled, strength) * 65535.0).round() as u16);
+                },
+                _ => {
+                    let sampled = texel[red] as f32 / 255.0;
+                    texel[red] = (apply_occlusion_strength(sampled, strength) * 255.0).round() as u8;
+                },
+            }
+        }
+
+        true
+    }
+
+    /// Every texel of an uncompressed texture, skipping row padding.
+    fn texels_mut(&mut self) -> Vec<&mut [u8]> {
+        let texel_size = self.format.block_size();
+        let row_size = self.width as usize * texel_size;
+        if row_size == 0 {
+            return Vec::new();
+        }
+        self.contents.chunks_mut(self.row_stride)
+            .flat_map(|row| row.split_at_mut(row_size).0.chunks_mut(texel_size))
+            .collect()
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Bytes between the starts of consecutive rows of `contents`. Rows of
+    /// block compressed textures are rows of blocks.
+    pub fn row_stride(&self) -> usize {
+        self.row_stride
+    }
+
+    /// Decoded pixels row by row, or compressed blocks for block
+    /// compressed formats. Each row is padded to `row_stride` bytes.
+    /// Encoded images are the source file unchanged, with a `row_stride`
+    /// of 0.
+    pub fn contents(&self) -> &[u8] {
+        &self.contents
+    }
+
+    /// Mip levels below the base level, largest first, as authored in the
+    /// source image. Empty unless a KTX2 texture provides a mip chain.
+    pub fn mip_levels(&self) -> &[MipLevel] {
+        &self.mip_levels
+    }
+
+    /// Renames the texture, for textures pooled with others of the same name.
+    pub fn rename(&mut self, name: String) {
+        self.name = name;
+    }
+
+    /// Hash of the dimensions and contents, to find textures identical but
+    /// for their name. Equal hashes still need `same_contents` to confirm.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        (self.width, self.height).hash(&mut hasher);
+        self.contents.hash(&mut hasher);
+        for level in self.mip_levels.iter() {
+            level.contents.hash(&mut hasher);
+        }
+
+        hasher.finish()
+    }
+
+    /// Whether the textures are equal but for their names.
+    pub fn same_contents(&self, other: &Texture) -> bool {
+        self.mag_filter == other.mag_filter &&
+            self.min_filter == other.min_filter &&
+            self.wrap_s_mode == other.wrap_s_mode &&
+            self.wrap_t_mode == other.wrap_t_mode &&
+            self.anisotropy == other.anisotropy &&
+            self.width == other.width &&
+            self.height == other.height &&
+            self.original_dimensions == other.original_dimensions &&
+            self.uv_scale == other.uv_scale &&
+            self.channel_order == other.channel_order &&
+            self.format == other.format &&
+            self.row_stride == other.row_stride &&
+            self.contents == other.contents &&
+            self.mip_levels == other.mip_levels
+    }
+}
+
+/// One level of a texture's mip chain, stored like `Texture::contents`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MipLevel {
+    width: u32,
+    height: u32,
+    row_stride: usize,
     contents: Vec<u8>,
 }
 
-#[derive(Clone, Copy, Debug)]
+impl MipLevel {
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn row_stride(&self) -> usize {
+        self.row_stride
+    }
+
+    pub fn contents(&self) -> &[u8] {
+        &self.contents
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum MagFilter {
     Nearest,
     Linear,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum MinFilter {
     Nearest,
     Linear,
@@ -51,28 +265,139 @@ pub enum MinFilter {
     LinearMipmapLinear,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum WrappingMode {
     ClampToEdge,
     MirroredRepeat,
     Repeat,
 }
 
-#[derive(Clone, Copy, Debug)]
+impl WrappingMode {
+    /// Applies the wrapping to a texture coordinate, for consumers without
+    /// hardware repeat or mirroring. Clamped coordinates are unchanged.
+    pub fn bake(self, coord: f32) -> f32 {
+        match self {
+            WrappingMode::ClampToEdge => coord,
+            WrappingMode::Repeat => coord - coord.floor(),
+            WrappingMode::MirroredRepeat => {
+                let period = coord - (coord / 2.0).floor() * 2.0;
+                if period > 1.0 { 2.0 - period } else { period }
+            },
+        }
+    }
+}
+
+impl From<GltfWrappingMode> for WrappingMode {
+    fn from(mode: GltfWrappingMode) -> WrappingMode {
+        match mode {
+            GltfWrappingMode::ClampToEdge => WrappingMode::ClampToEdge,
+            GltfWrappingMode::MirroredRepeat => WrappingMode::MirroredRepeat,
+            GltfWrappingMode::Repeat => WrappingMode::Repeat,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Format {
     GrayImage,
     GrayAlphaImage,
     RgbImage,
     RgbaImage,
+    /// 16 bit formats, with little endian channels. Only PNG images are
+    /// decoded to these.
+    Gray16Image,
+    GrayAlpha16Image,
+    Rgb16Image,
+    Rgba16Image,
+    /// Block compressed formats, preserved from KTX2 textures.
+    Bc1,
+    Bc2,
+    Bc3,
+    Bc4,
+    Bc5,
+    Bc6,
+    Bc7,
+    /// A PNG or JPEG file, from `Options::keep_encoded_images`.
+    Encoded {
+        mime: ImageMime,
+    },
+}
+
+impl Format {
+    /// Width and height in pixels of the blocks the contents are stored in.
+    /// Encoded images have no blocks, so both are 0.
+    pub fn block_dimensions(self) -> (u32, u32) {
+        match self {
+            Format::GrayImage |
+            Format::GrayAlphaImage |
+            Format::RgbImage |
+            Format::RgbaImage |
+            Format::Gray16Image |
+            Format::GrayAlpha16Image |
+            Format::Rgb16Image |
+            Format::Rgba16Image => (1, 1),
+            Format::Encoded { .. } => (0, 0),
+            _ => (4, 4),
+        }
+    }
+
+    /// Size in bytes of one block, 0 for encoded images.
+    pub fn block_size(self) -> usize {
+        match self {
+            Format::GrayImage => 1,
+            Format::GrayAlphaImage => 2,
+            Format::RgbImage => 3,
+            Format::RgbaImage => 4,
+            Format::Gray16Image => 2,
+            Format::GrayAlpha16Image => 4,
+            Format::Rgb16Image => 6,
+            Format::Rgba16Image => 8,
+            Format::Bc1 | Format::Bc4 => 8,
+            Format::Bc2 | Format::Bc3 | Format::Bc5 | Format::Bc6 | Format::Bc7 => 16,
+            Format::Encoded { .. } => 0,
+        }
+    }
+
+    /// Size in bytes of one channel of an uncompressed format.
+    pub fn channel_size(self) -> usize {
+        match self {
+            Format::Gray16Image |
+            Format::GrayAlpha16Image |
+            Format::Rgb16Image |
+            Format::Rgba16Image => 2,
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ImageMime {
+    Png,
+    Jpeg,
+}
+
+impl ImageMime {
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            ImageMime::Png => "image/png",
+            ImageMime::Jpeg => "image/jpeg",
+        }
+    }
 }
 
 pub fn get<'a>(
     base_path: &'a Path,
     textures: GltfTextures,
-    buffers: &'a Buffers
+    buffers: &'a Buffers,
+    options: &'a Options,
+    warnings: &'a mut Vec<ConvertWarning>,
 ) -> Result<Textures> {
+    let mut cache = ImageCache {
+        keep_encoded: options.keep_encoded_images,
+        ..ImageCache::default()
+    };
     let my_textures = textures.map(|texture| {
-        let name = texture.name().ok_or(ConvertError::NoName)?;
+        let name = get_name(&texture, options)?;
         let sampler = texture.sampler();
         let mag_filter = match sampler.mag_filter() {
             Some(GltfMagFilter::Linear) => MagFilter::Linear,
@@ -86,31 +411,210 @@ pub fn get<'a>(
             Some(GltfMinFilter::NearestMipmapNearest) => MinFilter::NearestMipmapNearest,
             Some(GltfMinFilter::LinearMipmapLinear) => MinFilter::LinearMipmapLinear,
             Some(GltfMinFilter::NearestMipmapLinear) => MinFilter::NearestMipmapLinear,
+            None if options.generate_mipmaps => MinFilter::LinearMipmapLinear,
             None => MinFilter::Nearest,
         };
-        let wrap_s = match sampler.wrap_s() {
-            GltfWrappingMode::ClampToEdge => WrappingMode::ClampToEdge,
-            GltfWrappingMode::MirroredRepeat => WrappingMode::MirroredRepeat,
-            GltfWrappingMode::Repeat => WrappingMode::Repeat,
-        };
-        let wrap_t = match sampler.wrap_t() {
-            GltfWrappingMode::ClampToEdge => WrappingMode::ClampToEdge,
-            GltfWrappingMode::MirroredRepeat => WrappingMode::MirroredRepeat,
-            GltfWrappingMode::Repeat => WrappingMode::Repeat,
-        };
+        let wrap_s = WrappingMode::from(sampler.wrap_s());
+        let wrap_t = WrappingMode::from(sampler.wrap_t());
 
-        // Get contents of image as either byte array or `image::DynamicImage`.
-        let img = match texture.source().data() {
-            GltfData::View { view, .. } => {
-                let contents = buffers.view(&view).ok_or(ConvertError::MissingImageBuffer)?;
-                load_image_from_memory(contents)?
+        let placeholder;
+        let img = match (cache.load(base_path, &texture.source(), buffers), options.placeholder_texture) {
+            (Ok(img), _) => img,
+            (Err(_), Some(kind)) => {
+                warnings.push(ConvertWarning::PlaceholderTexture { texture: name.clone() });
+                placeholder = ImageContents::placeholder(kind);
+                &placeholder
+            },
+            (Err(err), None) => { return Err(err); },
+        };
+        let original_dimensions = (img.width, img.height);
+        let downscaled;
+        let img = match options.max_texture_size {
+            Some(max) if img.width > max || img.height > max => {
+                let (width, height) = fit_within(img.width, img.height, max);
+                match img.resize(width, height, FilterType::Lanczos3) {
+                    Some(resized) => {
+                        downscaled = resized;
+                        &downscaled
+                    },
+                    None => {
+                        warnings.push(ConvertWarning::OversizedTexture { texture: name.clone() });
+                        img
+                    },
+                }
             },
-            GltfData::Uri{ uri, .. } => {
-                let full_path = base_path.to_path_buf().join(uri);
-                open_image(full_path)?
+            _ => img,
+        };
+        let (width, height, contents, uv_scale) = match options.force_power_of_two {
+            Some(mode) if img.format.block_dimensions() == (1, 1) => {
+                let (width, height, contents) = to_power_of_two(img, mode)?;
+                let uv_scale = match mode {
+                    PowerOfTwo::Pad => [
+                        img.width as f32 / width as f32,
+                        img.height as f32 / height as f32,
+                    ],
+                    PowerOfTwo::Resize => [1.0, 1.0],
+                };
+                (width, height, contents, uv_scale)
             },
+            _ => (img.width, img.height, img.contents.clone(), [1.0, 1.0]),
         };
+        let contents = match img.format {
+            Format::RgbImage |
+            Format::RgbaImage |
+            Format::Rgb16Image |
+            Format::Rgba16Image => reorder_channels(contents, img.format, options.channel_order),
+            _ => contents,
+        };
+        let (row_stride, contents) = match img.format {
+            Format::Encoded { .. } => (0, contents),
+            _ => pad_rows(contents, width, img.format, options.row_alignment),
+        };
+        let mip_levels = img.mip_levels.iter().enumerate().map(|(index, contents)| {
+            let level = index as u32 + 1;
+            let width = (img.width >> level).max(1);
+            let (row_stride, contents) = pad_rows(contents.clone(), width, img.format, options.row_alignment);
+
+            MipLevel {
+                width: width,
+                height: (img.height >> level).max(1),
+                row_stride: row_stride,
+                contents: contents,
+            }
+        }).collect();
+
+        Ok(Texture {
+            name: name,
+            mag_filter: mag_filter,
+            min_filter: min_filter,
+            wrap_s_mode: wrap_s,
+            wrap_t_mode: wrap_t,
+            anisotropy: options.default_anisotropy,
+            width: width,
+            height: height,
+            original_dimensions: original_dimensions,
+            uv_scale: uv_scale,
+            channel_order: options.channel_order,
+            format: img.format,
+            row_stride: row_stride,
+            contents: contents,
+            mip_levels: mip_levels,
+        })
+    }).collect::<Result<Vec<_>>>()?;
+
+    Ok(Textures {
+        textures: my_textures,
+    })
+}
+
+/// Pads each row of `contents` with zeros to a multiple of `alignment`
+/// bytes. Returns the row stride and the padded contents.
+fn pad_rows(contents: Vec<u8>, width: u32, format: Format, alignment: Option<usize>) -> (usize, Vec<u8>) {
+    let (block_width, _) = format.block_dimensions();
+    let row_size = ((width + block_width - 1) / block_width) as usize * format.block_size();
+    let row_stride = match alignment {
+        Some(alignment) if alignment > 0 => (row_size + alignment - 1) / alignment * alignment,
+        _ => row_size,
+    };
+    if row_stride == row_size || row_size == 0 {
+        return (row_size, contents);
+    }
+
+    let mut padded = Vec::<u8>::with_capacity(contents.len() / row_size * row_stride);
+    for row in contents.chunks(row_size) {
+        padded.extend_from_slice(row);
+        padded.resize(padded.len() + row_stride - row.len(), 0);
+    }
+
+    (row_stride, padded)
+}
+
+/// Moves the channels of RGB or RGBA `contents` into `order`.
+fn reorder_channels(mut contents: Vec<u8>, format: Format, order: ChannelOrder) -> Vec<u8> {
+    let size = format.channel_size();
+    let channels = format.block_size() / size;
+    let mut texel_copy = [0u8; 8];
+    for texel in contents.chunks_mut(format.block_size()) {
+        texel_copy[..texel.len()].copy_from_slice(texel);
+        for channel in 0..channels {
+            let position = order.position(channel, channels) * size;
+            texel[position..position + size].copy_from_slice(&texel_copy[channel * size..(channel + 1) * size]);
+        }
+    }
+
+    contents
+}
+
+/// Occlusion after applying `strength` to a `sampled` value, as glTF
+/// defines it: `1.0 + strength * (sampled - 1.0)`.
+pub fn apply_occlusion_strength(sampled: f32, strength: f32) -> f32 {
+    1.0 + strength * (sampled - 1.0)
+}
+
+/// Largest dimensions with the aspect ratio of `width` by `height` that fit
+/// in `max` by `max`.
+fn fit_within(width: u32, height: u32, max: u32) -> (u32, u32) {
+    let scale = max as f32 / width.max(height) as f32;
+
+    (((width as f32 * scale) as u32).max(1), ((height as f32 * scale) as u32).max(1))
+}
+
+/// Enlarges uncompressed image contents to the next power of two in both
+/// dimensions. Returns the new width, height and contents.
+fn to_power_of_two<'a>(
+    img: &'a ImageContents,
+    mode: PowerOfTwo,
+) -> Result<(u32, u32, Vec<u8>)> {
+    let width = img.width.next_power_of_two();
+    let height = img.height.next_power_of_two();
+    if (width, height) == (img.width, img.height) {
+        return Ok((width, height, img.contents.clone()));
+    }
+
+    match mode {
+        PowerOfTwo::Pad => {
+            let texel_size = img.format.block_size();
+            let row_size = img.width as usize * texel_size;
+            let mut contents = vec![0; width as usize * height as usize * texel_size];
+            for (y, row) in img.contents.chunks(row_size).enumerate() {
+                let start = y * width as usize * texel_size;
+                contents[start .. start + row_size].copy_from_slice(row);
+            }
 
+            Ok((width, height, contents))
+        },
+        PowerOfTwo::Resize => {
+            let resized = img.resize(width, height, FilterType::Triangle).ok_or(ConvertError::Other)?;
+
+            Ok((width, height, resized.contents))
+        },
+    }
+}
+
+/// Where the encoded data of an image is stored. Views covering the same
+/// bytes share one decoded image.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum ImageKey {
+    View {
+        buffer: usize,
+        offset: usize,
+        length: usize,
+    },
+    Uri(String),
+}
+
+/// Contents of an image, ready to be copied into a `Texture`.
+struct ImageContents {
+    width: u32,
+    height: u32,
+    format: Format,
+    contents: Vec<u8>,
+    /// Contents of the mip levels below the base level, largest first.
+    mip_levels: Vec<Vec<u8>>,
+}
+
+impl ImageContents {
+    fn from_image(img: DynamicImage) -> ImageContents {
         let format = match &img {
             &DynamicImage::ImageLuma8(_) => Format::GrayImage,
             &DynamicImage::ImageLumaA8(_) => Format::GrayAlphaImage,
@@ -118,28 +622,594 @@ pub fn get<'a>(
             &DynamicImage::ImageRgba8(_) => Format::RgbaImage,
         };
 
-        Ok(Texture {
-            name: String::from(name),
-            mag_filter: mag_filter,
-            min_filter: min_filter,
-            wrap_s_mode: wrap_s,
-            wrap_t_mode: wrap_t,
+        ImageContents {
             width: img.width(),
             height: img.height(),
             format: format,
             contents: img.raw_pixels(),
+            mip_levels: Vec::new(),
+        }
+    }
+
+    /// The contents as an image that can be resized. `None` for formats
+    /// `DynamicImage` has no variant for.
+    fn to_image(&self) -> Option<DynamicImage> {
+        let raw = self.contents.clone();
+        match self.format {
+            Format::GrayImage => ImageBuffer::from_raw(self.width, self.height, raw).map(DynamicImage::ImageLuma8),
+            Format::GrayAlphaImage => ImageBuffer::from_raw(self.width, self.height, raw).map(DynamicImage::ImageLumaA8),
+            Format::RgbImage => ImageBuffer::from_raw(self.width, self.height, raw).map(DynamicImage::ImageRgb8),
+            Format::RgbaImage => ImageBuffer::from_raw(self.width, self.height, raw).map(DynamicImage::ImageRgba8),
+            _ => None,
+        }
+    }
+
+    /// The contents resampled to exactly `width` by `height`, without mip
+    /// levels. `None` for block compressed and encoded formats.
+    fn resize(&self, width: u32, height: u32, filter: FilterType) -> Option<ImageContents> {
+        match self.format {
+            Format::Gray16Image => self.resize16::<Luma<u16>>(width, height, filter),
+            Format::GrayAlpha16Image => self.resize16::<LumaA<u16>>(width, height, filter),
+            Format::Rgb16Image => self.resize16::<Rgb<u16>>(width, height, filter),
+            Format::Rgba16Image => self.resize16::<Rgba<u16>>(width, height, filter),
+            _ => self.to_image().map(|dynamic| {
+                ImageContents::from_image(dynamic.resize_exact(width, height, filter))
+            }),
+        }
+    }
+
+    /// Resamples 16 bit contents, which `DynamicImage` has no variant for,
+    /// with pixels of type `P`.
+    fn resize16<P: Pixel<Subpixel = u16> + 'static>(
+        &self,
+        width: u32,
+        height: u32,
+        filter: FilterType,
+    ) -> Option<ImageContents> {
+        let mut samples = vec![0; self.contents.len() / 2];
+        LE::read_u16_into(&self.contents[..samples.len() * 2], &mut samples);
+        let buffer = ImageBuffer::<P, Vec<u16>>::from_raw(self.width, self.height, samples)?;
+        let samples = resize(&buffer, width, height, filter).into_raw();
+        let mut contents = vec![0; samples.len() * 2];
+        LE::write_u16_into(&samples, &mut contents);
+
+        Some(ImageContents {
+            width: width,
+            height: height,
+            format: self.format,
+            contents: contents,
+            mip_levels: Vec::new(),
         })
-    }).collect::<Result<Vec<_>>>()?;
+    }
 
-    Ok(Textures {
-        textures: my_textures,
-    })
+    /// An 8x8 RGBA stand-in for an image that could not be loaded.
+    fn placeholder(kind: PlaceholderTexture) -> ImageContents {
+        let size = 8;
+        let mut contents = Vec::<u8>::with_capacity(size * size * 4);
+        for y in 0..size {
+            for x in 0..size {
+                let texel = match kind {
+                    PlaceholderTexture::Solid(color) => color,
+                    PlaceholderTexture::Checkerboard(color) if (x + y) % 2 == 0 => color,
+                    PlaceholderTexture::Checkerboard(_) => [0, 0, 0, 255],
+                };
+                contents.extend_from_slice(&texel);
+            }
+        }
+
+        ImageContents {
+            width: size as u32,
+            height: size as u32,
+            format: Format::RgbaImage,
+            contents: contents,
+            mip_levels: Vec::new(),
+        }
+    }
+
+    /// Keeps PNG and JPEG `data` encoded, reading only the dimensions.
+    /// Returns `None` for other formats, which are decoded as usual.
+    fn from_encoded(data: Vec<u8>) -> Result<Option<ImageContents>> {
+        let (mime, (width, height)) = match guess_format(&data) {
+            Ok(ImageFormat::PNG) => (ImageMime::Png, PNGDecoder::new(Cursor::new(&data)).dimensions()?),
+            Ok(ImageFormat::JPEG) => (ImageMime::Jpeg, JPEGDecoder::new(Cursor::new(&data)).dimensions()?),
+            _ => { return Ok(None); },
+        };
+
+        Ok(Some(ImageContents {
+            width: width,
+            height: height,
+            format: Format::Encoded { mime: mime },
+            contents: data,
+            mip_levels: Vec::new(),
+        }))
+    }
+
+    /// Decodes 16 bit PNG `data`, which `image` cannot turn into a
+    /// `DynamicImage`. Returns `None` for other images.
+    fn from_png16(data: &[u8]) -> Result<Option<ImageContents>> {
+        if guess_format(data).ok() != Some(ImageFormat::PNG) {
+            return Ok(None);
+        }
+
+        // `image` strips 16 bit samples to 8 bits, so use `png` directly.
+        let mut decoder = PngDecoder::new(data);
+        decoder.set(Transformations::IDENTITY);
+        let (info, mut reader) = decoder.read_info().map_err(ImageError::from)?;
+        let format = match (info.color_type, info.bit_depth) {
+            (PngColorType::Grayscale, BitDepth::Sixteen) => Format::Gray16Image,
+            (PngColorType::GrayscaleAlpha, BitDepth::Sixteen) => Format::GrayAlpha16Image,
+            (PngColorType::RGB, BitDepth::Sixteen) => Format::Rgb16Image,
+            (PngColorType::RGBA, BitDepth::Sixteen) => Format::Rgba16Image,
+            _ => { return Ok(None); },
+        };
+        let mut contents = vec![0; info.buffer_size()];
+        reader.next_frame(&mut contents).map_err(ImageError::from)?;
+        // PNG stores samples big endian.
+        for sample in contents.chunks_mut(2) {
+            sample.swap(0, 1);
+        }
+
+        Ok(Some(ImageContents {
+            width: info.width,
+            height: info.height,
+            format: format,
+            contents: contents,
+            mip_levels: Vec::new(),
+        }))
+    }
+
+    /// Decodes `data`, keeping KTX2 textures block compressed.
+    fn from_memory(data: &[u8]) -> Result<ImageContents> {
+        if is_ktx2(data) {
+            let ktx2 = get_ktx2(data)?;
+
+            Ok(ImageContents {
+                width: ktx2.width,
+                height: ktx2.height,
+                format: ktx2.format,
+                contents: ktx2.contents,
+                mip_levels: ktx2.mip_levels,
+            })
+        } else if let Some(img) = ImageContents::from_png16(data)? {
+            Ok(img)
+        } else {
+            Ok(ImageContents::from_image(load_image_from_memory(data)?))
+        }
+    }
+}
+
+/// Decoded images. Buffer views over the same range of a buffer (e.g.
+/// several textures sharing an atlas) are only decoded once.
+#[derive(Default)]
+struct ImageCache {
+    images: HashMap<ImageKey, ImageContents>,
+    /// Whether PNG and JPEG images are kept encoded.
+    keep_encoded: bool,
+}
+
+impl ImageCache {
+    fn load<'a, 'b>(
+        &'a mut self,
+        base_path: &'b Path,
+        image: &'b GltfImage,
+        buffers: &'b Buffers,
+    ) -> Result<&'a ImageContents> {
+        let key = match image.data() {
+            GltfData::View { ref view, .. } => ImageKey::View {
+                buffer: view.buffer().index(),
+                offset: view.offset(),
+                length: view.length(),
+            },
+            GltfData::Uri { uri, .. } => ImageKey::Uri(String::from(uri)),
+        };
+
+        if !self.images.contains_key(&key) {
+            let encoded = match self.keep_encoded {
+                true => ImageContents::from_encoded(read_encoded(base_path, image, buffers)?)?,
+                false => None,
+            };
+            let img = match (encoded, image.data()) {
+                (Some(img), _) => img,
+                (None, GltfData::View { view, .. }) => {
+                    let contents = buffers.view(&view).ok_or(ConvertError::MissingImageBuffer)?;
+                    ImageContents::from_memory(contents)?
+                },
+                (None, GltfData::Uri { uri, .. }) if uri.starts_with("data:") => {
+                    ImageContents::from_memory(&decode_data_uri(uri)?.1)?
+                },
+                (None, GltfData::Uri { uri, .. }) if uri.ends_with(".ktx2") => {
+                    let mut data = Vec::<u8>::new();
+                    File::open(resolve_uri(base_path, uri)?)?.read_to_end(&mut data)?;
+                    ImageContents::from_memory(&data)?
+                },
+                (None, GltfData::Uri { .. }) => {
+                    ImageContents::from_memory(&read_encoded(base_path, image, buffers)?)?
+                },
+            };
+            self.images.insert(key.clone(), img);
+        }
+
+        Ok(&self.images[&key])
+    }
+}
+
+/// Reads the encoded data of an image: its buffer view, data URI or file.
+fn read_encoded<'a>(
+    base_path: &'a Path,
+    image: &'a GltfImage,
+    buffers: &'a Buffers,
+) -> Result<Vec<u8>> {
+    match image.data() {
+        GltfData::View { view, .. } => {
+            Ok(buffers.view(&view).ok_or(ConvertError::MissingImageBuffer)?.to_vec())
+        },
+        GltfData::Uri { uri, .. } if uri.starts_with("data:") => {
+            Ok(decode_data_uri(uri)?.1)
+        },
+        GltfData::Uri { uri, .. } => {
+            let mut data = Vec::<u8>::new();
+            File::open(resolve_uri(base_path, uri)?)?.read_to_end(&mut data)?;
+            Ok(data)
+        },
+    }
+}
+
+/// Decodes the contents of an image, either from a buffer view or a file.
+pub fn load_image<'a>(
+    base_path: &'a Path,
+    image: &'a GltfImage,
+    buffers: &'a Buffers,
+) -> Result<DynamicImage> {
+    match image.data() {
+        GltfData::View { view, .. } => {
+            let contents = buffers.view(&view).ok_or(ConvertError::MissingImageBuffer)?;
+            Ok(load_image_from_memory(contents)?)
+        },
+        GltfData::Uri { uri, .. } if uri.starts_with("data:") => {
+            Ok(load_image_from_memory(&decode_data_uri(uri)?.1)?)
+        },
+        GltfData::Uri{ uri, .. } => {
+            Ok(open_image(resolve_uri(base_path, uri)?)?)
+        },
+    }
+}
+
+fn get_name<'a>(
+    texture: &'a GltfTexture,
+    options: &'a Options,
+) -> Result<String> {
+    if let Some(name) = texture.name() {
+        return Ok(String::from(name));
+    }
+
+    match options.name_policy {
+        NamePolicy::Require => Err(Error::Convert(ConvertError::NoName)),
+        NamePolicy::Generate => {
+            // Prefer the file stem of the source image, e.g. `albedo.png` -> `albedo`.
+            if let GltfData::Uri { uri, .. } = texture.source().data() {
+                if !uri.starts_with("data:") {
+                    if let Some(stem) = Path::new(uri).file_stem().and_then(|stem| stem.to_str()) {
+                        return Ok(String::from(stem));
+                    }
+                }
+            }
+
+            Ok(format!("texture_{}", texture.index()))
+        },
+    }
 }
 
 #[cfg(test)]
 mod tests {
     // use super::super::load_gltf;
     use super::*;
+    use super::super::import;
+
+    #[test]
+    fn test_generated_names() {
+        let path = Path::new("testmodels/gltf2/UnnamedTexture/UnnamedTexture.gltf");
+        let parent = path.parent().unwrap();
+        let (gltf, _, buffers) = import(path, parent).unwrap();
+        let mut options = Options::default();
+
+        assert!(get(parent, gltf.textures(), &buffers, &options, &mut Vec::new()).is_err());
+
+        options.name_policy = NamePolicy::Generate;
+        let textures = get(parent, gltf.textures(), &buffers, &options, &mut Vec::new()).unwrap();
+        assert_eq!(textures.get(0), Some("albedo"));
+        assert_eq!(textures.get(1), Some("texture_1"));
+    }
+
+    #[test]
+    fn test_shared_image_range() {
+        let path = Path::new("testmodels/gltf2/SharedImage/SharedImage.gltf");
+        let parent = path.parent().unwrap();
+        let (gltf, _, buffers) = import(path, parent).unwrap();
+
+        let mut cache = ImageCache::default();
+        for texture in gltf.textures() {
+            cache.load(parent, &texture.source(), &buffers).unwrap();
+        }
+        assert_eq!(gltf.textures().count(), 2);
+        assert_eq!(cache.images.len(), 1);
+
+        let textures = get(parent, gltf.textures(), &buffers, &Options::default(), &mut Vec::new()).unwrap();
+        assert_eq!(textures.textures[0].contents, textures.textures[1].contents);
+    }
+
+    #[test]
+    fn test_ktx2_block_compression() {
+        let path = Path::new("testmodels/gltf2/Ktx2/Ktx2.gltf");
+        let parent = path.parent().unwrap();
+        let (gltf, _, buffers) = import(path, parent).unwrap();
+        let textures = get(parent, gltf.textures(), &buffers, &Options::default(), &mut Vec::new()).unwrap();
+        let texture = &textures.textures()[0];
+
+        assert_eq!(texture.format(), Format::Bc7);
+        assert_eq!(texture.format().block_dimensions(), (4, 4));
+        assert_eq!((texture.width, texture.height), (8, 8));
+        // Four 16 byte blocks cover an 8x8 texture.
+        assert_eq!(texture.contents().len(), 64);
+        assert_eq!(texture.contents()[16], 1);
+    }
+
+    #[test]
+    fn test_ktx2_mip_levels() {
+        let path = Path::new("testmodels/gltf2/Ktx2Mips/Ktx2Mips.gltf");
+        let parent = path.parent().unwrap();
+        let (gltf, _, buffers) = import(path, parent).unwrap();
+        let textures = get(parent, gltf.textures(), &buffers, &Options::default(), &mut Vec::new()).unwrap();
+        let texture = &textures.textures()[0];
+
+        assert_eq!((texture.width(), texture.height()), (16, 16));
+        assert_eq!(texture.contents().len(), 16 * 16);
+        assert!(texture.contents().iter().all(|&byte| byte == 1));
+
+        // The four smaller levels are kept as authored, down to one block.
+        let levels = texture.mip_levels();
+        let dimensions = levels.iter().map(|level| (level.width(), level.height())).collect::<Vec<_>>();
+        assert_eq!(dimensions, vec![(8, 8), (4, 4), (2, 2), (1, 1)]);
+        for (index, level) in levels.iter().enumerate() {
+            let blocks = ((level.width() + 3) / 4) as usize;
+            assert_eq!(level.row_stride(), blocks * 16);
+            assert_eq!(level.contents().len(), blocks * blocks * 16);
+            assert!(level.contents().iter().all(|&byte| byte == index as u8 + 2));
+        }
+
+        // A texture without a mip chain only has its base level.
+        let path = Path::new("testmodels/gltf2/Ktx2/Ktx2.gltf");
+        let parent = path.parent().unwrap();
+        let (gltf, _, buffers) = import(path, parent).unwrap();
+        let textures = get(parent, gltf.textures(), &buffers, &Options::default(), &mut Vec::new()).unwrap();
+        assert!(textures.textures()[0].mip_levels().is_empty());
+    }
+
+    #[test]
+    fn test_power_of_two() {
+        let path = Path::new("testmodels/gltf2/NonPowerOfTwo/NonPowerOfTwo.gltf");
+        let parent = path.parent().unwrap();
+        let (gltf, _, buffers) = import(path, parent).unwrap();
+        let mut options = Options::default();
+
+        options.force_power_of_two = Some(PowerOfTwo::Pad);
+        let textures = get(parent, gltf.textures(), &buffers, &options, &mut Vec::new()).unwrap();
+        let texture = &textures.textures()[0];
+        assert_eq!((texture.width(), texture.height()), (128, 256));
+        assert_eq!(texture.original_dimensions(), (100, 200));
+        assert_eq!(texture.uv_scale(), [100.0 / 128.0, 200.0 / 256.0]);
+        assert_eq!(texture.contents().len(), 128 * 256 * 4);
+        assert_eq!(&texture.contents()[396 .. 404], &[10, 20, 30, 255, 0, 0, 0, 0]);
+
+        options.force_power_of_two = Some(PowerOfTwo::Resize);
+        let textures = get(parent, gltf.textures(), &buffers, &options, &mut Vec::new()).unwrap();
+        let texture = &textures.textures()[0];
+        assert_eq!((texture.width(), texture.height()), (128, 256));
+        assert_eq!(texture.original_dimensions(), (100, 200));
+        assert_eq!(texture.uv_scale(), [1.0, 1.0]);
+        assert_eq!(&texture.contents()[508 .. 512], &[10, 20, 30, 255]);
+    }
+
+    #[test]
+    fn test_row_alignment() {
+        let path = Path::new("testmodels/gltf2/RowAlignment/RowAlignment.gltf");
+        let parent = path.parent().unwrap();
+        let (gltf, _, buffers) = import(path, parent).unwrap();
+        let mut options = Options::default();
+        options.row_alignment = Some(256);
+        let textures = get(parent, gltf.textures(), &buffers, &options, &mut Vec::new()).unwrap();
+
+        let rgba = &textures.textures()[0];
+        assert_eq!(rgba.row_stride(), 512);
+        assert_eq!(rgba.contents().len() / rgba.row_stride(), 10);
+        assert_eq!(&rgba.contents()[396 .. 404], &[10, 20, 30, 255, 0, 0, 0, 0]);
+        assert_eq!(&rgba.contents()[512 .. 516], &[10, 20, 30, 255]);
+
+        let gray = &textures.textures()[1];
+        assert_eq!(gray.format(), Format::GrayImage);
+        assert_eq!(gray.row_stride(), 256);
+        assert_eq!(gray.contents().len() / gray.row_stride(), 10);
+        assert_eq!(&gray.contents()[99 .. 101], &[77, 0]);
+
+        let textures = get(parent, gltf.textures(), &buffers, &Options::default(), &mut Vec::new()).unwrap();
+        assert_eq!(textures.textures()[0].row_stride(), 400);
+        assert_eq!(textures.textures()[0].contents().len(), 4000);
+    }
+
+    #[test]
+    fn test_keep_encoded_images() {
+        let path = Path::new("testmodels/gltf2/NormalMap/NormalMap.gltf");
+        let parent = path.parent().unwrap();
+        let (gltf, _, buffers) = import(path, parent).unwrap();
+        let mut options = Options::default();
+        options.keep_encoded_images = true;
+        options.row_alignment = Some(256);
+
+        let textures = get(parent, gltf.textures(), &buffers, &options, &mut Vec::new()).unwrap();
+        let decoded = get(parent, gltf.textures(), &buffers, &Options::default(), &mut Vec::new()).unwrap();
+        let mut png = Vec::<u8>::new();
+        File::open(parent.join("bumps.png")).unwrap().read_to_end(&mut png).unwrap();
+
+        let texture = &textures.textures()[0];
+        assert_eq!(texture.format(), Format::Encoded { mime: ImageMime::Png });
+        assert_eq!(texture.contents(), &png[..]);
+        assert_eq!(texture.row_stride(), 0);
+        assert_eq!((texture.width(), texture.height()), (decoded.textures()[0].width(), decoded.textures()[0].height()));
+
+        // KTX2 textures stay block compressed.
+        let path = Path::new("testmodels/gltf2/Ktx2/Ktx2.gltf");
+        let parent = path.parent().unwrap();
+        let (gltf, _, buffers) = import(path, parent).unwrap();
+        let textures = get(parent, gltf.textures(), &buffers, &options, &mut Vec::new()).unwrap();
+        assert_eq!(textures.textures()[0].format(), Format::Bc7);
+    }
+
+    #[test]
+    fn test_16_bit_images() {
+        let path = Path::new("testmodels/gltf2/Heightmap16/Heightmap16.gltf");
+        let parent = path.parent().unwrap();
+        let (gltf, _, buffers) = import(path, parent).unwrap();
+        let textures = get(parent, gltf.textures(), &buffers, &Options::default(), &mut Vec::new()).unwrap();
+
+        let height = &textures.textures()[0];
+        assert_eq!(height.format(), Format::Gray16Image);
+        assert_eq!(height.row_stride(), 4);
+        assert_eq!(height.contents(), &[0x02, 0x01, 0xff, 0xff, 0x00, 0x00, 0xe8, 0x03]);
+
+        let mut normal = textures.textures()[1].clone();
+        assert_eq!(normal.format(), Format::Rgb16Image);
+        assert_eq!(&normal.contents()[..6], &[0x00, 0x80, 0x34, 0x12, 0xff, 0xff]);
+        normal.flip_green();
+        assert_eq!(&normal.contents()[..6], &[0x00, 0x80, 0xcb, 0xed, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn test_resize_16_bit() {
+        let img = ImageContents {
+            width: 3,
+            height: 1,
+            format: Format::Gray16Image,
+            contents: vec![0x34, 0x12, 0x34, 0x12, 0x34, 0x12],
+            mip_levels: Vec::new(),
+        };
+
+        let (width, height, contents) = to_power_of_two(&img, PowerOfTwo::Resize).unwrap();
+        assert_eq!((width, height), (4, 1));
+        assert_eq!(contents, vec![0x34, 0x12, 0x34, 0x12, 0x34, 0x12, 0x34, 0x12]);
+    }
+
+    #[test]
+    fn test_max_texture_size() {
+        let path = Path::new("testmodels/gltf2/LargeTexture/LargeTexture.gltf");
+        let parent = path.parent().unwrap();
+        let (gltf, _, buffers) = import(path, parent).unwrap();
+        let mut options = Options::default();
+        options.max_texture_size = Some(1024);
+
+        let textures = get(parent, gltf.textures(), &buffers, &options, &mut Vec::new()).unwrap();
+        let texture = &textures.textures()[0];
+        assert_eq!((texture.width(), texture.height()), (1024, 64));
+        assert_eq!(texture.original_dimensions(), (4096, 256));
+        assert_eq!(texture.contents().len(), 1024 * 64 * 4);
+
+        options.max_texture_size = Some(8192);
+        let textures = get(parent, gltf.textures(), &buffers, &options, &mut Vec::new()).unwrap();
+        assert_eq!(textures.textures()[0].width(), 4096);
+
+        // 16 bit images are downscaled too.
+        let path = Path::new("testmodels/gltf2/Heightmap16/Heightmap16.gltf");
+        let parent = path.parent().unwrap();
+        let (gltf, _, buffers) = import(path, parent).unwrap();
+        options.max_texture_size = Some(1);
+        let mut warnings = Vec::new();
+        let textures = get(parent, gltf.textures(), &buffers, &options, &mut warnings).unwrap();
+        let height = &textures.textures()[0];
+        assert_eq!((height.width(), height.height()), (1, 1));
+        assert_eq!(height.format(), Format::Gray16Image);
+        assert_eq!(height.contents().len(), 2);
+        assert!(warnings.is_empty());
+        // Block compressed images are kept as they are, with a warning.
+        let path = Path::new("testmodels/gltf2/Ktx2/Ktx2.gltf");
+        let parent = path.parent().unwrap();
+        let (gltf, _, buffers) = import(path, parent).unwrap();
+        options.max_texture_size = Some(4);
+        let textures = get(parent, gltf.textures(), &buffers, &options, &mut warnings).unwrap();
+        assert_eq!(textures.textures()[0].width(), 8);
+        assert_eq!(warnings, vec![ConvertWarning::OversizedTexture {
+            texture: textures.textures()[0].name().to_owned(),
+        }]);
+    }
+
+    #[test]
+    fn test_default_min_filter() {
+        let path = Path::new("testmodels/gltf2/UnnamedTexture/UnnamedTexture.gltf");
+        let parent = path.parent().unwrap();
+        let (gltf, _, buffers) = import(path, parent).unwrap();
+        let mut options = Options::default();
+        options.name_policy = NamePolicy::Generate;
+
+        let textures = get(parent, gltf.textures(), &buffers, &options, &mut Vec::new()).unwrap();
+        assert_eq!(textures.textures[0].min_filter, MinFilter::Nearest);
+
+        options.generate_mipmaps = true;
+        let textures = get(parent, gltf.textures(), &buffers, &options, &mut Vec::new()).unwrap();
+        assert_eq!(textures.textures[0].min_filter, MinFilter::LinearMipmapLinear);
+    }
+
+    #[test]
+    fn test_default_anisotropy() {
+        let path = Path::new("testmodels/gltf2/NonPowerOfTwo/NonPowerOfTwo.gltf");
+        let parent = path.parent().unwrap();
+        let (gltf, _, buffers) = import(path, parent).unwrap();
+        let mut options = Options::default();
+
+        let textures = get(parent, gltf.textures(), &buffers, &options, &mut Vec::new()).unwrap();
+        assert_eq!(textures.textures()[0].anisotropy(), None);
+
+        options.default_anisotropy = Some(8);
+        let textures = get(parent, gltf.textures(), &buffers, &options, &mut Vec::new()).unwrap();
+        assert_eq!(textures.textures()[0].anisotropy(), Some(8));
+    }
+
+    #[test]
+    fn test_placeholder_texture() {
+        let path = Path::new("testmodels/gltf2/MissingImage/MissingImage.gltf");
+        let parent = path.parent().unwrap();
+        let (gltf, _, buffers) = import(path, parent).unwrap();
+        let mut options = Options::default();
+        let mut warnings = Vec::new();
+
+        assert!(get(parent, gltf.textures(), &buffers, &options, &mut warnings).is_err());
+
+        options.placeholder_texture = Some(PlaceholderTexture::Solid([255, 0, 255, 255]));
+        let textures = get(parent, gltf.textures(), &buffers, &options, &mut warnings).unwrap();
+        let texture = &textures.textures()[0];
+        assert_eq!((texture.width(), texture.height()), (8, 8));
+        assert_eq!(texture.format(), Format::RgbaImage);
+        assert!(texture.contents().chunks(4).all(|texel| texel == [255, 0, 255, 255]));
+        assert_eq!(warnings, vec![ConvertWarning::PlaceholderTexture { texture: String::from("noise") }]);
+
+        options.placeholder_texture = Some(PlaceholderTexture::Checkerboard([255, 0, 255, 255]));
+        let textures = get(parent, gltf.textures(), &buffers, &options, &mut warnings).unwrap();
+        assert_eq!(&textures.textures()[0].contents()[0..8], &[255, 0, 255, 255, 0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_channel_order() {
+        let path = Path::new("testmodels/gltf2/NonPowerOfTwo/NonPowerOfTwo.gltf");
+        let parent = path.parent().unwrap();
+        let (gltf, _, buffers) = import(path, parent).unwrap();
+        let mut options = Options::default();
+
+        let rgba = get(parent, gltf.textures(), &buffers, &options, &mut Vec::new()).unwrap();
+        options.channel_order = ChannelOrder::Bgra;
+        let bgra = get(parent, gltf.textures(), &buffers, &options, &mut Vec::new()).unwrap();
+
+        assert_eq!(bgra.textures()[0].channel_order(), ChannelOrder::Bgra);
+        assert_eq!(&rgba.textures()[0].contents()[0..4], &[10, 20, 30, 255]);
+        for (before, after) in rgba.textures()[0].contents().chunks(4).zip(bgra.textures()[0].contents().chunks(4)) {
+            assert_eq!(after, &[before[2], before[1], before[0], before[3]]);
+        }
+    }
 
     // #[test]
     // fn test_convert_buffers_get() {
@@ -1,16 +1,479 @@
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::path::{Component, Path, PathBuf};
+use std::ptr::{copy_nonoverlapping, read_unaligned};
+
+use base64;
 use gltf;
 use gltf::accessor::{DataType, Dimensions};
-use gltf_utils::{AccessorIter, Denormalize, Source};
+use gltf_utils::{Denormalize, Source};
+
+use super::super::{Result, Error};
+use super::ConvertError;
+
+/// Visits the items in an `Accessor`.
+///
+/// This mirrors `gltf_utils::AccessorIter`, but copies each item out of the
+/// buffer with an unaligned read instead of `mem::transmute_copy`.
+#[derive(Clone, Debug)]
+pub struct AccessorIter<'a, T> {
+    /// The total number of items.
+    count: usize,
+
+    /// The index of the next item.
+    index: usize,
+
+    /// The number of bytes between each item.
+    stride: usize,
+
+    /// Byte offset into the buffer view where the items begin.
+    offset: usize,
+
+    /// The buffer view data we're iterating over.
+    data: &'a [u8],
+
+    /// Consumes the data type we're returning at each iteration.
+    _marker: PhantomData<T>,
+}
+
+impl<'a, T: Copy> AccessorIter<'a, T> {
+    /// Fails if the accessor's items are not the size of `T`, or if they do
+    /// not all fit inside its buffer view.
+    pub fn new<S: Source>(accessor: gltf::Accessor<'a>, source: &'a S) -> Result<AccessorIter<'a, T>> {
+        if size_of::<T>() != accessor.size() {
+            return Err(Error::Convert(ConvertError::InvalidAccessor { accessor: accessor.index() }));
+        }
+        let view = accessor.view();
+        let buffer_data = source.source_buffer(&view.buffer());
+        let view_data = view.offset().checked_add(view.length())
+            .and_then(|end| buffer_data.get(view.offset() .. end))
+            .ok_or(ConvertError::BufferViewOverrun { view: view.index() })?;
+        let stride = view.stride().unwrap_or(size_of::<T>());
+
+        // The last item has to end inside the view.
+        let end = match accessor.count().checked_sub(1) {
+            Some(last) => last.checked_mul(stride).and_then(|start| start.checked_add(size_of::<T>())),
+            None => Some(0),
+        }.and_then(|end| end.checked_add(accessor.offset()));
+        match end {
+            Some(end) if end <= view_data.len() => {},
+            _ => { return Err(Error::Convert(ConvertError::AccessorOverrun { accessor: accessor.index() })); },
+        }
+
+        Ok(AccessorIter {
+            count: accessor.count(),
+            index: 0,
+            stride: stride,
+            offset: accessor.offset(),
+            data: view_data,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Reads all remaining items.
+    ///
+    /// Tightly packed items are copied out of the buffer in one block rather
+    /// than one at a time.
+    pub fn read_all(self) -> Vec<T> {
+        if self.stride != size_of::<T>() {
+            return self.collect();
+        }
+
+        let count = self.count - self.index;
+        let start = self.offset + self.index * self.stride;
+        let bytes = &self.data[start .. start + count * self.stride];
+        let mut items = Vec::<T>::with_capacity(count);
+
+        unsafe {
+            copy_nonoverlapping(bytes.as_ptr(), items.as_mut_ptr() as *mut u8, bytes.len());
+            items.set_len(count);
+        }
+
+        items
+    }
+}
+
+impl<'a, T: Copy> ExactSizeIterator for AccessorIter<'a, T> {}
+
+impl<'a, T: Copy> Iterator for AccessorIter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index < self.count {
+            let start = self.offset + self.index * self.stride;
+            let bytes = &self.data[start .. start + size_of::<T>()];
+            self.index += 1;
+
+            Some(unsafe { read_unaligned(bytes.as_ptr() as *const T) })
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let hint = self.count - self.index;
+        (hint, Some(hint))
+    }
+}
+
+/// Extra methods for working with `gltf::Primitive`.
+///
+/// These replace `gltf_utils::PrimitiveIterators`, whose accessor reads are
+/// unsound.
+pub trait PrimitiveIterators<'a> {
+    /// Visits the vertex positions of a primitive.
+    fn positions<S: Source>(&'a self, source: &'a S) -> Result<Option<AccessorIter<'a, [f32; 3]>>>;
+
+    /// Visits the vertex normals of a primitive.
+    fn normals<S: Source>(&'a self, source: &'a S) -> Result<Option<AccessorIter<'a, [f32; 3]>>>;
+
+    /// Visits the vertex tangents of a primitive.
+    fn tangents<S: Source>(&'a self, source: &'a S) -> Result<Option<AccessorIter<'a, [f32; 4]>>>;
+
+    /// Visits the vertex texture co-ordinates of a primitive.
+    fn tex_coords_f32<S: Source>(&'a self, set: u32, source: &'a S) -> Result<Option<TexCoordsF32<'a>>>;
+
+    /// Visits the vertex colors of a primitive.
+    fn colors_rgba_f32<S: Source>(&'a self, set: u32, source: &'a S) -> Result<Option<ColorsRgbaF32<'a>>>;
+
+    /// Visits the vertex indices of a primitive.
+    fn indices_u32<S: Source>(&'a self, source: &'a S) -> Result<Option<IndicesU32<'a>>>;
+
+    /// Visits the joint indices of a primitive.
+    fn joints_u16<S: Source>(&'a self, set: u32, source: &'a S) -> Result<Option<JointsU16<'a>>>;
+
+    /// Visits the joint weights of a primitive.
+    fn weights_f32<S: Source>(&'a self, set: u32, source: &'a S) -> Result<Option<WeightsF32<'a>>>;
+}
+
+impl<'a> PrimitiveIterators<'a> for gltf::Primitive<'a> {
+    fn positions<S: Source>(&'a self, source: &'a S) -> Result<Option<AccessorIter<'a, [f32; 3]>>> {
+        match self.get(&gltf::Semantic::Positions) {
+            Some(accessor) => Ok(Some(AccessorIter::new(accessor, source)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn normals<S: Source>(&'a self, source: &'a S) -> Result<Option<AccessorIter<'a, [f32; 3]>>> {
+        match self.get(&gltf::Semantic::Normals) {
+            Some(accessor) => Ok(Some(AccessorIter::new(accessor, source)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn tangents<S: Source>(&'a self, source: &'a S) -> Result<Option<AccessorIter<'a, [f32; 4]>>> {
+        match self.get(&gltf::Semantic::Tangents) {
+            Some(accessor) => Ok(Some(AccessorIter::new(accessor, source)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn tex_coords_f32<S: Source>(&'a self, set: u32, source: &'a S) -> Result<Option<TexCoordsF32<'a>>> {
+        let accessor = match self.get(&gltf::Semantic::TexCoords(set)) {
+            Some(accessor) => accessor,
+            None => { return Ok(None); },
+        };
+
+        Ok(Some(TexCoordsF32(match accessor.data_type() {
+            DataType::U8 => Normalized2::U8(AccessorIter::new(accessor, source)?),
+            DataType::U16 => Normalized2::U16(AccessorIter::new(accessor, source)?),
+            _ => Normalized2::F32(AccessorIter::new(accessor, source)?),
+        })))
+    }
+
+    fn colors_rgba_f32<S: Source>(&'a self, set: u32, source: &'a S) -> Result<Option<ColorsRgbaF32<'a>>> {
+        let accessor = match self.get(&gltf::Semantic::Colors(set)) {
+            Some(accessor) => accessor,
+            None => { return Ok(None); },
+        };
+
+        Ok(Some(ColorsRgbaF32(match accessor.dimensions() {
+            Dimensions::Vec3 => Colors::Rgb(match accessor.data_type() {
+                DataType::U8 => Normalized3::U8(AccessorIter::new(accessor, source)?),
+                DataType::U16 => Normalized3::U16(AccessorIter::new(accessor, source)?),
+                _ => Normalized3::F32(AccessorIter::new(accessor, source)?),
+            }),
+            _ => Colors::Rgba(match accessor.data_type() {
+                DataType::U8 => Normalized4::U8(AccessorIter::new(accessor, source)?),
+                DataType::U16 => Normalized4::U16(AccessorIter::new(accessor, source)?),
+                _ => Normalized4::F32(AccessorIter::new(accessor, source)?),
+            }),
+        })))
+    }
+
+    fn indices_u32<S: Source>(&'a self, source: &'a S) -> Result<Option<IndicesU32<'a>>> {
+        let accessor = match self.indices() {
+            Some(accessor) => accessor,
+            None => { return Ok(None); },
+        };
+
+        Ok(Some(match accessor.data_type() {
+            DataType::U8 => IndicesU32::U8(AccessorIter::new(accessor, source)?),
+            DataType::U16 => IndicesU32::U16(AccessorIter::new(accessor, source)?),
+            _ => IndicesU32::U32(AccessorIter::new(accessor, source)?),
+        }))
+    }
+
+    fn joints_u16<S: Source>(&'a self, set: u32, source: &'a S) -> Result<Option<JointsU16<'a>>> {
+        let accessor = match self.get(&gltf::Semantic::Joints(set)) {
+            Some(accessor) => accessor,
+            None => { return Ok(None); },
+        };
+
+        Ok(Some(match accessor.data_type() {
+            DataType::U8 => JointsU16::U8(AccessorIter::new(accessor, source)?),
+            _ => JointsU16::U16(AccessorIter::new(accessor, source)?),
+        }))
+    }
+
+    fn weights_f32<S: Source>(&'a self, set: u32, source: &'a S) -> Result<Option<WeightsF32<'a>>> {
+        let accessor = match self.get(&gltf::Semantic::Weights(set)) {
+            Some(accessor) => accessor,
+            None => { return Ok(None); },
+        };
+
+        Ok(Some(WeightsF32(match accessor.data_type() {
+            DataType::U8 => Normalized4::U8(AccessorIter::new(accessor, source)?),
+            DataType::U16 => Normalized4::U16(AccessorIter::new(accessor, source)?),
+            _ => Normalized4::F32(AccessorIter::new(accessor, source)?),
+        })))
+    }
+}
+
+/// Two-component values that are either `f32` or normalized integers.
+#[derive(Clone, Debug)]
+enum Normalized2<'a> {
+    F32(AccessorIter<'a, [f32; 2]>),
+    U8(AccessorIter<'a, [u8; 2]>),
+    U16(AccessorIter<'a, [u16; 2]>),
+}
+
+impl<'a> Iterator for Normalized2<'a> {
+    type Item = [f32; 2];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match *self {
+            Normalized2::F32(ref mut i) => i.next(),
+            Normalized2::U8(ref mut i) => i.next().map(|x| x.denormalize()),
+            Normalized2::U16(ref mut i) => i.next().map(|x| x.denormalize()),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match *self {
+            Normalized2::F32(ref i) => i.size_hint(),
+            Normalized2::U8(ref i) => i.size_hint(),
+            Normalized2::U16(ref i) => i.size_hint(),
+        }
+    }
+}
+
+/// Three-component values that are either `f32` or normalized integers.
+#[derive(Clone, Debug)]
+enum Normalized3<'a> {
+    F32(AccessorIter<'a, [f32; 3]>),
+    U8(AccessorIter<'a, [u8; 3]>),
+    U16(AccessorIter<'a, [u16; 3]>),
+}
+
+impl<'a> Iterator for Normalized3<'a> {
+    type Item = [f32; 3];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match *self {
+            Normalized3::F32(ref mut i) => i.next(),
+            Normalized3::U8(ref mut i) => i.next().map(|x| x.denormalize()),
+            Normalized3::U16(ref mut i) => i.next().map(|x| x.denormalize()),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match *self {
+            Normalized3::F32(ref i) => i.size_hint(),
+            Normalized3::U8(ref i) => i.size_hint(),
+            Normalized3::U16(ref i) => i.size_hint(),
+        }
+    }
+}
+
+/// Four-component values that are either `f32` or normalized integers.
+#[derive(Clone, Debug)]
+enum Normalized4<'a> {
+    F32(AccessorIter<'a, [f32; 4]>),
+    U8(AccessorIter<'a, [u8; 4]>),
+    U16(AccessorIter<'a, [u16; 4]>),
+}
+
+impl<'a> Iterator for Normalized4<'a> {
+    type Item = [f32; 4];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match *self {
+            Normalized4::F32(ref mut i) => i.next(),
+            Normalized4::U8(ref mut i) => i.next().map(|x| x.denormalize()),
+            Normalized4::U16(ref mut i) => i.next().map(|x| x.denormalize()),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match *self {
+            Normalized4::F32(ref i) => i.size_hint(),
+            Normalized4::U8(ref i) => i.size_hint(),
+            Normalized4::U16(ref i) => i.size_hint(),
+        }
+    }
+}
+
+/// Texture co-ordinates coerced into `[f32; 2]` values.
+#[derive(Clone, Debug)]
+pub struct TexCoordsF32<'a>(Normalized2<'a>);
+
+impl<'a> TexCoordsF32<'a> {
+    /// Reads all remaining texture co-ordinates, in one block if they are
+    /// tightly packed `f32`s.
+    pub fn read_all(self) -> Vec<[f32; 2]> {
+        match self.0 {
+            Normalized2::F32(iter) => iter.read_all(),
+            iter => iter.collect(),
+        }
+    }
+}
+
+impl<'a> ExactSizeIterator for TexCoordsF32<'a> {}
+
+impl<'a> Iterator for TexCoordsF32<'a> {
+    type Item = [f32; 2];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+/// Vertex colors.
+#[derive(Clone, Debug)]
+enum Colors<'a> {
+    Rgb(Normalized3<'a>),
+    Rgba(Normalized4<'a>),
+}
+
+/// Vertex colors coerced into RGBA `[f32; 4]` values.
+/// RGB colors are given an alpha of `1.0`.
+#[derive(Clone, Debug)]
+pub struct ColorsRgbaF32<'a>(Colors<'a>);
+
+impl<'a> ExactSizeIterator for ColorsRgbaF32<'a> {}
+
+impl<'a> Iterator for ColorsRgbaF32<'a> {
+    type Item = [f32; 4];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.0 {
+            Colors::Rgb(ref mut i) => i.next().map(|x| [x[0], x[1], x[2], 1.0]),
+            Colors::Rgba(ref mut i) => i.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.0 {
+            Colors::Rgb(ref i) => i.size_hint(),
+            Colors::Rgba(ref i) => i.size_hint(),
+        }
+    }
+}
+
+/// Index data coerced into `u32` values.
+#[derive(Clone, Debug)]
+pub enum IndicesU32<'a> {
+    U8(AccessorIter<'a, u8>),
+    U16(AccessorIter<'a, u16>),
+    U32(AccessorIter<'a, u32>),
+}
+
+impl<'a> ExactSizeIterator for IndicesU32<'a> {}
+
+impl<'a> Iterator for IndicesU32<'a> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match *self {
+            IndicesU32::U8(ref mut i) => i.next().map(|x| x as u32),
+            IndicesU32::U16(ref mut i) => i.next().map(|x| x as u32),
+            IndicesU32::U32(ref mut i) => i.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match *self {
+            IndicesU32::U8(ref i) => i.size_hint(),
+            IndicesU32::U16(ref i) => i.size_hint(),
+            IndicesU32::U32(ref i) => i.size_hint(),
+        }
+    }
+}
+
+/// Joint indices coerced into `[u16; 4]` values.
+#[derive(Clone, Debug)]
+pub enum JointsU16<'a> {
+    U8(AccessorIter<'a, [u8; 4]>),
+    U16(AccessorIter<'a, [u16; 4]>),
+}
+
+impl<'a> ExactSizeIterator for JointsU16<'a> {}
+
+impl<'a> Iterator for JointsU16<'a> {
+    type Item = [u16; 4];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match *self {
+            JointsU16::U8(ref mut i) => {
+                i.next().map(|x| [x[0] as u16, x[1] as u16, x[2] as u16, x[3] as u16])
+            },
+            JointsU16::U16(ref mut i) => i.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match *self {
+            JointsU16::U8(ref i) => i.size_hint(),
+            JointsU16::U16(ref i) => i.size_hint(),
+        }
+    }
+}
+
+/// Joint weights coerced into `[f32; 4]` values.
+#[derive(Clone, Debug)]
+pub struct WeightsF32<'a>(Normalized4<'a>);
+
+impl<'a> ExactSizeIterator for WeightsF32<'a> {}
+
+impl<'a> Iterator for WeightsF32<'a> {
+    type Item = [f32; 4];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
 
 /// Extra methods for working with `gltf::Skin`.
 pub trait SkinIterators<'a> {
     /// Visits the `inverseBindMatrices` of the skin.
-    fn ibms<S: Source>(&'a self, source: &'a S) -> Option<InverseBindMatrices<'a>>;
+    fn ibms<S: Source>(&'a self, source: &'a S) -> Result<Option<InverseBindMatrices<'a>>>;
 }
 
 impl<'a> SkinIterators<'a> for gltf::Skin<'a> {
-    fn ibms<S: Source>(&'a self, source: &'a S) -> Option<InverseBindMatrices<'a>> {
-        self.inverse_bind_matrices().map(|accessor| InverseBindMatrices(AccessorIter::new(accessor, source)))
+    fn ibms<S: Source>(&'a self, source: &'a S) -> Result<Option<InverseBindMatrices<'a>>> {
+        match self.inverse_bind_matrices() {
+            Some(accessor) => Ok(Some(InverseBindMatrices(AccessorIter::new(accessor, source)?))),
+            None => Ok(None),
+        }
     }
 }
 
@@ -34,59 +497,59 @@ impl<'a> Iterator for InverseBindMatrices<'a> {
 /// Extra methods for working with `gltf::animation::Channel`.
 pub trait ChannelIterators<'a> {
     /// Visits the input samples of a channel.
-    fn times<S: Source>(&'a self, source: &'a S) -> Times<'a>;
+    fn times<S: Source>(&'a self, source: &'a S) -> Result<Times<'a>>;
 
     /// Visits the translation samples of a channel.
-    fn translations<S: Source>(&'a self, source: &'a S) -> Option<Translations<'a>>;
+    fn translations<S: Source>(&'a self, source: &'a S) -> Result<Option<Translations<'a>>>;
 
     /// Visits the rotation samples of a channel.
-    fn rotations_f32<S: Source>(&'a self, source: &'a S) -> Option<RotationsF32<'a>>;
+    fn rotations_f32<S: Source>(&'a self, source: &'a S) -> Result<Option<RotationsF32<'a>>>;
 
     /// Visits the scaling samples of a channel.
-    fn scales<S: Source>(&'a self, source: &'a S) -> Option<Scales<'a>>;
+    fn scales<S: Source>(&'a self, source: &'a S) -> Result<Option<Scales<'a>>>;
 
     /// Visits the weight samples of a channel.
-    fn weights_f32<S: Source>(&'a self, source: &'a S) -> Option<MorphWeightsF32<'a>>;
+    fn weights_f32<S: Source>(&'a self, source: &'a S) -> Result<Option<MorphWeightsF32<'a>>>;
 }
 
 impl<'a> ChannelIterators<'a> for gltf::animation::Channel<'a> {
-    fn times<S: Source>(&'a self, source: &'a S) -> Times<'a> {
-        Times(AccessorIter::new(self.sampler().input(), source))
+    fn times<S: Source>(&'a self, source: &'a S) -> Result<Times<'a>> {
+        Ok(Times(AccessorIter::new(self.sampler().input(), source)?))
     }
  
-    fn translations<S: Source>(&'a self, source: &'a S) -> Option<Translations<'a>> {
+    fn translations<S: Source>(&'a self, source: &'a S) -> Result<Option<Translations<'a>>> {
         match self.target().path() {
             gltf::animation::TrsProperty::Translation => {
-                Some(Translations(AccessorIter::new(self.sampler().output(), source)))
+                Ok(Some(Translations(AccessorIter::new(self.sampler().output(), source)?)))
             },
-            _ => None,
+            _ => Ok(None),
         }
     }
 
-    fn rotations_f32<S: Source>(&'a self, source: &'a S) -> Option<RotationsF32<'a>> {
+    fn rotations_f32<S: Source>(&'a self, source: &'a S) -> Result<Option<RotationsF32<'a>>> {
         match self.target().path() {
             gltf::animation::TrsProperty::Rotation => {
-                Some(RotationsF32(Rotations::new(self.sampler().output(), source)))
+                Ok(Some(RotationsF32(Rotations::new(self.sampler().output(), source)?)))
             },
-            _ => None,
+            _ => Ok(None),
         }
     }
 
-    fn scales<S: Source>(&'a self, source: &'a S) -> Option<Scales<'a>> {
+    fn scales<S: Source>(&'a self, source: &'a S) -> Result<Option<Scales<'a>>> {
         match self.target().path() {
             gltf::animation::TrsProperty::Scale => {
-                Some(Scales(AccessorIter::new(self.sampler().output(), source)))
+                Ok(Some(Scales(AccessorIter::new(self.sampler().output(), source)?)))
             },
-            _ => None,
+            _ => Ok(None),
         }
     }
 
-    fn weights_f32<S: Source>(&'a self, source: &'a S) -> Option<MorphWeightsF32<'a>> {
+    fn weights_f32<S: Source>(&'a self, source: &'a S) -> Result<Option<MorphWeightsF32<'a>>> {
         match self.target().path() {
             gltf::animation::TrsProperty::Weights => {
-                Some(MorphWeightsF32(MorphWeights::new(self.sampler().output(), source)))
+                Ok(Some(MorphWeightsF32(MorphWeights::new(self.sampler().output(), source)?)))
             },
-            _ => None,
+            _ => Ok(None),
         }
     }
 }
@@ -216,26 +679,26 @@ enum Rotations<'a> {
 }
 
 impl<'a> Rotations<'a> {
-    fn new<S: Source>(accessor: gltf::Accessor<'a>, source: &'a S) -> Rotations<'a> {
+    fn new<S: Source>(accessor: gltf::Accessor<'a>, source: &'a S) -> Result<Rotations<'a>> {
         match accessor.dimensions() {
             Dimensions::Vec4 => {
                 match accessor.data_type() {
                     DataType::F32 => {
-                        Rotations::F32(AccessorIter::new(accessor, source))
+                        Ok(Rotations::F32(AccessorIter::new(accessor, source)?))
                     },
                     DataType::U8 => {
-                        Rotations::U8(AccessorIter::new(accessor, source))
+                        Ok(Rotations::U8(AccessorIter::new(accessor, source)?))
                     },
                     DataType::I16 => {
-                        Rotations::I16(AccessorIter::new(accessor, source))
+                        Ok(Rotations::I16(AccessorIter::new(accessor, source)?))
                     },
                     DataType::U16 => {
-                        Rotations::U16(AccessorIter::new(accessor, source))
+                        Ok(Rotations::U16(AccessorIter::new(accessor, source)?))
                     },
-                    _ => unimplemented!(),
+                    _ => Err(Error::Convert(ConvertError::InvalidAccessor { accessor: accessor.index() })),
                 }
             },
-            _ => unimplemented!(),
+            _ => Err(Error::Convert(ConvertError::InvalidAccessor { accessor: accessor.index() })),
         }
     }
 }
@@ -250,26 +713,233 @@ enum MorphWeights<'a> {
 }
 
 impl<'a> MorphWeights<'a> {
-    fn new<S: Source>(accessor: gltf::Accessor<'a>, source: &'a S) -> MorphWeights<'a> {
+    fn new<S: Source>(accessor: gltf::Accessor<'a>, source: &'a S) -> Result<MorphWeights<'a>> {
         match accessor.dimensions() {
             Dimensions::Scalar => {
                 match accessor.data_type() {
                     DataType::F32 => {
-                        MorphWeights::F32(AccessorIter::new(accessor, source))
+                        Ok(MorphWeights::F32(AccessorIter::new(accessor, source)?))
                     },
                     DataType::U8 => {
-                        MorphWeights::U8(AccessorIter::new(accessor, source))
+                        Ok(MorphWeights::U8(AccessorIter::new(accessor, source)?))
                     },
                     DataType::I16 => {
-                        MorphWeights::I16(AccessorIter::new(accessor, source))
+                        Ok(MorphWeights::I16(AccessorIter::new(accessor, source)?))
                     },
                     DataType::U16 => {
-                        MorphWeights::U16(AccessorIter::new(accessor, source))
+                        Ok(MorphWeights::U16(AccessorIter::new(accessor, source)?))
                     },
-                    _ => unimplemented!(),
+                    _ => Err(Error::Convert(ConvertError::InvalidAccessor { accessor: accessor.index() })),
                 }
             },
-            _ => unimplemented!(),
+            _ => Err(Error::Convert(ConvertError::InvalidAccessor { accessor: accessor.index() })),
+        }
+    }
+}
+
+/// Decodes a base64 `data:` URI into its media type and bytes.
+pub fn decode_data_uri<'a>(uri: &'a str) -> Result<(&'a str, Vec<u8>)> {
+    let invalid = || Error::Convert(ConvertError::InvalidDataUri);
+
+    if !uri.starts_with("data:") {
+        return Err(invalid());
+    }
+    let mut parts = uri["data:".len()..].splitn(2, ',');
+    let header = parts.next().ok_or_else(&invalid)?;
+    let encoded = parts.next().ok_or_else(&invalid)?;
+    if !header.ends_with(";base64") {
+        return Err(invalid());
+    }
+    let media_type = &header[.. header.len() - ";base64".len()];
+    let data = base64::decode(encoded).map_err(|_| invalid())?;
+
+    Ok((media_type, data))
+}
+
+/// Hashes a name into a compact ID with 64 bit FNV-1a, which is the same
+/// on every platform and in every run.
+pub fn name_id(name: &str) -> u64 {
+    name.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// Resolves a relative resource `uri` against `base_path`.
+///
+/// Absolute paths and paths that climb out of `base_path` are rejected so a
+/// glTF file can only read resources stored beside it.
+pub fn resolve_uri<'a>(
+    base_path: &'a Path,
+    uri: &'a str,
+) -> Result<PathBuf> {
+    let unsafe_path = || Error::Convert(ConvertError::UnsafeResourcePath {
+        uri: uri.to_owned(),
+    });
+    let mut depth = 0_usize;
+
+    for component in Path::new(uri).components() {
+        match component {
+            Component::Normal(_) => { depth += 1; },
+            Component::CurDir => {},
+            Component::ParentDir => {
+                depth = depth.checked_sub(1).ok_or_else(&unsafe_path)?;
+            },
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(unsafe_path());
+            },
+        }
+    }
+
+    Ok(base_path.join(uri))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use gltf::Gltf;
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct TestSource(Vec<u8>);
+
+    impl Source for TestSource {
+        fn source_buffer(&self, _: &gltf::Buffer) -> &[u8] {
+            &self.0
+        }
+    }
+
+    fn get_positions(count: usize, stride: usize) -> (Gltf, TestSource) {
+        let json = format!(r#"{{
+            "asset": {{ "version": "2.0" }},
+            "buffers": [ {{ "byteLength": {length} }} ],
+            "bufferViews": [ {{ "buffer": 0, "byteLength": {length}, "byteStride": {stride} }} ],
+            "accessors": [ {{
+                "bufferView": 0,
+                "componentType": 5126,
+                "count": {count},
+                "type": "VEC3"
+            }} ]
+        }}"#, length = count * stride, stride = stride, count = count);
+        let gltf = Gltf::from_str(&json).unwrap().validate_minimally().unwrap();
+        let data = (0..count * stride).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+
+        (gltf, TestSource(data))
+    }
+
+    #[test]
+    fn test_read_all() {
+        for &stride in [12, 16].iter() {
+            let (gltf, source) = get_positions(1000, stride);
+            let accessor = gltf.accessors().next().unwrap();
+            let scalar = AccessorIter::<[f32; 3]>::new(accessor.clone(), &source).unwrap().collect::<Vec<_>>();
+            let bulk = AccessorIter::<[f32; 3]>::new(accessor, &source).unwrap().read_all();
+
+            assert_eq!(
+                bulk.iter().map(|v| [v[0].to_bits(), v[1].to_bits(), v[2].to_bits()]).collect::<Vec<_>>(),
+                scalar.iter().map(|v| [v[0].to_bits(), v[1].to_bits(), v[2].to_bits()]).collect::<Vec<_>>(),
+            );
+        }
+    }
+
+    /// Run with `cargo test --release -- --ignored bench_read_all --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_read_all() {
+        let (gltf, source) = get_positions(1_000_000, 12);
+        let accessor = gltf.accessors().next().unwrap();
+
+        let scalar_time = (0..10).map(|_| {
+            let start = Instant::now();
+            let scalar = AccessorIter::<[f32; 3]>::new(accessor.clone(), &source).unwrap().collect::<Vec<_>>();
+            assert_eq!(scalar.len(), 1_000_000);
+            start.elapsed()
+        }).min().unwrap();
+
+        let bulk_time = (0..10).map(|_| {
+            let start = Instant::now();
+            let bulk = AccessorIter::<[f32; 3]>::new(accessor.clone(), &source).unwrap().read_all();
+            assert_eq!(bulk.len(), 1_000_000);
+            start.elapsed()
+        }).min().unwrap();
+
+        println!("scalar: {:?}, bulk: {:?}", scalar_time, bulk_time);
+        assert!(bulk_time < scalar_time);
+    }
+
+    #[test]
+    fn test_resolve_uri() {
+        let base_path = Path::new("models");
+
+        assert_eq!(
+            resolve_uri(base_path, "textures/../albedo.png").unwrap(),
+            base_path.join("textures/../albedo.png"),
+        );
+        assert!(resolve_uri(base_path, "../albedo.png").is_err());
+        assert!(resolve_uri(base_path, "textures/../../albedo.png").is_err());
+        assert!(resolve_uri(base_path, "/etc/passwd").is_err());
+    }
+
+    #[test]
+    fn test_name_id() {
+        assert_eq!(name_id("hips"), name_id(&String::from("hips")));
+        assert!(name_id("hips") != name_id("spine"));
+
+        // Published FNV-1a test vectors.
+        assert_eq!(name_id(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(name_id("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(name_id("foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn test_accessor_iter() {
+        use byteorder::{LE, WriteBytesExt};
+        use gltf::Gltf;
+
+        #[derive(Debug)]
+        struct Bytes(Vec<u8>);
+
+        impl Source for Bytes {
+            fn source_buffer(&self, _: &gltf::Buffer) -> &[u8] {
+                &self.0
+            }
+        }
+
+        // Two floats, each padded to eight bytes, starting one byte into the
+        // buffer so neither is aligned.
+        let json = r#"{
+            "asset": { "version": "2.0" },
+            "buffers": [ { "byteLength": 17 } ],
+            "bufferViews": [ { "buffer": 0, "byteOffset": 1, "byteLength": 16, "byteStride": 8 } ],
+            "accessors": [ { "bufferView": 0, "componentType": 5126, "count": 2, "type": "SCALAR" } ]
+        }"#;
+        let gltf = Gltf::from_str(json).unwrap().validate_minimally().unwrap();
+        let mut data = vec![0];
+        for &value in &[1.5, 0.0, -2.5, 0.0] {
+            data.write_f32::<LE>(value).unwrap();
+        }
+        let bytes = Bytes(data);
+
+        let items = AccessorIter::<f32>::new(gltf.accessors().next().unwrap(), &bytes).unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items.collect::<Vec<_>>(), vec![1.5, -2.5]);
+
+        // Reading the floats as vectors is an error rather than a panic.
+        match AccessorIter::<[f32; 3]>::new(gltf.accessors().next().unwrap(), &bytes) {
+            Err(Error::Convert(ConvertError::InvalidAccessor { accessor })) => assert_eq!(accessor, 0),
+            _ => panic!("expected InvalidAccessor"),
+        }
+
+        // A third float would run past the end of the view.
+        let json = json.replace(r#""count": 2"#, r#""count": 3"#);
+        let gltf = Gltf::from_str(&json).unwrap().validate_minimally().unwrap();
+        match AccessorIter::<f32>::new(gltf.accessors().next().unwrap(), &bytes) {
+            Err(Error::Convert(ConvertError::AccessorOverrun { accessor })) => assert_eq!(accessor, 0),
+            _ => panic!("expected AccessorOverrun"),
         }
     }
 }
@@ -0,0 +1,240 @@
+use std::collections::VecDeque;
+use std::u32;
+
+/// Size of the LRU cache the optimizer models.
+const CACHE_SIZE: usize = 32;
+const CACHE_DECAY_POWER: f32 = 1.5;
+const LAST_TRIANGLE_SCORE: f32 = 0.75;
+const VALENCE_BOOST_SCALE: f32 = 2.0;
+const VALENCE_BOOST_POWER: f32 = 0.5;
+
+/// Reorders the triangles of a triangle list for the post-transform vertex
+/// cache, using Tom Forsyth's linear-speed algorithm. Winding is preserved.
+/// Every index must be less than `vertex_count`.
+pub fn optimize(indices: &[u32], vertex_count: usize) -> Vec<u32> {
+    let triangle_count = indices.len() / 3;
+
+    // Triangles using each vertex.
+    let mut vertex_triangles = vec![Vec::<usize>::new(); vertex_count];
+    for triangle in 0..triangle_count {
+        for &index in &indices[triangle * 3 .. triangle * 3 + 3] {
+            vertex_triangles[index as usize].push(triangle);
+        }
+    }
+
+    let mut remaining = vertex_triangles.iter().map(Vec::len).collect::<Vec<_>>();
+    let mut cache_positions = vec![None; vertex_count];
+    let mut vertex_scores = remaining.iter()
+        .map(|&valence| vertex_score(None, valence))
+        .collect::<Vec<_>>();
+    let mut triangle_scores = (0..triangle_count).map(|triangle| {
+        indices[triangle * 3 .. triangle * 3 + 3].iter()
+            .map(|&index| vertex_scores[index as usize])
+            .sum::<f32>()
+    }).collect::<Vec<_>>();
+    let mut added = vec![false; triangle_count];
+
+    let mut cache = VecDeque::<u32>::with_capacity(CACHE_SIZE + 3);
+    let mut optimized = Vec::<u32>::with_capacity(triangle_count * 3);
+    // Where to resume scanning when no cached vertex has triangles left.
+    let mut next_unadded = 0;
+    let mut best = best_triangle(&triangle_scores, &added, 0);
+
+    while let Some(triangle) = best {
+        let corners = [
+            indices[triangle * 3],
+            indices[triangle * 3 + 1],
+            indices[triangle * 3 + 2],
+        ];
+        optimized.extend_from_slice(&corners);
+        added[triangle] = true;
+
+        // Move the triangle's vertices to the front of the cache.
+        for &index in corners.iter().rev() {
+            if let Some(position) = cache.iter().position(|&cached| cached == index) {
+                cache.remove(position);
+            }
+            cache.push_front(index);
+
+            let vertex = index as usize;
+            remaining[vertex] -= 1;
+            let position = vertex_triangles[vertex].iter()
+                .position(|&other| other == triangle)
+                .unwrap();
+            vertex_triangles[vertex].swap_remove(position);
+        }
+
+        // Vertices pushed past the end drop out of the cache.
+        let mut touched = cache.iter().cloned().collect::<Vec<_>>();
+        while cache.len() > CACHE_SIZE {
+            let evicted = cache.pop_back().unwrap();
+            cache_positions[evicted as usize] = None;
+        }
+        for (position, &index) in cache.iter().enumerate() {
+            cache_positions[index as usize] = Some(position);
+        }
+
+        // Rescore the affected vertices and their triangles.
+        touched.sort();
+        touched.dedup();
+        for &index in &touched {
+            let vertex = index as usize;
+            let score = vertex_score(cache_positions[vertex], remaining[vertex]);
+            let delta = score - vertex_scores[vertex];
+            vertex_scores[vertex] = score;
+            for &other in &vertex_triangles[vertex] {
+                triangle_scores[other] += delta;
+            }
+        }
+
+        // Prefer triangles sharing vertices with the cache.
+        best = None;
+        let mut best_score = -1.0;
+        for &index in &cache {
+            for &other in &vertex_triangles[index as usize] {
+                if triangle_scores[other] > best_score {
+                    best = Some(other);
+                    best_score = triangle_scores[other];
+                }
+            }
+        }
+        if best.is_none() {
+            while next_unadded < triangle_count && added[next_unadded] {
+                next_unadded += 1;
+            }
+            best = best_triangle(&triangle_scores, &added, next_unadded);
+        }
+    }
+
+    optimized
+}
+
+/// Renumbers vertices in the order `indices` first uses them. Returns the
+/// old index of every new vertex; unused vertices come last.
+pub fn remap_vertices(indices: &mut [u32], vertex_count: usize) -> Vec<u32> {
+    let mut new_indices = vec![u32::MAX; vertex_count];
+    let mut order = Vec::<u32>::with_capacity(vertex_count);
+
+    for index in indices.iter_mut() {
+        let old = *index as usize;
+        if new_indices[old] == u32::MAX {
+            new_indices[old] = order.len() as u32;
+            order.push(*index);
+        }
+        *index = new_indices[old];
+    }
+    for old in 0..vertex_count {
+        if new_indices[old] == u32::MAX {
+            order.push(old as u32);
+        }
+    }
+
+    order
+}
+
+fn vertex_score(cache_position: Option<usize>, remaining: usize) -> f32 {
+    if remaining == 0 {
+        return -1.0;
+    }
+
+    let cache_score = match cache_position {
+        Some(position) if position < 3 => LAST_TRIANGLE_SCORE,
+        Some(position) if position < CACHE_SIZE => {
+            let scale = 1.0 / (CACHE_SIZE - 3) as f32;
+            (1.0 - (position - 3) as f32 * scale).powf(CACHE_DECAY_POWER)
+        },
+        _ => 0.0,
+    };
+    let valence_score = VALENCE_BOOST_SCALE * (remaining as f32).powf(-VALENCE_BOOST_POWER);
+
+    cache_score + valence_score
+}
+
+fn best_triangle(scores: &[f32], added: &[bool], start: usize) -> Option<usize> {
+    let mut best = None;
+    let mut best_score = -1.0;
+
+    for triangle in start..scores.len() {
+        if !added[triangle] && scores[triangle] > best_score {
+            best = Some(triangle);
+            best_score = scores[triangle];
+        }
+    }
+
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Triangle with its smallest index first, keeping the winding.
+    fn canonical(triangle: [u32; 3]) -> [u32; 3] {
+        let first = (0..3).min_by_key(|&i| triangle[i]).unwrap();
+        [triangle[first], triangle[(first + 1) % 3], triangle[(first + 2) % 3]]
+    }
+
+    fn triangle_set(indices: &[u32], order: &[u32]) -> Vec<[u32; 3]> {
+        let mut triangles = indices.chunks(3)
+            .map(|triangle| canonical([
+                order[triangle[0] as usize],
+                order[triangle[1] as usize],
+                order[triangle[2] as usize],
+            ]))
+            .collect::<Vec<_>>();
+        triangles.sort();
+        triangles
+    }
+
+    /// Average number of vertices transformed per triangle with a FIFO cache
+    /// of `cache_size` vertices. Lower is better.
+    fn acmr(indices: &[u32], cache_size: usize) -> f32 {
+        let triangle_count = indices.len() / 3;
+        if triangle_count == 0 {
+            return 0.0;
+        }
+
+        let mut cache = VecDeque::<u32>::with_capacity(cache_size);
+        let mut misses = 0;
+        for &index in indices {
+            if !cache.contains(&index) {
+                misses += 1;
+                if cache.len() == cache_size {
+                    cache.pop_front();
+                }
+                cache.push_back(index);
+            }
+        }
+
+        misses as f32 / triangle_count as f32
+    }
+
+    #[test]
+    fn test_optimize() {
+        // A 16 by 16 grid of quads with its triangles shuffled.
+        let size = 16;
+        let mut triangles = Vec::<[u32; 3]>::new();
+        for y in 0..size {
+            for x in 0..size {
+                let corner = y * (size + 1) + x;
+                triangles.push([corner, corner + 1, corner + size + 2]);
+                triangles.push([corner, corner + size + 2, corner + size + 1]);
+            }
+        }
+        let mut seed = 12345u32;
+        for i in (1..triangles.len()).rev() {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            triangles.swap(i, (seed >> 16) as usize % (i + 1));
+        }
+        let indices = triangles.iter().flat_map(|triangle| triangle.iter().cloned()).collect::<Vec<_>>();
+        let vertex_count = ((size + 1) * (size + 1)) as usize;
+
+        let mut optimized = optimize(&indices, vertex_count);
+        let order = remap_vertices(&mut optimized, vertex_count);
+        let identity = (0..vertex_count as u32).collect::<Vec<_>>();
+
+        assert_eq!(triangle_set(&optimized, &order), triangle_set(&indices, &identity));
+        assert!(acmr(&optimized, 16) <= acmr(&indices, 16));
+        assert!(acmr(&optimized, 16) < 1.0);
+    }
+}
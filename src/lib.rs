@@ -1,12 +1,18 @@
+extern crate base64;
 extern crate bincode;
 extern crate byteorder;
 extern crate cgmath;
+#[cfg(feature = "compression")]
+extern crate deflate;
 extern crate float_cmp;
 extern crate gltf;
 extern crate gltf_importer;
 extern crate gltf_utils;
 extern crate image;
+#[cfg(feature = "compression")]
+extern crate inflate;
 extern crate itertools;
+extern crate png;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
@@ -22,6 +28,7 @@ pub mod convert;
 #[derive(Debug)]
 pub enum Error {
     Io(io::Error),
+    Bincode(bincode::Error),
     Gltf(gltf::Error),
     GltfImport(gltf_importer::Error),
     Image(image::ImageError),
@@ -32,6 +39,7 @@ impl fmt::Display for Error {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         match self {
             &Error::Io(ref err) => err.fmt(fmt),
+            &Error::Bincode(ref err) => err.fmt(fmt),
             &Error::Gltf(ref err) => err.fmt(fmt),
             &Error::GltfImport(ref err) => err.fmt(fmt),
             &Error::Image(ref err) => err.fmt(fmt),
@@ -44,6 +52,7 @@ impl error::Error for Error {
     fn description(&self) -> &str {
         match self {
             &Error::Io(ref err) => err.description(),
+            &Error::Bincode(ref err) => err.description(),
             &Error::Gltf(ref err) => err.description(),
             &Error::GltfImport(ref err) => err.description(),
             &Error::Image(ref err) => err.description(),
@@ -54,6 +63,7 @@ impl error::Error for Error {
     fn cause(&self) -> Option<&error::Error> {
         match self {
             &Error::Io(ref err) => err.cause(),
+            &Error::Bincode(ref err) => err.cause(),
             &Error::Gltf(ref err) => err.cause(),
             &Error::GltfImport(ref err) => err.cause(),
             &Error::Image(ref err) => err.cause(),
@@ -68,6 +78,12 @@ impl From<io::Error> for Error {
     }
 }
 
+impl From<bincode::Error> for Error {
+    fn from(err: bincode::Error) -> Error {
+        Error::Bincode(err)
+    }
+}
+
 impl From<gltf::Error> for Error {
     fn from(err: gltf::Error) -> Error {
         Error::Gltf(err)